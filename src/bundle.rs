@@ -0,0 +1,162 @@
+//! Tar archive of a Route with everything attached to it, for moving it
+//! between config hosts.
+//!
+//! The archive holds `route.json`, `euis.csv` as read by `euis sync`,
+//! `devaddrs.csv` with `start_addr,end_addr` rows, and `skfs.json`, the
+//! Session Key Filters of the Org inside the Route's Devaddr Ranges as
+//! written by `skf export`.
+use crate::{
+    route::{Route, RouteId},
+    DevaddrRange, Eui, PrettyJson, Result, SessionKeyFilter, SessionKeyFilterExport,
+};
+use anyhow::{anyhow, Context};
+use std::{collections::HashMap, io::Read};
+
+const ROUTE_FILE: &str = "route.json";
+const EUIS_FILE: &str = "euis.csv";
+const DEVADDRS_FILE: &str = "devaddrs.csv";
+const SKFS_FILE: &str = "skfs.json";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RouteBundle {
+    pub route: Route,
+    pub euis: Vec<Eui>,
+    pub devaddrs: Vec<DevaddrRange>,
+    pub filters: Vec<SessionKeyFilter>,
+}
+
+impl RouteBundle {
+    /// Only the `filters` with a devaddr in one of `devaddrs` are kept.
+    pub fn new(
+        route: Route,
+        euis: Vec<Eui>,
+        devaddrs: Vec<DevaddrRange>,
+        filters: Vec<SessionKeyFilter>,
+    ) -> Self {
+        let filters = filters
+            .into_iter()
+            .filter(|filter| devaddrs.iter().any(|range| range.contains(filter.devaddr)))
+            .collect();
+        Self {
+            route,
+            euis,
+            devaddrs,
+            filters,
+        }
+    }
+
+    pub fn to_tar(&self) -> Result<Vec<u8>> {
+        let euis = std::iter::once("app_eui,dev_eui".to_string())
+            .chain(
+                self.euis
+                    .iter()
+                    .map(|eui| format!("{},{}", eui.app_eui, eui.dev_eui)),
+            )
+            .collect::<Vec<_>>()
+            .join("\n");
+        let devaddrs = std::iter::once("start_addr,end_addr".to_string())
+            .chain(
+                self.devaddrs
+                    .iter()
+                    .map(|range| format!("{},{}", range.start_addr, range.end_addr)),
+            )
+            .collect::<Vec<_>>()
+            .join("\n");
+        let skfs = SessionKeyFilterExport::new(self.route.oui, self.filters.clone());
+
+        let mut builder = tar::Builder::new(vec![]);
+        for (name, data) in [
+            (ROUTE_FILE, self.route.pretty_json()?),
+            (EUIS_FILE, euis),
+            (DEVADDRS_FILE, devaddrs),
+            (SKFS_FILE, skfs.pretty_json()?),
+        ] {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            builder
+                .append_data(&mut header, name, data.as_bytes())
+                .with_context(|| format!("adding {name}"))?;
+        }
+        Ok(builder.into_inner()?)
+    }
+
+    /// The EUI pairs and Devaddr Ranges are read for the id in `route.json`.
+    pub fn from_tar(bytes: &[u8]) -> Result<Self> {
+        let mut files = HashMap::new();
+        for entry in tar::Archive::new(bytes).entries()? {
+            let mut entry = entry?;
+            let name = entry.path()?.to_string_lossy().into_owned();
+            let mut data = String::new();
+            entry
+                .read_to_string(&mut data)
+                .with_context(|| format!("reading {name}"))?;
+            files.insert(name, data);
+        }
+        let mut file = |name: &str| {
+            files
+                .remove(name)
+                .ok_or_else(|| anyhow!("{name} is missing from the bundle"))
+        };
+
+        let route: Route = serde_json::from_str(&file(ROUTE_FILE)?)
+            .with_context(|| format!("parsing {ROUTE_FILE}"))?;
+        let euis = Eui::from_csv(&route.id, &file(EUIS_FILE)?)
+            .with_context(|| format!("parsing {EUIS_FILE}"))?;
+        let devaddrs = DevaddrRange::from_csv(&route.id, &file(DEVADDRS_FILE)?)
+            .with_context(|| format!("parsing {DEVADDRS_FILE}"))?;
+        let filters = SessionKeyFilterExport::from_json(&file(SKFS_FILE)?)
+            .with_context(|| format!("parsing {SKFS_FILE}"))?
+            .into_filters();
+
+        Ok(Self {
+            route,
+            euis,
+            devaddrs,
+            filters,
+        })
+    }
+
+    /// The EUI pairs and Devaddr Ranges moved to `route_id`, the id of the
+    /// Route once it is created on another config host.
+    pub fn rebind(&mut self, route_id: &RouteId) {
+        for eui in &mut self.euis {
+            eui.route_id = route_id.clone();
+        }
+        for range in &mut self.devaddrs {
+            range.route_id = route_id.clone();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RouteBundle;
+    use crate::{hex_field, route::RouteId, DevaddrRange, Eui, Route, SessionKeyFilter};
+
+    #[test]
+    fn bundle_tar() {
+        let route_id = RouteId::new_unchecked("route-one");
+        let mut route = Route::new(hex_field::net_id(0xC00053), 4, 2);
+        route.id = route_id.clone();
+        let bundle = RouteBundle::new(
+            route,
+            vec![Eui::new(route_id.clone(), hex_field::eui(1), hex_field::eui(2)).unwrap()],
+            vec![DevaddrRange::new(
+                route_id,
+                hex_field::devaddr(0x48000000),
+                hex_field::devaddr(0x48000003),
+            )
+            .unwrap()],
+            vec![
+                SessionKeyFilter::new(4, hex_field::devaddr(0x48000001), "inside".to_string()),
+                SessionKeyFilter::new(4, hex_field::devaddr(0x48000010), "outside".to_string()),
+            ],
+        );
+        assert_eq!(1, bundle.filters.len());
+
+        let tar = bundle.to_tar().unwrap();
+        assert_eq!(bundle, RouteBundle::from_tar(&tar).unwrap());
+        assert!(RouteBundle::from_tar(&tar[..512]).is_err());
+    }
+}
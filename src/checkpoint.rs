@@ -0,0 +1,117 @@
+//! Progress of a bulk import, so an interrupted one can be resumed.
+//!
+//! The checkpoint of `euis.csv` is `euis.csv.checkpoint`, next to it. It
+//! records how many entries of the file, in file order, the config service
+//! has confirmed, and is removed once the import completes.
+use crate::Result;
+use anyhow::{anyhow, Context};
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::Display,
+    fs,
+    path::{Path, PathBuf},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Checkpoint {
+    path: PathBuf,
+    target: String,
+    total: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Record {
+    target: String,
+    total: usize,
+    confirmed: usize,
+}
+
+impl Checkpoint {
+    /// Checkpoint of importing the `total` entries of `input` into `target`,
+    /// like a route id.
+    pub fn new(input: &Path, target: impl Display, total: usize) -> Self {
+        let mut name = input.file_name().unwrap_or_default().to_os_string();
+        name.push(".checkpoint");
+        Self {
+            path: input.with_file_name(name),
+            target: target.to_string(),
+            total,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Entries confirmed by an earlier run, 0 without a checkpoint. A
+    /// checkpoint of another target or a file that changed length is an
+    /// error.
+    pub fn confirmed(&self) -> Result<usize> {
+        if !self.path.exists() {
+            return Ok(0);
+        }
+        let json = fs::read_to_string(&self.path)
+            .with_context(|| format!("reading {}", self.path.display()))?;
+        let record: Record = serde_json::from_str(&json)
+            .with_context(|| format!("parsing {}", self.path.display()))?;
+        if record.target != self.target || record.total != self.total {
+            return Err(anyhow!(
+                "{} is for {} entries into {}, not {} into {}, remove it to start over",
+                self.path.display(),
+                record.total,
+                record.target,
+                self.total,
+                self.target
+            ));
+        }
+        Ok(record.confirmed.min(self.total))
+    }
+
+    pub fn save(&self, confirmed: usize) -> Result {
+        let record = Record {
+            target: self.target.clone(),
+            total: self.total,
+            confirmed,
+        };
+        fs::write(&self.path, serde_json::to_string(&record)?)
+            .with_context(|| format!("writing {}", self.path.display()))
+    }
+
+    /// Remove the checkpoint of a completed import.
+    pub fn finish(&self) -> Result {
+        match fs::remove_file(&self.path) {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+                Err(anyhow::Error::from(err).context(format!("removing {}", self.path.display())))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Checkpoint;
+    use temp_dir::TempDir;
+
+    #[test]
+    fn import_checkpoint() {
+        let dir = TempDir::new().unwrap();
+        let input = dir.child("euis.csv");
+        let checkpoint = Checkpoint::new(&input, "route-one", 10);
+        assert_eq!(dir.child("euis.csv.checkpoint"), checkpoint.path());
+        assert_eq!(0, checkpoint.confirmed().unwrap());
+
+        checkpoint.save(4).unwrap();
+        assert_eq!(4, checkpoint.confirmed().unwrap());
+        assert!(Checkpoint::new(&input, "route-two", 10)
+            .confirmed()
+            .is_err());
+        assert!(Checkpoint::new(&input, "route-one", 11)
+            .confirmed()
+            .is_err());
+
+        checkpoint.finish().unwrap();
+        assert!(!checkpoint.path().exists());
+        checkpoint.finish().unwrap();
+    }
+}
@@ -0,0 +1,295 @@
+//! Devices from a ChirpStack device export, as EUI pairs and Session Key
+//! Filters, and EUI pairs as devices for ChirpStack to import.
+//!
+//! Column and field names are matched ignoring case, `_`, `-` and spaces, so
+//! `dev_eui`, `devEui` and `DevEUI` are the same. Nested JSON objects, like
+//! the `activation` of a device, are flattened. Exports with other names
+//! for the columns are read with [`Columns`].
+use crate::{
+    collect_all,
+    hex_field::{self, HexDevAddr, HexEui},
+    route::RouteId,
+    Eui, Oui, Result, SessionKeyFilter,
+};
+use anyhow::anyhow;
+use serde::Serialize;
+use std::collections::HashMap;
+
+const DEV_EUI: &[&str] = &["deveui"];
+const JOIN_EUI: &[&str] = &["joineui", "appeui"];
+const DEV_ADDR: &[&str] = &["devaddr"];
+const NWK_S_KEY: &[&str] = &["nwkskey", "nwksenckey"];
+
+/// Column names to read instead of the ChirpStack ones, matched the same way.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Columns {
+    pub dev_eui: Option<String>,
+    pub join_eui: Option<String>,
+    pub devaddr: Option<String>,
+    pub session_key: Option<String>,
+}
+
+impl Columns {
+    fn names(&self) -> [(&'static str, Option<&String>); 4] {
+        [
+            ("dev_eui", self.dev_eui.as_ref()),
+            ("join_eui", self.join_eui.as_ref()),
+            ("devaddr", self.devaddr.as_ref()),
+            ("session_key", self.session_key.as_ref()),
+        ]
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Device {
+    pub dev_eui: HexEui,
+    pub join_eui: HexEui,
+    /// The devaddr and network session key of an activated device.
+    pub session: Option<(HexDevAddr, String)>,
+}
+
+impl Device {
+    pub fn eui(&self, route_id: &RouteId) -> Result<Eui> {
+        Eui::new(route_id.clone(), self.join_eui, self.dev_eui)
+    }
+
+    pub fn filter(&self, oui: Oui) -> Option<SessionKeyFilter> {
+        self.session
+            .as_ref()
+            .map(|(devaddr, key)| SessionKeyFilter::new(oui, *devaddr, key.clone()))
+    }
+}
+
+/// Columns of [`ExportedDevice::csv_row`].
+pub const CSV_HEADER: &str = "dev_eui,join_eui,name,application_id,device_profile_id";
+
+/// An EUI pair as a ChirpStack device, named after its DevEUI. ChirpStack
+/// needs the application and device profile to create it in.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportedDevice {
+    pub dev_eui: HexEui,
+    pub join_eui: HexEui,
+    pub name: String,
+    pub application_id: String,
+    pub device_profile_id: String,
+}
+
+impl ExportedDevice {
+    pub fn new(eui: &Eui, application_id: &str, device_profile_id: &str) -> Self {
+        Self {
+            dev_eui: eui.dev_eui,
+            join_eui: eui.app_eui,
+            name: eui.dev_eui.to_string(),
+            application_id: application_id.to_string(),
+            device_profile_id: device_profile_id.to_string(),
+        }
+    }
+
+    pub fn csv_row(&self) -> String {
+        format!(
+            "{},{},{},{},{}",
+            self.dev_eui, self.join_eui, self.name, self.application_id, self.device_profile_id
+        )
+    }
+}
+
+/// A header row, then one device per row. Quoted cells cannot hold commas.
+/// A column named in `names` must be in the header.
+pub fn from_csv(csv: &str, names: &Columns) -> Result<Vec<Device>> {
+    let mut lines = csv
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty());
+    let Some((_, header)) = lines.next() else {
+        return Ok(vec![]);
+    };
+    let columns: Vec<String> = header.split(',').map(normalize).collect();
+    for (field, name) in names.names() {
+        if let Some(name) = name.filter(|name| !columns.contains(&normalize(name))) {
+            return Err(anyhow!("no column {name} for {field} in the header"));
+        }
+    }
+
+    collect_all(lines.map(|(number, line)| {
+        let row = columns
+            .iter()
+            .cloned()
+            .zip(
+                line.split(',')
+                    .map(|cell| cell.trim().trim_matches('"').to_string()),
+            )
+            .collect();
+        device(&row, names).map_err(|err| anyhow!("line {number}: {err}"))
+    }))
+}
+
+/// A list of device objects.
+pub fn from_json(json: &str, names: &Columns) -> Result<Vec<Device>> {
+    let devices: Vec<serde_json::Value> = serde_json::from_str(json)?;
+    collect_all(devices.iter().enumerate().map(|(index, value)| {
+        let mut row = HashMap::new();
+        flatten(value, &mut row);
+        device(&row, names).map_err(|err| anyhow!("device {index}: {err}"))
+    }))
+}
+
+fn flatten(value: &serde_json::Value, row: &mut HashMap<String, String>) {
+    let serde_json::Value::Object(fields) = value else {
+        return;
+    };
+    for (name, value) in fields {
+        match value {
+            serde_json::Value::Object(_) => flatten(value, row),
+            serde_json::Value::String(value) => {
+                row.insert(normalize(name), value.clone());
+            }
+            _ => (),
+        }
+    }
+}
+
+fn normalize(name: &str) -> String {
+    name.trim()
+        .trim_matches('"')
+        .chars()
+        .filter(|c| !matches!(c, '_' | '-' | ' '))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+fn device(row: &HashMap<String, String>, names: &Columns) -> Result<Device> {
+    let field = |name: &Option<String>, defaults: &[&str]| {
+        match name {
+            Some(name) => row.get(&normalize(name)),
+            None => defaults.iter().find_map(|name| row.get(*name)),
+        }
+        .filter(|value| !value.is_empty())
+    };
+    let dev_eui = field(&names.dev_eui, DEV_EUI).ok_or_else(|| anyhow!("missing dev_eui"))?;
+    let join_eui = field(&names.join_eui, JOIN_EUI).ok_or_else(|| anyhow!("missing join_eui"))?;
+
+    let session = match (
+        field(&names.devaddr, DEV_ADDR),
+        field(&names.session_key, NWK_S_KEY),
+    ) {
+        (Some(devaddr), Some(key)) => Some((
+            hex_field::validate_devaddr(devaddr)?,
+            hex_field::validate_session_key(key)?.to_lowercase(),
+        )),
+        _ => None,
+    };
+
+    Ok(Device {
+        dev_eui: hex_field::validate_eui(dev_eui)?,
+        join_eui: hex_field::validate_eui(join_eui)?,
+        session,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{from_csv, from_json, Columns, Device, ExportedDevice, CSV_HEADER};
+    use crate::{hex_field, route::RouteId, Eui};
+
+    const KEY: &str = "00112233445566778899aabbccddeeff";
+
+    #[test]
+    fn chirpstack_csv() {
+        let csv = format!(
+            "dev_eui,join_eui,name,dev_addr,nwk_s_enc_key\n\
+             0000000000000001,0000000000000002,one,48000001,{}\n\
+             \"0000000000000003\",\"0000000000000004\",two,,\n",
+            KEY.to_uppercase()
+        );
+        assert_eq!(
+            vec![
+                Device {
+                    dev_eui: hex_field::eui(1),
+                    join_eui: hex_field::eui(2),
+                    session: Some((hex_field::devaddr(0x48000001), KEY.to_string())),
+                },
+                Device {
+                    dev_eui: hex_field::eui(3),
+                    join_eui: hex_field::eui(4),
+                    session: None,
+                },
+            ],
+            from_csv(&csv, &Columns::default()).unwrap()
+        );
+
+        let err =
+            from_csv("dev_eui,join_eui\n0000000000000001\n", &Columns::default()).unwrap_err();
+        assert_eq!(
+            "invalid entries: 1 of 1\nline 2: missing join_eui",
+            err.to_string()
+        );
+    }
+
+    #[test]
+    fn chirpstack_json() {
+        let json = format!(
+            r#"[
+                {{
+                    "device": {{"devEui": "0000000000000001", "joinEui": "0000000000000002"}},
+                    "activation": {{"devAddr": "48000001", "nwkSEncKey": "{KEY}"}}
+                }},
+                {{"DevEUI": "0000000000000003", "AppEUI": "0000000000000004", "skipFCntCheck": true}}
+            ]"#
+        );
+        let devices = from_json(&json, &Columns::default()).unwrap();
+        assert_eq!(
+            Some((hex_field::devaddr(0x48000001), KEY.to_string())),
+            devices[0].session
+        );
+        assert_eq!(hex_field::eui(4), devices[1].join_eui);
+        assert!(devices[1].session.is_none());
+
+        let short_key = r#"[{"devEui": "0000000000000001", "joinEui": "0000000000000002",
+            "devAddr": "48000001", "nwkSKey": "0011"}]"#;
+        assert!(from_json(short_key, &Columns::default()).is_err());
+    }
+
+    #[test]
+    fn mapped_columns() {
+        let names = Columns {
+            dev_eui: Some("Device EUI".to_string()),
+            join_eui: Some("app_id".to_string()),
+            devaddr: None,
+            session_key: Some("NwkKey".to_string()),
+        };
+        let csv = format!(
+            "device eui,app id,devaddr,nwk key,dev_eui\n\
+             0000000000000001,0000000000000002,48000001,{KEY},0000000000000009\n"
+        );
+        let devices = from_csv(&csv, &names).unwrap();
+        assert_eq!(hex_field::eui(1), devices[0].dev_eui);
+        assert_eq!(hex_field::eui(2), devices[0].join_eui);
+        assert!(devices[0].session.is_some());
+
+        let err = from_csv("dev_eui,join_eui\n", &names).unwrap_err();
+        assert_eq!(
+            "no column Device EUI for dev_eui in the header",
+            err.to_string()
+        );
+    }
+
+    #[test]
+    fn exported_devices_read_back() {
+        let route_id = RouteId::new_unchecked("route-one");
+        let eui = Eui::new(route_id, hex_field::eui(2), hex_field::eui(1)).unwrap();
+        let device = ExportedDevice::new(&eui, "app", "profile");
+
+        let csv = format!("{CSV_HEADER}\n{}\n", device.csv_row());
+        let json = serde_json::to_string(&vec![&device]).unwrap();
+        for devices in [
+            from_csv(&csv, &Columns::default()).unwrap(),
+            from_json(&json, &Columns::default()).unwrap(),
+        ] {
+            assert_eq!(hex_field::eui(1), devices[0].dev_eui);
+            assert_eq!(hex_field::eui(2), devices[0].join_eui);
+        }
+        assert!(json.contains(r#""deviceProfileId":"profile""#));
+    }
+}
@@ -1,56 +1,158 @@
 use crate::{
-    hex_field, region::Region, region_params::RegionParams, route::Route, DevaddrRange, Eui, NetId,
-    OrgList, OrgResponse, Oui, Result, RouteList, SessionKeyFilter,
+    hex_field, progress,
+    region::Region,
+    region_params::RegionParams,
+    route::{Route, RouteId},
+    vcr::Transport,
+    DevaddrRange, Eui, NetId, OrgList, OrgResponse, Oui, Result, RouteList, SessionKeyFilter,
+};
+use anyhow::{anyhow, Context};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use futures::{
+    future,
+    stream::{BoxStream, Stream},
+    StreamExt, TryStreamExt,
 };
 use helium_crypto::{Keypair, PublicKey, Sign};
 use helium_proto::{
     services::iot_config::{
         gateway_client, org_client, route_client, session_key_filter_client, ActionV1,
-        GatewayLoadRegionReqV1, GatewayLoadRegionResV1, OrgCreateHeliumReqV1, OrgCreateRoamerReqV1,
-        OrgGetReqV1, OrgListReqV1, RouteCreateReqV1, RouteDeleteDevaddrRangesReqV1,
-        RouteDeleteEuisReqV1, RouteDeleteReqV1, RouteDevaddrRangesResV1, RouteEuisResV1,
-        RouteGetDevaddrRangesReqV1, RouteGetEuisReqV1, RouteGetReqV1, RouteListReqV1,
-        RouteUpdateDevaddrRangesReqV1, RouteUpdateEuisReqV1, RouteUpdateReqV1,
-        SessionKeyFilterGetReqV1, SessionKeyFilterListReqV1, SessionKeyFilterUpdateReqV1,
-        SessionKeyFilterUpdateResV1,
+        GatewayLoadRegionReqV1, GatewayLoadRegionResV1, GatewayLocationReqV1, OrgCreateHeliumReqV1,
+        OrgCreateRoamerReqV1, OrgGetReqV1, OrgListReqV1, RouteCreateReqV1,
+        RouteDeleteDevaddrRangesReqV1, RouteDeleteEuisReqV1, RouteDeleteReqV1,
+        RouteDevaddrRangesResV1, RouteEuisResV1, RouteGetDevaddrRangesReqV1, RouteGetEuisReqV1,
+        RouteGetReqV1, RouteListReqV1, RouteUpdateDevaddrRangesReqV1, RouteUpdateEuisReqV1,
+        RouteUpdateReqV1, SessionKeyFilterGetReqV1, SessionKeyFilterListReqV1,
+        SessionKeyFilterUpdateReqV1, SessionKeyFilterUpdateResV1,
     },
     Message,
 };
-use std::time::{SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    future::Future,
+    io::{ErrorKind, Write},
+    net::{Ipv6Addr, SocketAddr},
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tonic::transport::{Certificate, Channel, ClientTlsConfig, Endpoint, Identity};
+use trust_dns_resolver::TokioAsyncResolver;
+
+pub const DEFAULT_CONFIG_PORT: u16 = 50051;
+
+/// Signed requests held in memory at once while they are written to the
+/// `--dump-request` file.
+const DUMP_CHUNK_SIZE: usize = 10_000;
+
+/// `requests` as a request stream, counted on a progress bar as they are
+/// sent.
+fn with_progress<T>(what: &'static str, requests: Vec<T>) -> impl Stream<Item = T> {
+    let progress = progress::bar(what, Some(requests.len() as u64));
+    futures::stream::iter(requests).inspect(move |_| progress.inc(1))
+}
+
+/// How clients connect to the config service.
+///
+/// All fields are read from the global arguments on `Cli`.
+#[derive(Debug, Clone, Default, clap::Args)]
+pub struct ConnectOpts {
+    #[arg(from_global)]
+    pub tls_ca_bundle: Option<PathBuf>,
+    #[arg(from_global)]
+    pub tls_cert: Option<PathBuf>,
+    #[arg(from_global)]
+    pub tls_key: Option<PathBuf>,
+    #[arg(from_global)]
+    pub tls_insecure: bool,
+    #[arg(from_global)]
+    pub connect_timeout: Option<u64>,
+    #[arg(from_global)]
+    pub rpc_timeout: Option<u64>,
+    #[arg(from_global)]
+    pub keepalive_interval: Option<u64>,
+    #[arg(from_global)]
+    pub keepalive_timeout: Option<u64>,
+    #[arg(from_global)]
+    pub max_retries: u32,
+    #[arg(from_global)]
+    pub dump_request: Option<PathBuf>,
+    #[arg(from_global)]
+    pub timestamp: Option<u64>,
+    #[arg(from_global)]
+    pub timestamp_skew_tolerance: Option<u64>,
+    #[arg(from_global)]
+    pub vcr_record: Option<PathBuf>,
+    #[arg(from_global)]
+    pub vcr_replay: Option<PathBuf>,
+    #[arg(from_global)]
+    pub proto_out: Option<PathBuf>,
+}
 
 pub struct OrgClient {
-    client: org_client::OrgClient<tonic::transport::Channel>,
+    client: org_client::OrgClient<Transport>,
+    dump_request: Option<PathBuf>,
+    clock: RequestClock,
+    retry: Retry,
 }
+#[derive(Clone)]
 pub struct RouteClient {
-    client: route_client::RouteClient<tonic::transport::Channel>,
+    client: route_client::RouteClient<Transport>,
+    dump_request: Option<PathBuf>,
+    clock: RequestClock,
+    retry: Retry,
 }
 
+#[derive(Clone)]
 pub struct SkfClient {
-    client: session_key_filter_client::SessionKeyFilterClient<tonic::transport::Channel>,
+    client: session_key_filter_client::SessionKeyFilterClient<Transport>,
+    dump_request: Option<PathBuf>,
+    clock: RequestClock,
+    retry: Retry,
 }
 
 pub struct GatewayClient {
-    client: gateway_client::GatewayClient<tonic::transport::Channel>,
+    client: gateway_client::GatewayClient<Transport>,
+    dump_request: Option<PathBuf>,
+    retry: Retry,
 }
 
 pub type EuiClient = RouteClient;
 pub type DevaddrClient = RouteClient;
 
 impl OrgClient {
-    pub async fn new(host: &str) -> Result<Self> {
+    pub async fn new(host: &str, connection: &ConnectOpts) -> Result<Self> {
         Ok(Self {
-            client: org_client::OrgClient::connect(host.to_owned()).await?,
+            client: org_client::OrgClient::new(connect(host, connection).await?),
+            dump_request: connection.dump_request.clone(),
+            retry: Retry::new(connection),
+            clock: RequestClock::new(connection),
         })
     }
 
     pub async fn list(&mut self) -> Result<OrgList> {
-        let request = OrgListReqV1 {};
-        Ok(self.client.list(request).await?.into_inner().into())
+        Ok(self
+            .retry
+            .call(
+                &self.client,
+                || Ok(OrgListReqV1 {}),
+                |mut client, request| async move { client.list(request).await },
+            )
+            .await?
+            .into())
     }
 
     pub async fn get(&mut self, oui: Oui) -> Result<OrgResponse> {
-        let request = OrgGetReqV1 { oui };
-        Ok(self.client.get(request).await?.into_inner().into())
+        Ok(self
+            .retry
+            .call(
+                &self.client,
+                || Ok(OrgGetReqV1 { oui }),
+                |mut client, request| async move { client.get(request).await },
+            )
+            .await?
+            .into())
     }
 
     pub async fn create_helium(
@@ -64,11 +166,18 @@ impl OrgClient {
             owner: owner.into(),
             payer: payer.into(),
             devaddrs: devaddr_count,
-            timestamp: current_timestamp()?,
+            timestamp: self.clock.current_timestamp()?,
             delegate_keys: vec![],
             signature: vec![],
         };
         request.signature = request.sign(keypair)?;
+        dump_requests(
+            &self.dump_request,
+            "org.create_helium",
+            std::slice::from_ref(&request),
+            Some(request.timestamp),
+            keypair,
+        )?;
         Ok(self
             .client
             .create_helium(request)
@@ -88,11 +197,18 @@ impl OrgClient {
             owner: owner.into(),
             payer: payer.into(),
             net_id,
-            timestamp: current_timestamp()?,
+            timestamp: self.clock.current_timestamp()?,
             delegate_keys: vec![],
             signature: vec![],
         };
         request.signature = request.sign(&keypair)?;
+        dump_requests(
+            &self.dump_request,
+            "org.create_roamer",
+            std::slice::from_ref(&request),
+            Some(request.timestamp),
+            &keypair,
+        )?;
         Ok(self
             .client
             .create_roamer(request)
@@ -105,23 +221,43 @@ impl OrgClient {
 impl DevaddrClient {
     pub async fn get_devaddrs(
         &mut self,
-        route_id: &str,
+        route_id: &RouteId,
         keypair: &Keypair,
     ) -> Result<Vec<DevaddrRange>> {
-        let mut request = RouteGetDevaddrRangesReqV1 {
-            route_id: route_id.to_string(),
-            timestamp: current_timestamp()?,
-            signature: vec![],
-        };
-        request.signature = request.sign(keypair)?;
-        let mut stream = self.client.get_devaddr_ranges(request).await?.into_inner();
+        self.stream_devaddrs(route_id, keypair)
+            .await?
+            .try_collect()
+            .await
+    }
 
-        let mut ranges = vec![];
-        while let Some(range) = stream.message().await? {
-            ranges.push(range.into());
-        }
+    /// Devaddr Ranges as they arrive, without holding them all in memory.
+    pub async fn stream_devaddrs(
+        &mut self,
+        route_id: &RouteId,
+        keypair: &Keypair,
+    ) -> Result<BoxStream<'static, Result<DevaddrRange>>> {
+        let request = || {
+            let mut request = RouteGetDevaddrRangesReqV1 {
+                route_id: route_id.to_string(),
+                timestamp: self.clock.current_timestamp()?,
+                signature: vec![],
+            };
+            request.signature = request.sign(keypair)?;
+            Ok(request)
+        };
+        let stream = self
+            .retry
+            .call(&self.client, request, |mut client, request| async move {
+                client.get_devaddr_ranges(request).await
+            })
+            .await?;
 
-        Ok(ranges)
+        let progress = progress::bar("devaddrs", None);
+        Ok(stream
+            .map_ok(DevaddrRange::from)
+            .map_err(anyhow::Error::from)
+            .inspect_ok(move |_| progress.inc(1))
+            .boxed())
     }
 
     pub async fn add_devaddrs(
@@ -129,26 +265,7 @@ impl DevaddrClient {
         devaddrs: Vec<DevaddrRange>,
         keypair: &Keypair,
     ) -> Result<RouteDevaddrRangesResV1> {
-        let timestamp = current_timestamp()?;
-        let route_devaddrs: Vec<RouteUpdateDevaddrRangesReqV1> = devaddrs
-            .into_iter()
-            .flat_map(|devaddr| -> Result<RouteUpdateDevaddrRangesReqV1> {
-                let mut request = RouteUpdateDevaddrRangesReqV1 {
-                    action: ActionV1::Add.into(),
-                    timestamp,
-                    signature: vec![],
-                    devaddr_range: Some(devaddr.into()),
-                };
-                request.signature = request.sign(keypair)?;
-                Ok(request)
-            })
-            .collect();
-        let request = futures::stream::iter(route_devaddrs);
-        Ok(self
-            .client
-            .update_devaddr_ranges(request)
-            .await?
-            .into_inner())
+        self.update_devaddrs(ActionV1::Add, devaddrs, keypair).await
     }
 
     pub async fn remove_devaddrs(
@@ -156,75 +273,107 @@ impl DevaddrClient {
         devaddrs: Vec<DevaddrRange>,
         keypair: &Keypair,
     ) -> Result<RouteDevaddrRangesResV1> {
-        let timestamp = current_timestamp()?;
-        let route_devaddrs: Vec<RouteUpdateDevaddrRangesReqV1> = devaddrs
-            .into_iter()
-            .flat_map(|devaddr| -> Result<RouteUpdateDevaddrRangesReqV1> {
-                let mut request = RouteUpdateDevaddrRangesReqV1 {
-                    action: ActionV1::Remove.into(),
-                    timestamp,
-                    signature: vec![],
-                    devaddr_range: Some(devaddr.into()),
-                };
-                request.signature = request.sign(keypair)?;
-                Ok(request)
+        self.update_devaddrs(ActionV1::Remove, devaddrs, keypair)
+            .await
+    }
+
+    async fn update_devaddrs(
+        &mut self,
+        action: ActionV1,
+        devaddrs: Vec<DevaddrRange>,
+        keypair: &Keypair,
+    ) -> Result<RouteDevaddrRangesResV1> {
+        let requests = || {
+            let timestamp = self.clock.current_timestamp()?;
+            let requests: Vec<RouteUpdateDevaddrRangesReqV1> = devaddrs
+                .iter()
+                .flat_map(|devaddr| -> Result<RouteUpdateDevaddrRangesReqV1> {
+                    let mut request = RouteUpdateDevaddrRangesReqV1 {
+                        action: action.into(),
+                        timestamp,
+                        signature: vec![],
+                        devaddr_range: Some(devaddr.clone().into()),
+                    };
+                    request.signature = request.sign(keypair)?;
+                    Ok(request)
+                })
+                .collect();
+            dump_requests(
+                &self.dump_request,
+                "route.update_devaddr_ranges",
+                &requests,
+                Some(timestamp),
+                keypair,
+            )?;
+            Ok(requests)
+        };
+        self.retry
+            .call(&self.client, requests, |mut client, requests| async move {
+                let request = with_progress("devaddrs", requests);
+                client.update_devaddr_ranges(request).await
             })
-            .collect();
-        let request = futures::stream::iter(route_devaddrs);
-        Ok(self
-            .client
-            .update_devaddr_ranges(request)
-            .await?
-            .into_inner())
+            .await
     }
 
-    pub async fn delete_devaddrs(&mut self, route_id: String, keypair: &Keypair) -> Result {
+    pub async fn delete_devaddrs(&mut self, route_id: RouteId, keypair: &Keypair) -> Result {
         let mut request = RouteDeleteDevaddrRangesReqV1 {
-            route_id,
-            timestamp: current_timestamp()?,
+            route_id: route_id.into(),
+            timestamp: self.clock.current_timestamp()?,
             signature: vec![],
         };
         request.signature = request.sign(keypair)?;
+        dump_requests(
+            &self.dump_request,
+            "route.delete_devaddr_ranges",
+            std::slice::from_ref(&request),
+            Some(request.timestamp),
+            keypair,
+        )?;
         self.client.delete_devaddr_ranges(request).await?;
         Ok(())
     }
 }
 
 impl EuiClient {
-    pub async fn get_euis(&mut self, route_id: &str, keypair: &Keypair) -> Result<Vec<Eui>> {
-        let mut request = RouteGetEuisReqV1 {
-            route_id: route_id.to_string(),
-            timestamp: current_timestamp()?,
-            signature: vec![],
-        };
-        request.signature = request.sign(keypair)?;
-        let mut stream = self.client.get_euis(request).await?.into_inner();
+    pub async fn get_euis(&mut self, route_id: &RouteId, keypair: &Keypair) -> Result<Vec<Eui>> {
+        self.stream_euis(route_id, keypair)
+            .await?
+            .try_collect()
+            .await
+    }
 
-        let mut pairs = vec![];
-        while let Some(pair) = stream.message().await? {
-            pairs.push(pair.into());
-        }
+    /// EUI pairs as they arrive, without holding them all in memory.
+    pub async fn stream_euis(
+        &mut self,
+        route_id: &RouteId,
+        keypair: &Keypair,
+    ) -> Result<BoxStream<'static, Result<Eui>>> {
+        let request = || {
+            let mut request = RouteGetEuisReqV1 {
+                route_id: route_id.to_string(),
+                timestamp: self.clock.current_timestamp()?,
+                signature: vec![],
+            };
+            request.signature = request.sign(keypair)?;
+            Ok(request)
+        };
+        let stream = self
+            .retry
+            .call(&self.client, request, |mut client, request| async move {
+                client.get_euis(request).await
+            })
+            .await?;
 
-        Ok(pairs)
+        let progress = progress::bar("euis", None);
+        Ok(stream
+            .map_ok(Eui::from)
+            .map_err(anyhow::Error::from)
+            .inspect_ok(move |_| progress.inc(1))
+            .boxed())
     }
 
     pub async fn add_euis(&mut self, euis: Vec<Eui>, keypair: &Keypair) -> Result<RouteEuisResV1> {
-        let timestamp = current_timestamp()?;
-        let route_euis: Vec<RouteUpdateEuisReqV1> = euis
-            .into_iter()
-            .flat_map(|eui| -> Result<RouteUpdateEuisReqV1> {
-                let mut request = RouteUpdateEuisReqV1 {
-                    action: ActionV1::Add.into(),
-                    timestamp,
-                    signature: vec![],
-                    eui_pair: Some(eui.into()),
-                };
-                request.signature = request.sign(keypair)?;
-                Ok(request)
-            })
-            .collect();
-        let request = futures::stream::iter(route_euis);
-        Ok(self.client.update_euis(request).await?.into_inner())
+        self.update_euis(ActionV1::Add, euis, keypair).await
     }
 
     pub async fn remove_euis(
@@ -232,100 +381,184 @@ impl EuiClient {
         euis: Vec<Eui>,
         keypair: &Keypair,
     ) -> Result<RouteEuisResV1> {
-        let timestamp = current_timestamp()?;
-        let route_euis: Vec<RouteUpdateEuisReqV1> = euis
-            .into_iter()
-            .flat_map(|eui| -> Result<RouteUpdateEuisReqV1> {
-                let mut request = RouteUpdateEuisReqV1 {
-                    action: ActionV1::Remove.into(),
-                    timestamp,
-                    signature: vec![],
-                    eui_pair: Some(eui.into()),
-                };
-                request.signature = request.sign(keypair)?;
-                Ok(request)
+        self.update_euis(ActionV1::Remove, euis, keypair).await
+    }
+
+    async fn update_euis(
+        &mut self,
+        action: ActionV1,
+        euis: Vec<Eui>,
+        keypair: &Keypair,
+    ) -> Result<RouteEuisResV1> {
+        // Every attempt signs its own request stream, with its own copy of
+        // the keypair as the stream outlives the borrow of `keypair`. The
+        // pairs are shared by the attempts rather than copied for each.
+        let euis = Arc::new(euis);
+        let signer = Arc::new(Keypair::try_from(&keypair.to_vec()[..])?);
+        let requests = || {
+            let timestamp = self.clock.current_timestamp()?;
+            dump_eui_updates(action, &euis, timestamp, keypair, &self.dump_request)?;
+            Ok((euis.clone(), timestamp))
+        };
+        self.retry
+            .call(&self.client, requests, |mut client, (euis, timestamp)| {
+                let unsigned = Arc::new(Mutex::new(None));
+                let request =
+                    eui_updates(action, euis, timestamp, signer.clone(), unsigned.clone());
+                async move {
+                    let response = client.update_euis(request).await;
+                    // Not a transient failure, a retry would stop at the same pair.
+                    let unsigned = unsigned
+                        .lock()
+                        .unwrap_or_else(|err| err.into_inner())
+                        .take();
+                    match unsigned {
+                        Some(err) => Err(tonic::Status::internal(format!("{err:#}"))),
+                        None => response,
+                    }
+                }
             })
-            .collect();
-        let request = futures::stream::iter(route_euis);
-        Ok(self.client.update_euis(request).await?.into_inner())
+            .await
     }
 
-    pub async fn delete_euis(&mut self, route_id: String, keypair: &Keypair) -> Result {
+    pub async fn delete_euis(&mut self, route_id: RouteId, keypair: &Keypair) -> Result {
         let mut request = RouteDeleteEuisReqV1 {
-            route_id,
-            timestamp: current_timestamp()?,
+            route_id: route_id.into(),
+            timestamp: self.clock.current_timestamp()?,
             signature: vec![],
         };
         request.signature = request.sign(keypair)?;
+        dump_requests(
+            &self.dump_request,
+            "route.delete_euis",
+            std::slice::from_ref(&request),
+            Some(request.timestamp),
+            keypair,
+        )?;
         self.client.delete_euis(request).await?;
         Ok(())
     }
 }
 
 impl RouteClient {
-    pub async fn new(host: &str) -> Result<Self> {
+    pub async fn new(host: &str, connection: &ConnectOpts) -> Result<Self> {
         Ok(Self {
-            client: route_client::RouteClient::connect(host.to_owned()).await?,
+            client: route_client::RouteClient::new(connect(host, connection).await?),
+            dump_request: connection.dump_request.clone(),
+            retry: Retry::new(connection),
+            clock: RequestClock::new(connection),
         })
     }
 
     pub async fn list(&mut self, oui: Oui, keypair: &Keypair) -> Result<RouteList> {
-        let mut request = RouteListReqV1 {
-            oui,
-            timestamp: current_timestamp()?,
-            signature: vec![],
+        let request = || {
+            let mut request = RouteListReqV1 {
+                oui,
+                timestamp: self.clock.current_timestamp()?,
+                signature: vec![],
+            };
+            request.signature = request.sign(keypair)?;
+            Ok(request)
         };
-        request.signature = request.sign(keypair)?;
-        Ok(self.client.list(request).await?.into_inner().into())
+        Ok(self
+            .retry
+            .call(&self.client, request, |mut client, request| async move {
+                client.list(request).await
+            })
+            .await?
+            .into())
     }
 
-    pub async fn get(&mut self, id: &str, keypair: &Keypair) -> Result<Route> {
-        let mut request = RouteGetReqV1 {
-            id: id.into(),
-            signature: vec![],
-            timestamp: current_timestamp()?,
+    pub async fn get(&mut self, id: &RouteId, keypair: &Keypair) -> Result<Route> {
+        let request = || {
+            let mut request = RouteGetReqV1 {
+                id: id.into(),
+                signature: vec![],
+                timestamp: self.clock.current_timestamp()?,
+            };
+            request.signature = request.sign(keypair)?;
+            Ok(request)
         };
-        request.signature = request.sign(keypair)?;
-        Ok(self.client.get(request).await?.into_inner().into())
+        Ok(self
+            .retry
+            .call(&self.client, request, |mut client, request| async move {
+                client.get(request).await
+            })
+            .await?
+            .into())
     }
 
     pub async fn create_route(&mut self, route: Route, keypair: &Keypair) -> Result<Route> {
         let mut request = RouteCreateReqV1 {
             oui: route.oui,
             route: Some(route.into()),
-            timestamp: current_timestamp()?,
+            timestamp: self.clock.current_timestamp()?,
             signature: vec![],
         };
         request.signature = request.sign(keypair)?;
+        dump_requests(
+            &self.dump_request,
+            "route.create",
+            std::slice::from_ref(&request),
+            Some(request.timestamp),
+            keypair,
+        )?;
         Ok(self.client.create(request).await?.into_inner().into())
     }
 
-    pub async fn delete(&mut self, id: &str, keypair: &Keypair) -> Result<Route> {
+    pub async fn delete(&mut self, id: &RouteId, keypair: &Keypair) -> Result<Route> {
         let mut request = RouteDeleteReqV1 {
             id: id.into(),
-            timestamp: current_timestamp()?,
+            timestamp: self.clock.current_timestamp()?,
             signature: vec![],
         };
         request.signature = request.sign(keypair)?;
+        dump_requests(
+            &self.dump_request,
+            "route.delete",
+            std::slice::from_ref(&request),
+            Some(request.timestamp),
+            keypair,
+        )?;
         Ok(self.client.delete(request).await?.into_inner().into())
     }
 
     pub async fn push(&mut self, route: Route, keypair: &Keypair) -> Result<Route> {
-        let mut request = RouteUpdateReqV1 {
-            route: Some(route.into()),
-            timestamp: current_timestamp()?,
-            signature: vec![],
+        let request = || {
+            let mut request = RouteUpdateReqV1 {
+                route: Some(route.clone().into()),
+                timestamp: self.clock.current_timestamp()?,
+                signature: vec![],
+            };
+            request.signature = request.sign(keypair)?;
+            dump_requests(
+                &self.dump_request,
+                "route.update",
+                std::slice::from_ref(&request),
+                Some(request.timestamp),
+                keypair,
+            )?;
+            Ok(request)
         };
-        request.signature = request.sign(keypair)?;
-        Ok(self.client.update(request).await?.into_inner().into())
+        Ok(self
+            .retry
+            .call(&self.client, request, |mut client, request| async move {
+                client.update(request).await
+            })
+            .await?
+            .into())
     }
 }
 
 impl SkfClient {
-    pub async fn new(host: &str) -> Result<Self> {
+    pub async fn new(host: &str, connection: &ConnectOpts) -> Result<Self> {
         Ok(Self {
-            client: session_key_filter_client::SessionKeyFilterClient::connect(host.to_owned())
-                .await?,
+            client: session_key_filter_client::SessionKeyFilterClient::new(
+                connect(host, connection).await?,
+            ),
+            dump_request: connection.dump_request.clone(),
+            retry: Retry::new(connection),
+            clock: RequestClock::new(connection),
         })
     }
 
@@ -334,20 +567,37 @@ impl SkfClient {
         oui: Oui,
         keypair: &Keypair,
     ) -> Result<Vec<SessionKeyFilter>> {
-        let mut request = SessionKeyFilterListReqV1 {
-            oui,
-            timestamp: current_timestamp()?,
-            signature: vec![],
-        };
-        request.signature = request.sign(keypair)?;
-        let mut stream = self.client.list(request).await?.into_inner();
+        self.stream_filters(oui, keypair).await?.try_collect().await
+    }
 
-        let mut filters = vec![];
-        while let Some(filter) = stream.message().await? {
-            filters.push(filter.into());
-        }
+    /// Session Key Filters as they arrive, without holding them all in memory.
+    pub async fn stream_filters(
+        &mut self,
+        oui: Oui,
+        keypair: &Keypair,
+    ) -> Result<BoxStream<'static, Result<SessionKeyFilter>>> {
+        let request = || {
+            let mut request = SessionKeyFilterListReqV1 {
+                oui,
+                timestamp: self.clock.current_timestamp()?,
+                signature: vec![],
+            };
+            request.signature = request.sign(keypair)?;
+            Ok(request)
+        };
+        let stream = self
+            .retry
+            .call(&self.client, request, |mut client, request| async move {
+                client.list(request).await
+            })
+            .await?;
 
-        Ok(filters)
+        let progress = progress::bar("filters", None);
+        Ok(stream
+            .map_ok(SessionKeyFilter::from)
+            .map_err(anyhow::Error::from)
+            .inspect_ok(move |_| progress.inc(1))
+            .boxed())
     }
 
     pub async fn get_filters(
@@ -356,14 +606,22 @@ impl SkfClient {
         devaddr: hex_field::HexDevAddr,
         keypair: &Keypair,
     ) -> Result<Vec<SessionKeyFilter>> {
-        let mut request = SessionKeyFilterGetReqV1 {
-            oui,
-            devaddr: devaddr.into(),
-            timestamp: current_timestamp()?,
-            signature: vec![],
+        let request = || {
+            let mut request = SessionKeyFilterGetReqV1 {
+                oui,
+                devaddr: devaddr.into(),
+                timestamp: self.clock.current_timestamp()?,
+                signature: vec![],
+            };
+            request.signature = request.sign(keypair)?;
+            Ok(request)
         };
-        request.signature = request.sign(keypair)?;
-        let mut stream = self.client.get(request).await?.into_inner();
+        let mut stream = self
+            .retry
+            .call(&self.client, request, |mut client, request| async move {
+                client.get(request).await
+            })
+            .await?;
 
         let mut filters = vec![];
         while let Some(filter) = stream.message().await? {
@@ -377,22 +635,11 @@ impl SkfClient {
         filters: Vec<SessionKeyFilter>,
         keypair: &Keypair,
     ) -> Result<SessionKeyFilterUpdateResV1> {
-        let timestamp = current_timestamp()?;
-        let filters: Vec<SessionKeyFilterUpdateReqV1> = filters
+        let updates: Vec<_> = filters
             .into_iter()
-            .flat_map(|filter| -> Result<SessionKeyFilterUpdateReqV1> {
-                let mut request = SessionKeyFilterUpdateReqV1 {
-                    action: ActionV1::Add.into(),
-                    filter: Some(filter.into()),
-                    timestamp,
-                    signature: vec![],
-                };
-                request.signature = request.sign(keypair)?;
-                Ok(request)
-            })
+            .map(|filter| (ActionV1::Add, filter))
             .collect();
-        let request = futures::stream::iter(filters);
-        Ok(self.client.update(request).await?.into_inner())
+        self.update_filters(updates, keypair).await
     }
 
     pub async fn remove_filters(
@@ -400,29 +647,69 @@ impl SkfClient {
         filters: Vec<SessionKeyFilter>,
         keypair: &Keypair,
     ) -> Result<SessionKeyFilterUpdateResV1> {
-        let timestamp = current_timestamp()?;
-        let filters: Vec<SessionKeyFilterUpdateReqV1> = filters
+        let updates: Vec<_> = filters
             .into_iter()
-            .flat_map(|filter| -> Result<SessionKeyFilterUpdateReqV1> {
-                let mut request = SessionKeyFilterUpdateReqV1 {
-                    action: ActionV1::Remove.into(),
-                    filter: Some(filter.into()),
-                    timestamp,
-                    signature: vec![],
-                };
-                request.signature = request.sign(keypair)?;
-                Ok(request)
-            })
+            .map(|filter| (ActionV1::Remove, filter))
             .collect();
-        let request = futures::stream::iter(filters);
-        Ok(self.client.update(request).await?.into_inner())
+        self.update_filters(updates, keypair).await
+    }
+
+    /// Add `new` and remove `old` in a single update stream.
+    ///
+    /// The add is sent first so the devaddr is never left without a filter.
+    pub async fn replace_filter(
+        &mut self,
+        old: SessionKeyFilter,
+        new: SessionKeyFilter,
+        keypair: &Keypair,
+    ) -> Result<SessionKeyFilterUpdateResV1> {
+        self.update_filters(vec![(ActionV1::Add, new), (ActionV1::Remove, old)], keypair)
+            .await
+    }
+
+    async fn update_filters(
+        &mut self,
+        updates: Vec<(ActionV1, SessionKeyFilter)>,
+        keypair: &Keypair,
+    ) -> Result<SessionKeyFilterUpdateResV1> {
+        let requests = || {
+            let timestamp = self.clock.current_timestamp()?;
+            let requests: Vec<SessionKeyFilterUpdateReqV1> = updates
+                .iter()
+                .flat_map(|(action, filter)| -> Result<SessionKeyFilterUpdateReqV1> {
+                    let mut request = SessionKeyFilterUpdateReqV1 {
+                        action: (*action).into(),
+                        filter: Some(filter.clone().into()),
+                        timestamp,
+                        signature: vec![],
+                    };
+                    request.signature = request.sign(keypair)?;
+                    Ok(request)
+                })
+                .collect();
+            dump_requests(
+                &self.dump_request,
+                "session_key_filter.update",
+                &requests,
+                Some(timestamp),
+                keypair,
+            )?;
+            Ok(requests)
+        };
+        self.retry
+            .call(&self.client, requests, |mut client, requests| async move {
+                client.update(with_progress("filters", requests)).await
+            })
+            .await
     }
 }
 
 impl GatewayClient {
-    pub async fn new(host: &str) -> Result<Self> {
+    pub async fn new(host: &str, connection: &ConnectOpts) -> Result<Self> {
         Ok(Self {
-            client: gateway_client::GatewayClient::connect(host.to_owned()).await?,
+            client: gateway_client::GatewayClient::new(connect(host, connection).await?),
+            dump_request: connection.dump_request.clone(),
+            retry: Retry::new(connection),
         })
     }
 
@@ -433,19 +720,524 @@ impl GatewayClient {
         indexes: Vec<u8>,
         keypair: &Keypair,
     ) -> Result<GatewayLoadRegionResV1> {
-        let mut request = GatewayLoadRegionReqV1 {
-            region: region.into(),
-            params: Some(params.into()),
-            hex_indexes: indexes,
-            signature: vec![],
-        };
-        request.signature = request.sign(keypair)?;
-        Ok(self.client.load_region(request).await?.into_inner())
+        let request = load_region_request(region, params, indexes, keypair)?;
+        dump_requests(
+            &self.dump_request,
+            "gateway.load_region",
+            std::slice::from_ref(&request),
+            None,
+            keypair,
+        )?;
+        self.submit_load_region(request).await
+    }
+
+    /// Send a load region request that was signed ahead of time.
+    pub async fn submit_load_region(
+        &mut self,
+        request: GatewayLoadRegionReqV1,
+    ) -> Result<GatewayLoadRegionResV1> {
+        self.retry
+            .call(
+                &self.client,
+                || Ok(request.clone()),
+                |mut client, request| async move { client.load_region(request).await },
+            )
+            .await
+    }
+}
+
+/// Load region requests carry no timestamp, so they can be signed on an
+/// offline machine and submitted later.
+pub fn load_region_request(
+    region: Region,
+    params: RegionParams,
+    indexes: Vec<u8>,
+    keypair: &Keypair,
+) -> Result<GatewayLoadRegionReqV1> {
+    let mut request = GatewayLoadRegionReqV1 {
+        region: region.into(),
+        params: Some(params.into()),
+        hex_indexes: indexes,
+        signature: vec![],
+    };
+    request.signature = request.sign(keypair)?;
+    Ok(request)
+}
+
+/// Call the read-only RPCs the CLI depends on with empty requests.
+///
+/// The config service rejects most of them as unsigned or invalid, which is
+/// fine, only the returned status code matters. `Ok` is used when a call
+/// succeeded.
+pub async fn probe_rpcs(
+    host: &str,
+    connection: &ConnectOpts,
+) -> Result<Vec<(&'static str, tonic::Status)>> {
+    let channel = connect(host, connection).await?;
+    let mut org = org_client::OrgClient::new(channel.clone());
+    let mut route = route_client::RouteClient::new(channel.clone());
+    let mut skf = session_key_filter_client::SessionKeyFilterClient::new(channel.clone());
+    let mut gateway = gateway_client::GatewayClient::new(channel);
+
+    Ok(vec![
+        probe("org.list", org.list(OrgListReqV1 {}).await),
+        probe("route.list", route.list(RouteListReqV1::default()).await),
+        probe(
+            "route.get_euis",
+            route.get_euis(RouteGetEuisReqV1::default()).await,
+        ),
+        probe(
+            "route.get_devaddr_ranges",
+            route
+                .get_devaddr_ranges(RouteGetDevaddrRangesReqV1::default())
+                .await,
+        ),
+        probe(
+            "session_key_filter.list",
+            skf.list(SessionKeyFilterListReqV1::default()).await,
+        ),
+        probe(
+            "gateway.location",
+            gateway.location(GatewayLocationReqV1::default()).await,
+        ),
+    ])
+}
+
+fn probe<T>(
+    rpc: &'static str,
+    result: Result<tonic::Response<T>, tonic::Status>,
+) -> (&'static str, tonic::Status) {
+    match result {
+        Ok(_) => (rpc, tonic::Status::new(tonic::Code::Ok, "")),
+        Err(status) => (rpc, status),
+    }
+}
+
+/// Retries of requests that fail on the way to the config service or that
+/// it is too busy to answer, waiting longer before each one.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Retry {
+    pub max_retries: u32,
+}
+
+impl Retry {
+    /// Wait before the first retry, doubled for every retry after it.
+    const FIRST_DELAY: Duration = Duration::from_millis(250);
+    const MAX_DELAY: Duration = Duration::from_secs(30);
+
+    pub fn new(connection: &ConnectOpts) -> Self {
+        Self {
+            max_retries: connection.max_retries,
+        }
+    }
+
+    /// Call `rpc` with a clone of `client` and a request from `request`
+    /// until it succeeds, fails with a status that is not
+    /// [transient](is_transient), or is out of retries. `request` builds and
+    /// signs the request of every attempt, so a retry after a long backoff
+    /// carries a fresh timestamp and an attempt never made copies nothing.
+    async fn call<C, R, T, Fut>(
+        &self,
+        client: &C,
+        request: impl Fn() -> Result<R>,
+        rpc: impl Fn(C, R) -> Fut,
+    ) -> Result<T>
+    where
+        C: Clone,
+        Fut: Future<Output = Result<tonic::Response<T>, tonic::Status>>,
+    {
+        let mut retries = 0;
+        loop {
+            match rpc(client.clone(), request()?).await {
+                Ok(response) => return Ok(response.into_inner()),
+                Err(status) if retries < self.max_retries && is_transient(&status) => {
+                    retries += 1;
+                    let delay = backoff(retries, rand::random());
+                    if progress::enabled() {
+                        eprintln!(
+                            "warning: {:?}: {}, retry {retries} of {} in {:.1}s",
+                            status.code(),
+                            status.message(),
+                            self.max_retries,
+                            delay.as_secs_f64()
+                        );
+                    }
+                    tokio::time::sleep(delay).await;
+                }
+                Err(status) => return Err(status.into()),
+            }
+        }
+    }
+}
+
+/// Failures that can pass on their own: the config service unreachable or
+/// overloaded, a timed out request, or a connection reset on the way.
+fn is_transient(status: &tonic::Status) -> bool {
+    if matches!(
+        status.code(),
+        tonic::Code::Unavailable | tonic::Code::DeadlineExceeded
+    ) {
+        return true;
+    }
+    let mut source = std::error::Error::source(status);
+    while let Some(err) = source {
+        if let Some(err) = err.downcast_ref::<std::io::Error>() {
+            if matches!(
+                err.kind(),
+                ErrorKind::ConnectionReset | ErrorKind::ConnectionAborted | ErrorKind::BrokenPipe
+            ) {
+                return true;
+            }
+        }
+        source = err.source();
+    }
+    false
+}
+
+/// Wait before retry number `retry`, counting from 1. Exponential, with
+/// `jitter` in `0.0..1.0` picking a point in the upper half of the delay so
+/// parallel updates that failed together do not retry together.
+fn backoff(retry: u32, jitter: f64) -> Duration {
+    let delay = Retry::FIRST_DELAY
+        .saturating_mul(2u32.saturating_pow(retry.saturating_sub(1)))
+        .min(Retry::MAX_DELAY);
+    delay.mul_f64(0.5 + jitter / 2.0)
+}
+
+/// Connect to the config service, or to the cassette of `--vcr-replay`.
+pub async fn connect(host: &str, connection: &ConnectOpts) -> Result<Transport> {
+    let transport = match &connection.vcr_replay {
+        Some(path) => Transport::replay(path)?,
+        None => {
+            let channel = connect_channel(host, connection).await?;
+            match &connection.vcr_record {
+                Some(path) => Transport::record(channel, path)?,
+                None => Transport::live(channel),
+            }
+        }
+    };
+    Ok(transport.with_proto_out(connection.proto_out.clone()))
+}
+
+async fn connect_channel(host: &str, connection: &ConnectOpts) -> Result<Channel> {
+    let mut endpoint = Endpoint::from_shared(resolve_config_host(host).await?)?;
+    if let Some(secs) = connection.connect_timeout {
+        endpoint = endpoint.connect_timeout(Duration::from_secs(secs));
+    }
+    if let Some(secs) = connection.rpc_timeout {
+        endpoint = endpoint.timeout(Duration::from_secs(secs));
+    }
+    if let Some(secs) = connection.keepalive_interval {
+        endpoint = endpoint.http2_keep_alive_interval(Duration::from_secs(secs));
     }
+    if let Some(secs) = connection.keepalive_timeout {
+        endpoint = endpoint.keep_alive_timeout(Duration::from_secs(secs));
+    }
+
+    if connection.tls_insecure {
+        // There is no certificate to skip over plaintext.
+        if endpoint.uri().scheme_str() != Some("https") {
+            return Err(anyhow!(
+                "--tls-insecure only applies to https:// config hosts, not {host}"
+            ));
+        }
+        eprintln!(
+            "!! WARNING: --tls-insecure is set, the certificate of {host} is NOT verified !!"
+        );
+        eprintln!("!! Anyone on the network path can impersonate the config service.    !!");
+        return Ok(endpoint.connect_with_connector_lazy(insecure_connector()));
+    }
+
+    let endpoint = match tls_config(&endpoint, connection)? {
+        Some(tls) => endpoint.tls_config(tls)?,
+        None => endpoint,
+    };
+
+    // Connected on the first request, so a config service that can't be
+    // reached yet is retried like any other `Unavailable`.
+    Ok(endpoint.connect_lazy())
+}
+
+/// TLS for `https://` hosts and hosts with a `--tls-ca-bundle` or client
+/// certificate. The server name sent for SNI and checked against the
+/// certificate is the host of the uri, verified with the system root
+/// certificates unless a bundle is given.
+fn tls_config(endpoint: &Endpoint, connection: &ConnectOpts) -> Result<Option<ClientTlsConfig>> {
+    let https = endpoint.uri().scheme_str() == Some("https");
+    if !https && connection.tls_ca_bundle.is_none() && connection.tls_cert.is_none() {
+        return Ok(None);
+    }
+    let mut tls = ClientTlsConfig::new();
+    if let Some(host) = endpoint.uri().host() {
+        tls = tls.domain_name(host.trim_start_matches('[').trim_end_matches(']'));
+    }
+    if let Some(path) = &connection.tls_ca_bundle {
+        let pem =
+            fs::read(path).with_context(|| format!("reading tls ca bundle {}", path.display()))?;
+        tls = tls.ca_certificate(Certificate::from_pem(pem));
+    }
+    // clap only accepts the certificate and key together.
+    if let (Some(cert), Some(key)) = (&connection.tls_cert, &connection.tls_key) {
+        let cert =
+            fs::read(cert).with_context(|| format!("reading tls cert {}", cert.display()))?;
+        let key = fs::read(key).with_context(|| format!("reading tls key {}", key.display()))?;
+        tls = tls.identity(Identity::from_pem(cert, key));
+    }
+    Ok(Some(tls))
+}
+
+fn insecure_connector() -> hyper_rustls::HttpsConnector<hyper::client::HttpConnector> {
+    let mut tls = rustls::ClientConfig::builder()
+        .with_safe_defaults()
+        .with_custom_certificate_verifier(Arc::new(NoCertificateVerification))
+        .with_no_client_auth();
+    tls.alpn_protocols = vec![b"h2".to_vec()];
+
+    let mut http = hyper::client::HttpConnector::new();
+    http.enforce_http(false);
+
+    hyper_rustls::HttpsConnectorBuilder::new()
+        .with_tls_config(tls)
+        .https_or_http()
+        .enable_http2()
+        .wrap_connector(http)
 }
 
-fn current_timestamp() -> Result<u64> {
-    Ok(SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as u64)
+struct NoCertificateVerification;
+
+impl rustls::client::ServerCertVerifier for NoCertificateVerification {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &rustls::Certificate,
+        _intermediates: &[rustls::Certificate],
+        _server_name: &rustls::ServerName,
+        _scts: &mut dyn Iterator<Item = &[u8]>,
+        _ocsp_response: &[u8],
+        _now: SystemTime,
+    ) -> Result<rustls::client::ServerCertVerified, rustls::Error> {
+        Ok(rustls::client::ServerCertVerified::assertion())
+    }
+}
+
+/// Resolve a config host into a uri that can be connected to.
+///
+/// Hosts of the form `srv://example.com` (or `srv+https://example.com`) are
+/// looked up through the `_grpc._tcp` SRV record of the name, preferring the
+/// lowest priority and then highest weight target. Everything else goes
+/// through [`normalize_config_host`].
+pub async fn resolve_config_host(host: &str) -> Result<String> {
+    let (scheme, name) = match host.trim().split_once("://") {
+        Some(("srv", name)) => ("http", name),
+        Some(("srv+https", name)) => ("https", name),
+        _ => return normalize_config_host(host),
+    };
+
+    let resolver = TokioAsyncResolver::tokio_from_system_conf()?;
+    let lookup = resolver.srv_lookup(format!("_grpc._tcp.{name}")).await?;
+    let record = lookup
+        .iter()
+        .min_by_key(|srv| (srv.priority(), std::cmp::Reverse(srv.weight())))
+        .ok_or_else(|| anyhow!("no _grpc._tcp SRV records found for {name}"))?;
+    let target = record.target().to_utf8();
+    normalize_config_host(&format!(
+        "{scheme}://{}:{}",
+        target.trim_end_matches('.'),
+        record.port()
+    ))
+}
+
+/// Fill in the parts of a config host users commonly leave out.
+///
+/// - no scheme defaults to `http://`
+/// - no port defaults to [`DEFAULT_CONFIG_PORT`]
+/// - IPv6 literals are accepted bare (`::1`) or bracketed (`[::1]:50051`)
+pub fn normalize_config_host(host: &str) -> Result<String> {
+    let host = host.trim();
+    let uri = if host.contains("://") {
+        host.to_string()
+    } else if let Ok(addr) = host.parse::<Ipv6Addr>() {
+        format!("http://[{addr}]:{DEFAULT_CONFIG_PORT}")
+    } else if let Ok(addr) = host.parse::<SocketAddr>() {
+        format!("http://{addr}")
+    } else if host.starts_with('[') && host.ends_with(']') {
+        format!("http://{host}:{DEFAULT_CONFIG_PORT}")
+    } else if host.contains(':') {
+        format!("http://{host}")
+    } else {
+        format!("http://{host}:{DEFAULT_CONFIG_PORT}")
+    };
+
+    Endpoint::from_shared(uri.clone())
+        .map_err(|err| anyhow!("invalid config host {host}: {err}"))?;
+    Ok(uri)
+}
+
+/// Audit record of a signed request, written as one JSON line per request
+/// to the `--dump-request` file.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SignedRequestDump {
+    pub rpc: String,
+    pub signer: String,
+    /// `None` for requests that carry no timestamp.
+    pub timestamp: Option<u64>,
+    /// Base64 of the protobuf encoded request, signature included, exactly
+    /// as sent.
+    pub request: String,
+}
+
+impl SignedRequestDump {
+    pub fn new<T: Message>(
+        rpc: &str,
+        request: &T,
+        timestamp: Option<u64>,
+        keypair: &Keypair,
+    ) -> Self {
+        Self {
+            rpc: rpc.to_string(),
+            signer: keypair.public_key().to_string(),
+            timestamp,
+            request: STANDARD.encode(request.encode_to_vec()),
+        }
+    }
+}
+
+fn sign_eui_update(
+    action: ActionV1,
+    eui: Eui,
+    timestamp: u64,
+    keypair: &Keypair,
+) -> Result<RouteUpdateEuisReqV1> {
+    let mut request = RouteUpdateEuisReqV1 {
+        action: action.into(),
+        timestamp,
+        signature: vec![],
+        eui_pair: Some(eui.into()),
+    };
+    request.signature = request.sign(keypair)?;
+    Ok(request)
+}
+
+/// Dump the update requests for `euis` before an attempt sends them, a
+/// chunk at a time. Signatures are deterministic, so [`eui_updates`] signs
+/// the very same requests again.
+fn dump_eui_updates(
+    action: ActionV1,
+    euis: &[Eui],
+    timestamp: u64,
+    keypair: &Keypair,
+    dump_request: &Option<PathBuf>,
+) -> Result {
+    if dump_request.is_none() {
+        return Ok(());
+    }
+    for chunk in euis.chunks(DUMP_CHUNK_SIZE) {
+        let requests: Vec<RouteUpdateEuisReqV1> = chunk
+            .iter()
+            .flat_map(|eui| sign_eui_update(action, eui.clone(), timestamp, keypair))
+            .collect();
+        dump_requests(
+            dump_request,
+            "route.update_euis",
+            &requests,
+            Some(timestamp),
+            keypair,
+        )?;
+    }
+    Ok(())
+}
+
+/// Update requests for `euis`, signed one at a time as the stream is
+/// polled, so a multi-million pair upload holds the pairs in memory but
+/// not a signed request for each. A pair that can't be signed ends the
+/// stream, with the error left in `unsigned` to fail the upload.
+fn eui_updates(
+    action: ActionV1,
+    euis: Arc<Vec<Eui>>,
+    timestamp: u64,
+    keypair: Arc<Keypair>,
+    unsigned: Arc<Mutex<Option<anyhow::Error>>>,
+) -> impl Stream<Item = RouteUpdateEuisReqV1> {
+    let progress = progress::bar("euis", Some(euis.len() as u64));
+    futures::stream::unfold((0, euis, keypair), move |(next, euis, keypair)| {
+        let request = euis.get(next).and_then(|eui| {
+            match sign_eui_update(action, eui.clone(), timestamp, &keypair) {
+                Ok(request) => Some(request),
+                Err(err) => {
+                    let mut unsigned = unsigned.lock().unwrap_or_else(|err| err.into_inner());
+                    *unsigned = Some(err.context(format!("signing {eui:?}")));
+                    None
+                }
+            }
+        });
+        future::ready(request.map(|request| (request, (next + 1, euis, keypair))))
+    })
+    .inspect(move |_| progress.inc(1))
+}
+
+fn dump_requests<T: Message>(
+    path: &Option<PathBuf>,
+    rpc: &str,
+    requests: &[T],
+    timestamp: Option<u64>,
+    keypair: &Keypair,
+) -> Result {
+    let path = match path {
+        Some(path) => path,
+        None => return Ok(()),
+    };
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .context(format!("opening request dump {}", path.display()))?;
+    for request in requests {
+        let dump = SignedRequestDump::new(rpc, request, timestamp, keypair);
+        writeln!(file, "{}", serde_json::to_string(&dump)?)
+            .context(format!("writing request dump {}", path.display()))?;
+    }
+    Ok(())
+}
+
+/// Timestamps for signed requests, shared by every request builder.
+///
+/// The config service rejects requests whose timestamp is outside its
+/// freshness window. `--timestamp` pins the timestamp, mostly to test how a
+/// server handles stale or future requests.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RequestClock {
+    /// Timestamp used instead of the local clock.
+    pub fixed: Option<u64>,
+    /// Largest difference in milliseconds allowed between a fixed timestamp
+    /// and the local clock. Unlimited when `None`.
+    pub skew_tolerance: Option<u64>,
+}
+
+impl RequestClock {
+    pub fn new(connection: &ConnectOpts) -> Self {
+        Self {
+            fixed: connection.timestamp,
+            skew_tolerance: connection.timestamp_skew_tolerance,
+        }
+    }
+
+    pub fn current_timestamp(&self) -> Result<u64> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as u64;
+        self.timestamp_at(now)
+    }
+
+    fn timestamp_at(&self, now: u64) -> Result<u64> {
+        let fixed = match self.fixed {
+            Some(fixed) => fixed,
+            None => return Ok(now),
+        };
+        match self.skew_tolerance {
+            Some(tolerance) if fixed.abs_diff(now) > tolerance => Err(anyhow!(
+                "timestamp {fixed} is {}ms from the local clock, over the {tolerance}ms tolerance",
+                fixed.abs_diff(now)
+            )),
+            _ => Ok(fixed),
+        }
+    }
 }
 
 pub trait MsgSign: Message + std::clone::Clone {
@@ -483,3 +1275,210 @@ impl_sign!(SessionKeyFilterUpdateReqV1, signature);
 impl_sign!(OrgCreateHeliumReqV1, signature);
 impl_sign!(OrgCreateRoamerReqV1, signature);
 impl_sign!(GatewayLoadRegionReqV1, signature);
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        backoff, eui_updates, is_transient, normalize_config_host, tls_config, ConnectOpts,
+        MsgSign, RequestClock, Retry, SignedRequestDump,
+    };
+    use crate::{hex_field, route::RouteId, Eui};
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use futures::StreamExt;
+    use helium_crypto::{KeyTag, KeyType, Keypair, Network, Verify};
+    use helium_proto::{
+        services::iot_config::{ActionV1, RouteDeleteReqV1, RouteUpdateEuisReqV1},
+        Message,
+    };
+    use std::{
+        cell::Cell,
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+    use tonic::{transport::Endpoint, Status};
+
+    #[test]
+    fn config_host_normalization() {
+        let tests = vec![
+            ("http://127.0.0.1:50051", "http://127.0.0.1:50051"),
+            ("https://config.example.com", "https://config.example.com"),
+            ("127.0.0.1:50051", "http://127.0.0.1:50051"),
+            ("config.example.com:8080", "http://config.example.com:8080"),
+            ("config.example.com", "http://config.example.com:50051"),
+            ("::1", "http://[::1]:50051"),
+            ("2001:db8::1", "http://[2001:db8::1]:50051"),
+            ("[::1]", "http://[::1]:50051"),
+            ("[2001:db8::1]:1337", "http://[2001:db8::1]:1337"),
+            ("http://[::1]:50051", "http://[::1]:50051"),
+        ];
+        for (input, expected) in tests {
+            assert_eq!(expected, normalize_config_host(input).unwrap(), "{input}");
+        }
+    }
+
+    #[test]
+    fn retry_backoff() {
+        assert!(is_transient(&Status::unavailable("down")));
+        assert!(is_transient(&Status::deadline_exceeded("slow")));
+        assert!(!is_transient(&Status::invalid_argument("bad signature")));
+        assert!(!is_transient(&Status::not_found("no route")));
+
+        assert_eq!(Duration::from_millis(125), backoff(1, 0.0));
+        assert_eq!(Duration::from_millis(500), backoff(2, 1.0));
+        assert_eq!(Duration::from_secs(30), backoff(20, 1.0));
+        assert_eq!(Duration::from_secs(15), backoff(u32::MAX, 0.0));
+    }
+
+    #[tokio::test]
+    async fn retry_builds_each_attempt() {
+        let (built, sent) = (Cell::new(0), Cell::new(vec![]));
+        let retry = Retry { max_retries: 2 };
+        let request = || {
+            built.set(built.get() + 1);
+            Ok(built.get())
+        };
+        let response = retry
+            .call(&(), request, |_, attempt| {
+                let mut attempts = sent.take();
+                attempts.push(attempt);
+                sent.set(attempts);
+                async move {
+                    match attempt {
+                        1 => Err(Status::unavailable("down")),
+                        _ => Ok(tonic::Response::new("applied")),
+                    }
+                }
+            })
+            .await
+            .unwrap();
+        assert_eq!("applied", response);
+        assert_eq!(vec![1, 2], sent.take());
+    }
+
+    #[test]
+    fn tls_for_https_hosts() {
+        let connection = ConnectOpts::default();
+        for (host, tls) in [
+            ("https://config.example.com:443", true),
+            ("http://config.example.com:50051", false),
+            ("config.example.com", false),
+        ] {
+            let endpoint = Endpoint::from_shared(normalize_config_host(host).unwrap()).unwrap();
+            assert_eq!(
+                tls,
+                tls_config(&endpoint, &connection).unwrap().is_some(),
+                "{host}"
+            );
+        }
+
+        let connection = ConnectOpts {
+            tls_ca_bundle: Some("missing-ca.pem".into()),
+            ..ConnectOpts::default()
+        };
+        let endpoint = Endpoint::from_static("http://config.example.com:50051");
+        let err = tls_config(&endpoint, &connection).unwrap_err();
+        assert_eq!("reading tls ca bundle missing-ca.pem", err.to_string());
+
+        let connection = ConnectOpts {
+            tls_cert: Some("missing-cert.pem".into()),
+            tls_key: Some("missing-key.pem".into()),
+            ..ConnectOpts::default()
+        };
+        let err = tls_config(&endpoint, &connection).unwrap_err();
+        assert_eq!("reading tls cert missing-cert.pem", err.to_string());
+    }
+
+    #[test]
+    fn signed_request_dump() {
+        let keypair = Keypair::generate(
+            KeyTag {
+                network: Network::MainNet,
+                key_type: KeyType::Ed25519,
+            },
+            &mut rand::rngs::OsRng,
+        );
+        let mut request = RouteDeleteReqV1 {
+            id: "route-id".to_string(),
+            timestamp: 1_676_000_000_000,
+            signature: vec![],
+        };
+        request.signature = request.sign(&keypair).unwrap();
+
+        let dump =
+            SignedRequestDump::new("route.delete", &request, Some(request.timestamp), &keypair);
+        assert_eq!(keypair.public_key().to_string(), dump.signer);
+        assert_eq!(Some(1_676_000_000_000), dump.timestamp);
+
+        let decoded =
+            RouteDeleteReqV1::decode(&STANDARD.decode(dump.request).unwrap()[..]).unwrap();
+        assert_eq!(request, decoded);
+    }
+
+    #[test]
+    fn request_clock() {
+        let now = 1_676_000_000_000;
+        assert_eq!(now, RequestClock::default().timestamp_at(now).unwrap());
+
+        let fixed = RequestClock {
+            fixed: Some(now - 60_000),
+            skew_tolerance: None,
+        };
+        assert_eq!(now - 60_000, fixed.timestamp_at(now).unwrap());
+
+        let tolerant = RequestClock {
+            skew_tolerance: Some(60_000),
+            ..fixed
+        };
+        assert_eq!(now - 60_000, tolerant.timestamp_at(now).unwrap());
+
+        let strict = RequestClock {
+            skew_tolerance: Some(59_999),
+            ..fixed
+        };
+        assert!(strict.timestamp_at(now).is_err());
+    }
+
+    #[tokio::test]
+    async fn lazy_eui_updates() {
+        let keypair = Keypair::generate(
+            KeyTag {
+                network: Network::MainNet,
+                key_type: KeyType::Ed25519,
+            },
+            &mut rand::rngs::OsRng,
+        );
+        let route_id = RouteId::new_unchecked("route-one");
+        let euis: Vec<Eui> = (0..3)
+            .map(|n| Eui::new(route_id.clone(), hex_field::eui(1), hex_field::eui(n)).unwrap())
+            .collect();
+
+        let keypair = Arc::new(keypair);
+        let requests: Vec<_> = eui_updates(
+            ActionV1::Remove,
+            Arc::new(euis),
+            1_000,
+            keypair.clone(),
+            Arc::new(Mutex::new(None)),
+        )
+        .collect()
+        .await;
+        assert_eq!(3, requests.len());
+        for (n, request) in requests.into_iter().enumerate() {
+            assert_eq!(
+                Some(n as u64),
+                request.eui_pair.as_ref().map(|pair| pair.dev_eui)
+            );
+            assert_eq!(i32::from(ActionV1::Remove), request.action);
+            assert_eq!(1_000, request.timestamp);
+            let signature = request.signature.clone();
+            let unsigned = RouteUpdateEuisReqV1 {
+                signature: vec![],
+                ..request
+            };
+            keypair
+                .public_key()
+                .verify(&unsigned.encode_to_vec(), &signature)
+                .unwrap();
+        }
+    }
+}
@@ -0,0 +1,152 @@
+use super::{Apply, PathBufKeypair};
+use crate::{
+    client::{self, ConnectOpts},
+    route::{Route, RouteId},
+    snapshot::{OrgSnapshot, RestorePlan, RouteAction},
+    Msg, PrettyJson, Result,
+};
+use anyhow::Context;
+use helium_crypto::Keypair;
+
+pub async fn apply(args: Apply) -> Result<Msg> {
+    let desired = OrgSnapshot::from_file(&args.file)?;
+    let oui = desired.oui;
+    let keypair = args.keypair.to_keypair()?;
+    let (plan, mut route_client, mut skf_client) =
+        plan(desired, &args.config_host, &args.connection, &keypair).await?;
+
+    let file = args.file.display();
+    if plan.is_empty() {
+        return Msg::ok(format!("org {oui} already matches {file}"));
+    }
+    let summary = plan.summary().pretty_json()?;
+    if !args.commit {
+        return Msg::dry_run(format!("apply {file} to org {oui}\n{summary}"));
+    }
+
+    execute(plan, &mut route_client, &mut skf_client, &keypair).await?;
+    Msg::ok(format!("applied {file} to org {oui}\n{summary}"))
+}
+
+/// The plan to bring the Org of `desired` to it, with the clients to
+/// execute it.
+pub async fn plan(
+    desired: OrgSnapshot,
+    config_host: &str,
+    connection: &ConnectOpts,
+    keypair: &Keypair,
+) -> Result<(RestorePlan, client::RouteClient, client::SkfClient)> {
+    let mut route_client = client::RouteClient::new(config_host, connection).await?;
+    let mut live = vec![];
+    for route in route_client.list(desired.oui, keypair).await?.routes {
+        let euis = route_client.get_euis(&route.id, keypair).await?;
+        let devaddrs = route_client.get_devaddrs(&route.id, keypair).await?;
+        live.push((route, euis, devaddrs));
+    }
+    let mut skf_client = client::SkfClient::new(config_host, connection).await?;
+    let live_filters = skf_client.list_filters(desired.oui, keypair).await?;
+
+    let plan = RestorePlan::new(desired, live, live_filters)?;
+    Ok((plan, route_client, skf_client))
+}
+
+/// Send the mutations of `plan`, stopping at the first one that fails.
+///
+/// Every removal, of a whole Route or of EUI pairs and Devaddr Ranges, is
+/// sent before any add, so an entry moving from one Route to another is
+/// never on both.
+pub async fn execute(
+    plan: RestorePlan,
+    route_client: &mut client::RouteClient,
+    skf_client: &mut client::SkfClient,
+    keypair: &Keypair,
+) -> Result {
+    let mut adds = vec![];
+    for route_plan in plan.routes {
+        let (euis, devaddrs) = (route_plan.euis, route_plan.devaddrs);
+        match route_plan.action {
+            RouteAction::Unchanged => continue,
+            RouteAction::Remove => {
+                let id = route_plan.route.id;
+                route_client
+                    .delete(&id, keypair)
+                    .await
+                    .with_context(|| format!("removing route {id}"))?;
+                continue;
+            }
+            RouteAction::Create => (),
+            RouteAction::Update => {
+                let id = route_plan.route.id.clone();
+                if !route_plan.fields.is_empty() {
+                    route_client
+                        .push(route_plan.route.clone(), keypair)
+                        .await
+                        .with_context(|| format!("updating route {id}"))?;
+                }
+                if !euis.remove.is_empty() {
+                    route_client
+                        .remove_euis(euis.remove, keypair)
+                        .await
+                        .with_context(|| format!("removing euis of route {id}"))?;
+                }
+                if !devaddrs.remove.is_empty() {
+                    route_client
+                        .remove_devaddrs(devaddrs.remove, keypair)
+                        .await
+                        .with_context(|| format!("removing devaddrs of route {id}"))?;
+                }
+            }
+        }
+        adds.push((route_plan.action, route_plan.route, euis.add, devaddrs.add));
+    }
+
+    for (action, route, mut euis, mut devaddrs) in adds {
+        let route_id = match action {
+            RouteAction::Create => {
+                let route = Route {
+                    id: RouteId::default(),
+                    ..route
+                };
+                let created = route_client
+                    .create_route(route, keypair)
+                    .await
+                    .context("creating route")?;
+                for eui in &mut euis {
+                    eui.route_id = created.id.clone();
+                }
+                for range in &mut devaddrs {
+                    range.route_id = created.id.clone();
+                }
+                created.id
+            }
+            _ => route.id,
+        };
+        if !euis.is_empty() {
+            route_client
+                .add_euis(euis, keypair)
+                .await
+                .with_context(|| format!("adding euis to route {route_id}"))?;
+        }
+        if !devaddrs.is_empty() {
+            route_client
+                .add_devaddrs(devaddrs, keypair)
+                .await
+                .with_context(|| format!("adding devaddrs to route {route_id}"))?;
+        }
+    }
+
+    if !plan.filters.remove.is_empty() {
+        skf_client
+            .remove_filters(plan.filters.remove, keypair)
+            .await
+            .context("removing session key filters")?;
+    }
+    if !plan.filters.add.is_empty() {
+        skf_client
+            .add_filters(plan.filters.add, keypair)
+            .await
+            .context("adding session key filters")?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,212 @@
+use super::{server_info, Doctor, PathBufKeypair};
+use crate::{client, ExitCode, Msg, Oui, Result};
+use helium_crypto::Keypair;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+    Passed,
+    Failed,
+    /// Not run for lack of input, which is not a failure.
+    Skipped,
+}
+
+struct Check {
+    name: &'static str,
+    outcome: Outcome,
+    detail: String,
+    hint: &'static str,
+}
+
+impl Check {
+    fn pass(name: &'static str, detail: String) -> Self {
+        Self {
+            name,
+            outcome: Outcome::Passed,
+            detail,
+            hint: "",
+        }
+    }
+
+    fn fail(name: &'static str, detail: String, hint: &'static str) -> Self {
+        Self {
+            name,
+            outcome: Outcome::Failed,
+            detail,
+            hint,
+        }
+    }
+
+    fn skip(name: &'static str, detail: String, hint: &'static str) -> Self {
+        Self {
+            name,
+            outcome: Outcome::Skipped,
+            detail,
+            hint,
+        }
+    }
+
+    fn report(&self) -> String {
+        match self.outcome {
+            Outcome::Passed => format!("\u{2713} {}: {}", self.name, self.detail),
+            Outcome::Failed => format!(
+                "\u{2717} {}: {}\n    hint: {}",
+                self.name, self.detail, self.hint
+            ),
+            Outcome::Skipped => format!(
+                "- {}: {} (skipped)\n    hint: {}",
+                self.name, self.detail, self.hint
+            ),
+        }
+    }
+}
+
+pub async fn doctor(args: Doctor) -> Result<Msg> {
+    let mut checks = vec![];
+
+    let keypair = match args.keypair.to_keypair() {
+        Ok(keypair) => {
+            checks.push(Check::pass(
+                "keypair",
+                format!("{} ({})", args.keypair.display(), keypair.public_key()),
+            ));
+            Some(keypair)
+        }
+        Err(err) => {
+            checks.push(Check::fail(
+                "keypair",
+                format!("{}: {err}", args.keypair.display()),
+                "create one with `env generate-keypair` or point HELIUM_KEYPAIR_BIN at an existing keypair",
+            ));
+            None
+        }
+    };
+
+    let transport = if args.config_host.starts_with("https://") {
+        "tls"
+    } else {
+        "plaintext"
+    };
+    // Clients connect on their first request, listing the Orgs needs no
+    // signature.
+    let org_client = match client::OrgClient::new(&args.config_host, &args.connection).await {
+        Ok(mut client) => client.list().await.map(|_| client),
+        Err(err) => Err(err),
+    };
+    let org_client = match org_client {
+        Ok(client) => {
+            checks.push(Check::pass(
+                "config_host",
+                format!("{} reachable ({transport})", args.config_host),
+            ));
+            checks.push(check_protocol(&args).await);
+            Some(client)
+        }
+        Err(err) => {
+            checks.push(Check::fail(
+                "config_host",
+                format!("{} unreachable ({transport}): {err}", args.config_host),
+                "check HELIUM_CONFIG_HOST, including the http:// or https:// scheme",
+            ));
+            None
+        }
+    };
+
+    match (org_client, &keypair, args.oui) {
+        (Some(mut org_client), Some(keypair), Some(oui)) => {
+            checks.push(check_ownership(&mut org_client, keypair, oui).await);
+            checks.push(check_signed_request(&args, keypair, oui).await);
+        }
+        (_, _, None) => checks.push(Check::skip(
+            "oui",
+            "unset, no ownership and signature checks".to_string(),
+            "pass `--oui` or set HELIUM_OUI",
+        )),
+        _ => (),
+    }
+
+    let all_passed = checks.iter().all(|check| check.outcome != Outcome::Failed);
+    let report = checks
+        .iter()
+        .map(Check::report)
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    if all_passed {
+        Msg::ok(format!("all checks passed\n{report}"))
+    } else {
+        Ok(Msg::Error(
+            format!("some checks failed\n{report}"),
+            ExitCode::Failure,
+        ))
+    }
+}
+
+async fn check_ownership(org_client: &mut client::OrgClient, keypair: &Keypair, oui: Oui) -> Check {
+    let public_key = keypair.public_key();
+    match org_client.get(oui).await {
+        Ok(res) if &res.org.owner == public_key => {
+            Check::pass("oui", format!("{oui} is owned by {public_key}"))
+        }
+        Ok(res) if res.org.delegate_keys.contains(public_key) => {
+            Check::pass("oui", format!("{oui} has {public_key} as a delegate"))
+        }
+        Ok(_) => Check::fail(
+            "oui",
+            format!("{public_key} is neither owner nor delegate of {oui}"),
+            "run `env info` to see which OUIs this keypair can administer",
+        ),
+        Err(err) => Check::fail(
+            "oui",
+            format!("could not get org {oui}: {err}"),
+            "make sure HELIUM_OUI refers to an existing org",
+        ),
+    }
+}
+
+/// The RPCs of the helium-proto revision this cli was built with, probed
+/// the same way as `server-info`.
+async fn check_protocol(args: &Doctor) -> Check {
+    let probes = match client::probe_rpcs(&args.config_host, &args.connection).await {
+        Ok(probes) => probes,
+        Err(err) => {
+            return Check::fail(
+                "proto",
+                format!("could not probe rpcs: {err}"),
+                "run `server-info` for the answer of each rpc",
+            )
+        }
+    };
+    let warnings: Vec<String> = probes
+        .iter()
+        .filter_map(|(rpc, status)| server_info::probe_warning(rpc, status))
+        .collect();
+    if warnings.is_empty() {
+        Check::pass(
+            "proto",
+            format!("all {} probed rpcs are compatible", probes.len()),
+        )
+    } else {
+        Check::fail(
+            "proto",
+            warnings.join("; "),
+            "upgrade the cli or the config service so both use the same helium-proto revision",
+        )
+    }
+}
+
+/// Signed requests carry a timestamp the config service checks for
+/// freshness, a rejected request is most often a skewed clock.
+async fn check_signed_request(args: &Doctor, keypair: &Keypair, oui: Oui) -> Check {
+    let result = match client::RouteClient::new(&args.config_host, &args.connection).await {
+        Ok(mut client) => client.list(oui, keypair).await,
+        Err(err) => Err(err),
+    };
+    match result {
+        Ok(_) => Check::pass("signature", "signed request accepted".to_string()),
+        Err(err) => Check::fail(
+            "signature",
+            format!("signed request rejected: {err}"),
+            "make sure the system clock is synchronized (e.g. with NTP)",
+        ),
+    }
+}
@@ -1,12 +1,13 @@
 use std::{env, fs, path::PathBuf};
 
 use super::{
-    EnvInfo, GenerateKeypair, ENV_CONFIG_HOST, ENV_KEYPAIR_BIN, ENV_MAX_COPIES, ENV_NET_ID, ENV_OUI,
+    EnvInfo, GenerateKeypair, PathBufKeypair, ENV_CONFIG_HOST, ENV_KEYPAIR_BIN, ENV_MAX_COPIES,
+    ENV_NET_ID, ENV_OUI,
 };
-use crate::{hex_field, Msg, Oui, PrettyJson, Result};
+use crate::{client, hex_field, Msg, OrgList, Oui, PrettyJson, Result};
 use anyhow::Context;
 use dialoguer::Input;
-use helium_crypto::Keypair;
+use helium_crypto::{Keypair, PublicKey};
 use rand::rngs::OsRng;
 use serde_json::json;
 
@@ -62,9 +63,10 @@ pub async fn env_init() -> Result<Msg> {
     Msg::ok(report.join("\n"))
 }
 
-pub fn env_info(args: EnvInfo) -> Result<Msg> {
+pub async fn env_info(args: EnvInfo) -> Result<Msg> {
     let env_keypair = env::var(ENV_KEYPAIR_BIN).ok().map(|i| i.into());
     let (env_keypair_location, env_public_key) = get_public_key_from_path(env_keypair);
+    let identity = verify_identity(&args).await;
     let (arg_keypair_location, arg_public_key) = get_public_key_from_path(args.keypair);
 
     let output = json!({
@@ -83,12 +85,70 @@ pub fn env_info(args: EnvInfo) -> Result<Msg> {
             "max_copies": args.max_copies,
             "keypair": arg_keypair_location,
             "public_key_from_keypair": arg_public_key
-        }
+        },
+        "identity": identity
     });
     Msg::ok(output.pretty_json()?)
 }
 
+/// Ask the config service which Orgs the configured keypair can administer.
+///
+/// Failures are reported in the returned value rather than as errors so
+/// `env info` can still print everything it was able to gather locally.
+pub async fn verify_identity(args: &EnvInfo) -> serde_json::Value {
+    let keypair = match args.keypair.as_ref().map(|path| path.to_keypair()) {
+        Some(Ok(keypair)) => keypair,
+        Some(Err(err)) => return json!({ "error": format!("could not read keypair: {err}") }),
+        None => return json!({ "error": "keypair unset" }),
+    };
+    let config_host = match args.config_host.as_deref() {
+        Some(host) if host != "unset" => host,
+        _ => return json!({ "error": "config_host unset" }),
+    };
+
+    let org_list = match client::OrgClient::new(config_host, &args.connection).await {
+        Ok(mut client) => client.list().await,
+        Err(err) => Err(err),
+    };
+    match org_list {
+        Ok(org_list) => identity(keypair.public_key(), args.oui, &org_list),
+        Err(err) => json!({ "error": format!("could not list orgs: {err}") }),
+    }
+}
+
+/// The Orgs of `org_list` that `public_key` owns or is a delegate of, and
+/// whether `oui` is one of them.
+fn identity(public_key: &PublicKey, oui: Option<Oui>, org_list: &OrgList) -> serde_json::Value {
+    let owned_ouis: Vec<Oui> = org_list
+        .orgs
+        .iter()
+        .filter(|org| &org.owner == public_key)
+        .map(|org| org.oui)
+        .collect();
+    let delegate_ouis: Vec<Oui> = org_list
+        .orgs
+        .iter()
+        .filter(|org| org.delegate_keys.contains(public_key))
+        .map(|org| org.oui)
+        .collect();
+    let oui_matches = oui.map(|oui| owned_ouis.contains(&oui) || delegate_ouis.contains(&oui));
+
+    json!({
+        "public_key": public_key.to_string(),
+        "owned_ouis": owned_ouis,
+        "delegate_ouis": delegate_ouis,
+        "oui_matches": oui_matches,
+    })
+}
+
 pub fn generate_keypair(args: GenerateKeypair) -> Result<Msg> {
+    if args.out_file.exists() && !args.commit {
+        return Msg::dry_run(format!(
+            "{} already exists and would be overwritten with a new keypair",
+            args.out_file.display()
+        ));
+    }
+
     let key = helium_crypto::Keypair::generate(
         helium_crypto::KeyTag {
             network: helium_crypto::Network::MainNet,
@@ -125,20 +185,25 @@ pub fn get_public_key_from_path(path: Option<PathBuf>) -> (String, String) {
 #[cfg(test)]
 mod tests {
 
+    use helium_crypto::{KeyTag, KeyType, Keypair, Network, PublicKey};
+    use rand::rngs::OsRng;
     use std::{env, fs};
     use temp_dir::TempDir;
 
     use crate::{
+        client::ConnectOpts,
         cmds::{
             self,
-            env::{env_info, generate_keypair, get_public_key_from_path},
+            env::{env_info, generate_keypair, get_public_key_from_path, identity},
             EnvInfo, GenerateKeypair,
         },
         hex_field,
+        vcr::Cassette,
+        Org, OrgList,
     };
 
-    #[test]
-    fn env_info_test() {
+    #[tokio::test]
+    async fn env_info_test() {
         // Make the keypairs to be referenced
         let dir = TempDir::new().unwrap();
         let env_keypair = dir.child("env-keypair.bin");
@@ -161,8 +226,15 @@ mod tests {
         env::set_var(cmds::ENV_MAX_COPIES, "42");
         env::set_var(cmds::ENV_KEYPAIR_BIN, env_keypair.clone());
 
+        // Replayed from an empty cassette, nothing is resolved or connected to.
+        let cassette = dir.child("cassette.json");
+        Cassette::default().write(&cassette).unwrap();
         let env_args = EnvInfo {
             config_host: Some("arg-localhost:1337".to_string()),
+            connection: ConnectOpts {
+                vcr_replay: Some(cassette),
+                ..ConnectOpts::default()
+            },
             keypair: Some(arg_keypair.clone()),
             net_id: Some(hex_field::net_id(42)),
             oui: Some(4),
@@ -170,7 +242,7 @@ mod tests {
         };
 
         // =======
-        let output = env_info(env_args).unwrap().into_inner();
+        let output = env_info(env_args).await.unwrap().into_inner();
         let s: serde_json::Value = serde_json::from_str(&output).unwrap();
 
         let env = &s["environment"];
@@ -195,6 +267,47 @@ mod tests {
         assert_eq!(arg["net_id"], "00002A");
         assert_eq!(arg["oui"], 4);
         assert_eq!(arg["max_copies"], 1337);
+
+        // The cassette has no org list to answer with, identity can only
+        // report why.
+        assert!(s["identity"]["error"].is_string());
+    }
+
+    #[test]
+    fn identity_ouis() {
+        let generate = || {
+            Keypair::generate(
+                KeyTag {
+                    network: Network::MainNet,
+                    key_type: KeyType::Ed25519,
+                },
+                &mut OsRng,
+            )
+            .public_key()
+            .clone()
+        };
+        let (mine, other) = (generate(), generate());
+        let org = |oui, owner: &PublicKey, delegate_keys| Org {
+            oui,
+            owner: owner.clone(),
+            payer: owner.clone(),
+            delegate_keys,
+            locked: false,
+        };
+        let org_list = OrgList {
+            orgs: vec![
+                org(1, &mine, vec![]),
+                org(2, &other, vec![mine.clone()]),
+                org(3, &other, vec![]),
+            ],
+        };
+
+        let delegate = identity(&mine, Some(2), &org_list);
+        assert_eq!(delegate["public_key"], mine.to_string());
+        assert_eq!(delegate["owned_ouis"], serde_json::json!([1]));
+        assert_eq!(delegate["delegate_ouis"], serde_json::json!([2]));
+        assert_eq!(delegate["oui_matches"], true);
+        assert_eq!(identity(&mine, Some(3), &org_list)["oui_matches"], false);
     }
 
     #[test]
@@ -0,0 +1,117 @@
+use super::{ImportChirpstack, ImportTts, PathBufKeypair};
+use crate::{chirpstack, client, split_present, tts, Msg, Result};
+use anyhow::Context;
+use std::fs;
+
+pub async fn import_chirpstack(args: ImportChirpstack) -> Result<Msg> {
+    let data = fs::read_to_string(&args.file)
+        .with_context(|| format!("reading {}", args.file.display()))?;
+    let columns = chirpstack::Columns {
+        dev_eui: args.dev_eui_col.clone(),
+        join_eui: args.app_eui_col.clone(),
+        devaddr: args.devaddr_col.clone(),
+        session_key: args.session_key_col.clone(),
+    };
+    let devices = match args.file.extension().and_then(|ext| ext.to_str()) {
+        Some("json") => chirpstack::from_json(&data, &columns),
+        _ => chirpstack::from_csv(&data, &columns),
+    }
+    .with_context(|| format!("parsing {}", args.file.display()))?;
+
+    let keypair = args.keypair.to_keypair()?;
+    let mut client = client::RouteClient::new(&args.config_host, &args.connection).await?;
+    let route = match client.get(&args.route_id, &keypair).await {
+        Ok(route) => route,
+        Err(err) => return Msg::failed("could not get route", err),
+    };
+    let euis = devices
+        .iter()
+        .map(|device| device.eui(&route.id))
+        .collect::<Result<Vec<_>>>()?;
+    let filters = devices
+        .iter()
+        .filter_map(|device| device.filter(route.oui))
+        .collect::<Vec<_>>();
+
+    let mut skf_client = client::SkfClient::new(&args.config_host, &args.connection).await?;
+    let (missing_euis, present_euis) =
+        split_present(euis, client.get_euis(&route.id, &keypair).await?);
+    let (missing_filters, present_filters) =
+        split_present(filters, skf_client.list_filters(route.oui, &keypair).await?);
+
+    let mut lines = vec![format!(
+        "{} devices: {} EUI pairs ({} already present), {} Session Key Filters ({} already present)",
+        devices.len(),
+        missing_euis.len(),
+        present_euis.len(),
+        missing_filters.len(),
+        present_filters.len()
+    )];
+    let without_keys = devices
+        .iter()
+        .filter(|device| device.session.is_none())
+        .count();
+    if without_keys > 0 {
+        lines.push(format!(
+            "warning: {without_keys} devices have no devaddr and session key, only their EUI pairs are imported"
+        ));
+    }
+    let summary = lines.join("\n");
+
+    if !args.commit {
+        return Msg::dry_run(format!("import to route {}\n{summary}", route.id));
+    }
+
+    if !missing_euis.is_empty() {
+        if let Err(err) = client.add_euis(missing_euis, &keypair).await {
+            return Msg::failed("EUI pairs not added", err);
+        }
+    }
+    if !missing_filters.is_empty() {
+        if let Err(err) = skf_client.add_filters(missing_filters, &keypair).await {
+            return Msg::failed("EUI pairs added, Session Key Filters not added", err);
+        }
+    }
+
+    Msg::ok(format!("imported to route {}\n{summary}", route.id))
+}
+
+pub async fn import_tts(args: ImportTts) -> Result<Msg> {
+    let json = fs::read_to_string(&args.file)
+        .with_context(|| format!("reading {}", args.file.display()))?;
+    let devices =
+        tts::from_json(&json).with_context(|| format!("parsing {}", args.file.display()))?;
+    let (euis, skipped) = tts::euis(&args.route_id, &devices)?;
+
+    let keypair = args.keypair.to_keypair()?;
+    let mut client = client::EuiClient::new(&args.config_host, &args.connection).await?;
+    let existing = client.get_euis(&args.route_id, &keypair).await?;
+    let (missing, present) = split_present(euis, existing);
+
+    let mut lines = vec![format!(
+        "{} end devices: {} EUI pairs ({} already present)",
+        devices.len(),
+        missing.len(),
+        present.len()
+    )];
+    if !skipped.is_empty() {
+        lines.push(format!(
+            "warning: skipped {} end devices without a DevEUI or JoinEUI: {}",
+            skipped.len(),
+            skipped.join(", ")
+        ));
+    }
+    let summary = lines.join("\n");
+
+    if !args.commit {
+        return Msg::dry_run(format!("import to route {}\n{summary}", args.route_id));
+    }
+
+    if !missing.is_empty() {
+        if let Err(err) = client.add_euis(missing, &keypair).await {
+            return Msg::failed("EUI pairs not added", err);
+        }
+    }
+
+    Msg::ok(format!("imported to route {}\n{summary}", args.route_id))
+}
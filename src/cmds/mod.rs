@@ -1,17 +1,34 @@
 use crate::{
-    hex_field::{self, HexNetID},
+    client::ConnectOpts,
+    hex_field::{self, HexCase, HexNetID, HexStyle},
+    output,
     region::Region,
-    DevaddrConstraint, Msg, Oui, PrettyJson, Result,
+    render::{OutputFormat, Template},
+    route::{validate_route_id, RouteId},
+    server::{self, FlowType},
+    subnet, DevaddrConstraint, Msg, Oui, PrettyJson, Result, RouteFilter, RouteSort,
 };
 use anyhow::Context;
-use clap::{Args, Parser, Subcommand};
+use clap::{ArgMatches, Args, Parser, Subcommand};
+use futures::{future, Future, Stream, StreamExt, TryFutureExt, TryStreamExt};
 use helium_crypto::PublicKey;
-use std::path::PathBuf;
+use serde::Serialize;
+use std::{
+    cell::Cell,
+    collections::BTreeMap,
+    io::{BufWriter, Write},
+    path::PathBuf,
+};
 
+pub mod apply;
+pub mod doctor;
 pub mod env;
+pub mod import;
 pub mod org;
+pub mod region;
 pub mod region_params;
 pub mod route;
+pub mod server_info;
 pub mod session_key_filter;
 
 pub const ENV_CONFIG_HOST: &str = "HELIUM_CONFIG_HOST";
@@ -19,6 +36,13 @@ pub const ENV_KEYPAIR_BIN: &str = "HELIUM_KEYPAIR_BIN";
 pub const ENV_NET_ID: &str = "HELIUM_NET_ID";
 pub const ENV_OUI: &str = "HELIUM_OUI";
 pub const ENV_MAX_COPIES: &str = "HELIUM_MAX_COPIES";
+pub const ENV_GWMP_PRESETS: &str = "HELIUM_GWMP_PRESETS";
+pub const ENV_GWMP_HISTORY: &str = "HELIUM_GWMP_HISTORY";
+pub const ENV_TLS_CA: &str = "HELIUM_TLS_CA";
+pub const ENV_CONNECT_TIMEOUT: &str = "HELIUM_CONNECT_TIMEOUT";
+pub const ENV_RPC_TIMEOUT: &str = "HELIUM_RPC_TIMEOUT";
+pub const ENV_KEEPALIVE_INTERVAL: &str = "HELIUM_KEEPALIVE_INTERVAL";
+pub const ENV_KEEPALIVE_TIMEOUT: &str = "HELIUM_KEEPALIVE_TIMEOUT";
 
 #[derive(Debug, Parser)]
 #[command(name = "helium-config-cli")]
@@ -27,6 +51,10 @@ pub struct Cli {
     #[command(subcommand)]
     pub command: Commands,
 
+    /// Config service to talk to.
+    ///
+    /// Accepts full uris, `host:port`, and bracketed or bare IPv6 literals.
+    /// Use `srv://<name>` to resolve the `_grpc._tcp.<name>` SRV record.
     #[arg(
         global = true,
         long,
@@ -42,6 +70,180 @@ pub struct Cli {
         default_value = "./keypair.bin"
     )]
     pub keypair: PathBuf,
+
+    /// PEM file of CA certificates to trust for the config host.
+    #[arg(global = true, long, alias = "tls-ca", env = ENV_TLS_CA)]
+    pub tls_ca_bundle: Option<PathBuf>,
+
+    /// PEM client certificate, for config hosts that require mutual TLS.
+    #[arg(global = true, long, requires = "tls_key")]
+    pub tls_cert: Option<PathBuf>,
+
+    /// PEM private key of `--tls-cert`.
+    #[arg(global = true, long, requires = "tls_cert")]
+    pub tls_key: Option<PathBuf>,
+
+    /// DANGEROUS: do not verify the config host TLS certificate.
+    ///
+    /// Anyone on the network path can impersonate the config service.
+    /// Only use this against a local test deployment.
+    #[arg(global = true, long, conflicts_with = "tls_cert")]
+    pub tls_insecure: bool,
+
+    /// Seconds to wait for the connection to the config host.
+    #[arg(
+        global = true,
+        long,
+        env = ENV_CONNECT_TIMEOUT,
+        default_value = "10",
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    pub connect_timeout: Option<u64>,
+
+    /// Seconds to wait for the config service to answer each request.
+    ///
+    /// Streamed responses only need to start within the timeout. Unset by
+    /// default, large updates can take the config service a while.
+    #[arg(
+        global = true,
+        long,
+        env = ENV_RPC_TIMEOUT,
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    pub rpc_timeout: Option<u64>,
+
+    /// Seconds between HTTP/2 keepalive pings while a request is open.
+    ///
+    /// Keeps long EUI and Session Key Filter streams alive through NATs and
+    /// load balancers that drop quiet connections. No pings when unset.
+    #[arg(
+        global = true,
+        long,
+        env = ENV_KEEPALIVE_INTERVAL,
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    pub keepalive_interval: Option<u64>,
+
+    /// Seconds to wait for the answer to a keepalive ping before the
+    /// connection is considered dead.
+    #[arg(
+        global = true,
+        long,
+        env = ENV_KEEPALIVE_TIMEOUT,
+        default_value = "20",
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    pub keepalive_timeout: Option<u64>,
+
+    /// Retry requests that fail with Unavailable, DeadlineExceeded or a reset
+    /// connection this many times, waiting longer before each retry.
+    ///
+    /// Creating an org or a route, or deleting a route or all of its EUI
+    /// pairs or Devaddr Ranges, is never retried, the first attempt may have
+    /// been applied.
+    #[arg(global = true, long, default_value_t = 3)]
+    pub max_retries: u32,
+
+    /// Append every signed mutating request to this file before sending it.
+    ///
+    /// One JSON line per request with the rpc, signer public key, timestamp,
+    /// and the base64 protobuf bytes, signature included.
+    #[arg(global = true, long)]
+    pub dump_request: Option<PathBuf>,
+
+    /// Sign requests with this timestamp, in milliseconds since the epoch,
+    /// instead of the current time.
+    ///
+    /// For testing how a config service handles stale or future requests.
+    #[arg(global = true, long)]
+    pub timestamp: Option<u64>,
+
+    /// Refuse to sign when `--timestamp` is more than this many milliseconds
+    /// away from the local clock.
+    #[arg(global = true, long, requires = "timestamp")]
+    pub timestamp_skew_tolerance: Option<u64>,
+
+    /// Record every request and response to this cassette file.
+    #[arg(global = true, long, conflicts_with = "vcr_replay")]
+    pub vcr_record: Option<PathBuf>,
+
+    /// Answer requests from a cassette file recorded with `--vcr-record`
+    /// instead of the config service.
+    ///
+    /// Fails when a request differs from the recorded one. Use the keypair
+    /// and `--timestamp` of the recording so signatures match.
+    #[arg(global = true, long)]
+    pub vcr_replay: Option<PathBuf>,
+
+    /// Append the raw protobuf responses of the config service to this file.
+    ///
+    /// Written as the gRPC frames they arrive in, a flag byte and a 4 byte
+    /// big-endian length before each encoded message, for debugging the
+    /// difference between the wire format and the JSON output.
+    #[arg(global = true, long)]
+    pub proto_out: Option<PathBuf>,
+
+    /// Print output as versioned single-line JSON for scripts.
+    ///
+    /// The shape of this output is stable between releases.
+    #[arg(global = true, long)]
+    pub porcelain: bool,
+
+    /// Print only the essential values of the output, the route id of a
+    /// created route or the ouis of listed orgs, and errors on stderr.
+    ///
+    /// The exit code tells what happened: 0 success, 1 any other failure,
+    /// 2 not found, 3 unauthorized, 4 invalid request.
+    #[arg(global = true, short, long, conflicts_with = "porcelain")]
+    pub quiet: bool,
+
+    /// Case of hex devaddrs, EUIs, and net ids in output.
+    #[arg(global = true, long, value_enum, default_value_t = HexCase::Upper)]
+    pub hex_case: HexCase,
+
+    /// Layout of hex devaddrs, EUIs, and net ids in output, for tools that
+    /// expect a `0x` prefix or colon-separated bytes.
+    #[arg(global = true, long, value_enum, default_value_t = HexStyle::Plain)]
+    pub hex_style: HexStyle,
+
+    /// How to print output: JSON, an aligned table, CSV, or YAML, or the
+    /// subnets of the subnet mask commands as `cidr` lines.
+    ///
+    /// Lists become one row per entry. Ignored with `--porcelain`.
+    #[arg(global = true, long, value_enum, default_value_t = OutputFormat::Json)]
+    pub format: OutputFormat,
+
+    /// Print each entry of a list through a template instead, e.g.
+    /// `{{ route.id }} {{ route.server.host }}`.
+    ///
+    /// The entry is named `route`, `org`, `eui`, `devaddr`, or `filter`.
+    /// Takes precedence over `--format`.
+    #[arg(global = true, long)]
+    pub template: Option<String>,
+
+    /// Write the output to this file instead of stdout, creating its parent
+    /// directories.
+    #[arg(global = true, long)]
+    pub out: Option<PathBuf>,
+
+    /// Overwrite the `--out` file if it already exists.
+    #[arg(global = true, long, requires = "out")]
+    pub force: bool,
+
+    /// Name JSON output fields as protobuf JSON does, `routeId` rather than `route_id`.
+    #[arg(global = true, long)]
+    pub proto_names: bool,
+
+    /// Accept route ids that are not UUIDs.
+    ///
+    /// For config services that assign route ids in another format.
+    #[arg(global = true, long)]
+    pub allow_any_id: bool,
+
+    /// Apply the change. Without it every mutating command only prints a
+    /// preview of what it would do.
+    #[arg(global = true, short, long)]
+    pub commit: bool,
 }
 
 #[derive(Debug, Subcommand)]
@@ -71,11 +273,36 @@ pub enum Commands {
     },
     /// Print a Subnet Mask for a given Devaddr Range
     SubnetMask(SubnetMask),
+    /// Work out Devaddr Ranges before assigning them
+    Subnet {
+        #[command(subcommand)]
+        command: SubnetCommands,
+    },
+    /// Regions
+    Region {
+        #[command(subcommand)]
+        command: RegionCommands,
+    },
     /// Region Params
     RegionParams {
         #[command(subcommand)]
         command: RegionParamsCommands,
     },
+    /// Check your environment for common problems
+    Doctor(Doctor),
+    /// Check which config service RPCs the config host supports
+    ServerInfo(ServerInfo),
+    /// Converge an OUI to a desired state file, previewing the plan first
+    ///
+    /// The file describes the Routes with their EUI pairs and Devaddr
+    /// Ranges, and the Session Key Filters, in JSON, YAML, or TOML. Routes
+    /// not in the file are removed, Routes with an empty id are created.
+    Apply(Apply),
+    /// Import devices from other LoRaWAN Network Servers
+    Import {
+        #[command(subcommand)]
+        command: ImportCommands,
+    },
 }
 
 #[derive(Debug, Subcommand)]
@@ -83,6 +310,9 @@ pub enum EnvCommands {
     /// Make Environment variables to ease repeated use
     Init,
     /// View information about your environment
+    ///
+    /// The keypair is checked against the config service to report
+    /// which OUIs it can administer.
     Info(EnvInfo),
     /// Make a new keypair
     GenerateKeypair(GenerateKeypair),
@@ -123,28 +353,110 @@ pub enum RouteCommands {
     /// the route field `locked` supersedes this setting.
     #[command(alias = "disable")]
     Deactivate(DeactivateRoute),
+    /// Print the JSON Schema of Route files
+    ///
+    /// For validating route files in CI before they are pushed.
+    Schema,
+    /// Write a Route with its EUI pairs and Devaddr Ranges, use `--out` for
+    /// a file
+    Export(ExportRoute),
+    /// Create a Route with the EUI pairs and Devaddr Ranges of an export
+    ///
+    /// The Route gets a new id, the OUI is the one recorded in the file.
+    Import(ImportRoute),
+    /// Compare a Route file with the Route on the config service
+    ///
+    /// `old` is the remote value of a field and `new` the one in the file.
+    /// EUI pairs and Devaddr Ranges are compared when the file is a `route
+    /// export`.
+    Diff(DiffRoute),
+    /// Reconcile a directory of Route files with the Routes of an OUI
+    ///
+    /// Routes changed only on the config service are written to their file,
+    /// Routes changed only in their file are pushed. Routes changed on both
+    /// sides are reported as conflicts and left alone. The version of the
+    /// last sync is kept in `<dir>/.sync`.
+    Sync(SyncRoutes),
+    /// Copy or move the EUI pairs and Devaddr Ranges of a Route to another
+    ///
+    /// Both by default. If a step fails the steps before it are undone.
+    Migrate(MigrateRoute),
+    /// Validate and push every Route file in a directory
+    ///
+    /// Nothing is pushed unless every file is valid. Each file is reported
+    /// as pushed or failed.
+    PushAll(PushAllRoutes),
+    /// Move a Route with its EUIs, Devaddrs, and Session Key Filters between
+    /// config hosts as a tar archive
+    Bundle {
+        #[command(subcommand)]
+        command: BundleCommands,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum BundleCommands {
+    /// Write a tar archive of a Route, use `--out` for a file
+    ///
+    /// The archive holds route.json, euis.csv, devaddrs.csv, and skfs.json,
+    /// the Session Key Filters inside the Route's Devaddr Ranges.
+    Export(ExportBundle),
+    /// Create a Route from a tar archive, with its EUIs, Devaddrs, and
+    /// Session Key Filters
+    ///
+    /// The Route gets a new id, the OUI is the one recorded in route.json.
+    /// Filters already present on the Org are skipped.
+    Import(ImportBundle),
 }
 
 #[derive(Debug, Args)]
 pub struct ListRoutes {
     #[arg(long, env = ENV_OUI)]
     pub oui: Oui,
+    /// Print only Route IDs, one per line
+    #[arg(long)]
+    pub ids: bool,
+    /// Print only these fields of each Route, e.g. `id,max_copies,server.host`
+    #[arg(long, value_delimiter = ',')]
+    pub fields: Vec<String>,
+    #[arg(long, value_enum)]
+    pub sort_by: Option<RouteSort>,
+    /// Only list Routes matching `key=value`, for the keys active, locked,
+    /// protocol, host, and max_copies. Repeat to match all of them.
+    #[arg(long)]
+    pub filter: Vec<RouteFilter>,
+    /// Add the number of EUI pairs and Devaddr Ranges of each Route to
+    /// `--format table`, at the cost of streaming both for every Route
+    #[arg(long)]
+    pub with_counts: bool,
+    #[arg(from_global)]
+    pub format: OutputFormat,
+    #[arg(from_global)]
+    pub template: Option<String>,
     #[arg(from_global)]
     pub keypair: PathBuf,
     #[arg(from_global)]
     pub config_host: String,
-    #[arg(long)]
+    #[command(flatten)]
+    pub connection: ConnectOpts,
+    #[arg(from_global)]
     pub commit: bool,
 }
 
 #[derive(Debug, Args)]
 pub struct GetRoute {
-    #[arg(short, long)]
-    pub route_id: String,
+    #[arg(short, long, value_parser = validate_route_id)]
+    pub route_id: RouteId,
+    /// Print a short description of the Route instead of its JSON, with its
+    /// EUI count and Devaddr Ranges
+    #[arg(long)]
+    pub summary: bool,
     #[arg(from_global)]
     pub keypair: PathBuf,
     #[arg(from_global)]
     pub config_host: String,
+    #[command(flatten)]
+    pub connection: ConnectOpts,
 }
 
 #[derive(Debug, Args)]
@@ -160,43 +472,187 @@ pub struct NewRoute {
     pub keypair: PathBuf,
     #[arg(from_global)]
     pub config_host: String,
+    #[command(flatten)]
+    pub connection: ConnectOpts,
+    #[arg(from_global)]
+    pub commit: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct ExportRoute {
+    #[arg(short, long, value_parser = validate_route_id)]
+    pub route_id: RouteId,
+    /// Length-delimited protobuf instead of JSON
+    #[arg(long)]
+    pub proto: bool,
+    #[arg(from_global)]
+    pub keypair: PathBuf,
+    #[arg(from_global)]
+    pub config_host: String,
+    #[command(flatten)]
+    pub connection: ConnectOpts,
+}
+
+#[derive(Debug, Args)]
+pub struct ImportRoute {
+    #[arg(short, long)]
+    pub file: PathBuf,
+    /// The file was exported with `--proto`
     #[arg(long)]
+    pub proto: bool,
+    #[arg(from_global)]
+    pub keypair: PathBuf,
+    #[arg(from_global)]
+    pub config_host: String,
+    #[command(flatten)]
+    pub connection: ConnectOpts,
+    #[arg(from_global)]
     pub commit: bool,
 }
 
 #[derive(Debug, Args)]
-pub struct DeleteRoute {
+pub struct ExportBundle {
+    #[arg(short, long, value_parser = validate_route_id)]
+    pub route_id: RouteId,
+    #[arg(from_global)]
+    pub keypair: PathBuf,
+    #[arg(from_global)]
+    pub config_host: String,
+    #[command(flatten)]
+    pub connection: ConnectOpts,
+}
+
+#[derive(Debug, Args)]
+pub struct ImportBundle {
     #[arg(short, long)]
-    pub route_id: String,
+    pub file: PathBuf,
     #[arg(from_global)]
     pub keypair: PathBuf,
     #[arg(from_global)]
     pub config_host: String,
-    #[arg(long)]
+    #[command(flatten)]
+    pub connection: ConnectOpts,
+    #[arg(from_global)]
     pub commit: bool,
 }
 
 #[derive(Debug, Args)]
-pub struct ActivateRoute {
+pub struct DiffRoute {
+    /// Route file, JSON, YAML, or TOML, or the JSON of `route export`
     #[arg(short, long)]
-    pub route_id: String,
+    pub file: PathBuf,
+    /// Remote Route to compare with, the id in the file by default
+    #[arg(short, long, value_parser = validate_route_id)]
+    pub route_id: Option<RouteId>,
+    #[arg(from_global)]
+    pub keypair: PathBuf,
+    #[arg(from_global)]
+    pub config_host: String,
+    #[command(flatten)]
+    pub connection: ConnectOpts,
+}
+
+#[derive(Debug, Args)]
+pub struct SyncRoutes {
+    #[arg(long, env = ENV_OUI)]
+    pub oui: Oui,
+    /// Directory of Route files, JSON, YAML, or TOML
+    #[arg(long)]
+    pub dir: PathBuf,
     #[arg(from_global)]
     pub keypair: PathBuf,
     #[arg(from_global)]
     pub config_host: String,
+    #[command(flatten)]
+    pub connection: ConnectOpts,
+    #[arg(from_global)]
+    pub commit: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct MigrateRoute {
+    /// Route to take the EUI pairs and Devaddr Ranges from
+    #[arg(long, value_parser = validate_route_id)]
+    pub from: RouteId,
+    /// Route to add them to
+    #[arg(long, value_parser = validate_route_id)]
+    pub to: RouteId,
+    /// Only the EUI pairs, unless `--devaddrs` is also given
+    #[arg(long)]
+    pub euis: bool,
+    /// Only the Devaddr Ranges, unless `--euis` is also given
+    #[arg(long)]
+    pub devaddrs: bool,
+    /// Remove them from `--from` once they are on `--to`
+    #[arg(long = "move")]
+    pub remove_source: bool,
+    #[arg(from_global)]
+    pub keypair: PathBuf,
+    #[arg(from_global)]
+    pub config_host: String,
+    #[command(flatten)]
+    pub connection: ConnectOpts,
+    #[arg(from_global)]
+    pub commit: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct PushAllRoutes {
+    /// Directory of Route files, JSON, YAML, or TOML
     #[arg(long)]
+    pub dir: PathBuf,
+    /// Number of Routes pushed at the same time
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u16).range(1..))]
+    pub jobs: u16,
+    #[arg(from_global)]
+    pub keypair: PathBuf,
+    #[arg(from_global)]
+    pub config_host: String,
+    #[command(flatten)]
+    pub connection: ConnectOpts,
+    #[arg(from_global)]
+    pub commit: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct DeleteRoute {
+    #[arg(short, long, value_parser = validate_route_id)]
+    pub route_id: RouteId,
+    #[arg(from_global)]
+    pub keypair: PathBuf,
+    #[arg(from_global)]
+    pub config_host: String,
+    #[command(flatten)]
+    pub connection: ConnectOpts,
+    #[arg(from_global)]
+    pub commit: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct ActivateRoute {
+    #[arg(short, long, value_parser = validate_route_id)]
+    pub route_id: RouteId,
+    #[arg(from_global)]
+    pub keypair: PathBuf,
+    #[arg(from_global)]
+    pub config_host: String,
+    #[command(flatten)]
+    pub connection: ConnectOpts,
+    #[arg(from_global)]
     pub commit: bool,
 }
 
 #[derive(Debug, Args)]
 pub struct DeactivateRoute {
-    #[arg(short, long)]
-    pub route_id: String,
+    #[arg(short, long, value_parser = validate_route_id)]
+    pub route_id: RouteId,
     #[arg(from_global)]
     pub keypair: PathBuf,
     #[arg(from_global)]
     pub config_host: String,
-    #[arg(long)]
+    #[command(flatten)]
+    pub connection: ConnectOpts,
+    #[arg(from_global)]
     pub commit: bool,
 }
 
@@ -215,28 +671,33 @@ pub enum RouteUpdateCommand {
     /// Remove a region mapping from the Gwmp Protocol.
     /// This only works if the protocol is already gwmp.
     RemoveGwmpRegion(RemoveGwmpRegion),
+    /// Set the Route Protocol to Gwmp (UDP) with a generated region mapping.
+    /// Any existing region mapping is replaced.
+    Gwmp(UpdateGwmp),
     /// Set the Route Protocol to PacketRouter (GRPC)
     PacketRouter(UpdatePacketRouter),
 }
 
 #[derive(Debug, Args)]
 pub struct UpdateMaxCopies {
-    #[arg(short, long)]
-    pub route_id: String,
+    #[arg(short, long, value_parser = validate_route_id)]
+    pub route_id: RouteId,
     #[arg(short, long)]
     pub max_copies: u32,
     #[arg(from_global)]
     pub keypair: PathBuf,
     #[arg(from_global)]
     pub config_host: String,
-    #[arg(long)]
+    #[command(flatten)]
+    pub connection: ConnectOpts,
+    #[arg(from_global)]
     pub commit: bool,
 }
 
 #[derive(Debug, Args)]
 pub struct UpdateServer {
-    #[arg(short, long)]
-    pub route_id: String,
+    #[arg(short, long, value_parser = validate_route_id)]
+    pub route_id: RouteId,
     #[arg(long)]
     pub host: String,
     #[arg(long)]
@@ -245,65 +706,122 @@ pub struct UpdateServer {
     pub keypair: PathBuf,
     #[arg(from_global)]
     pub config_host: String,
-    #[arg(long)]
+    #[command(flatten)]
+    pub connection: ConnectOpts,
+    #[arg(from_global)]
     pub commit: bool,
 }
 
 #[derive(Debug, Args)]
 pub struct UpdateHttp {
+    #[arg(short, long, value_parser = validate_route_id)]
+    pub route_id: RouteId,
+    /// Defaults to async when switching to http
+    #[arg(long, value_enum)]
+    pub flow_type: Option<FlowType>,
+    /// Defaults to 250 when switching to http
     #[arg(short, long)]
-    pub route_id: String,
-    #[arg(short, long, default_value = "250")]
-    pub dedupe_timeout: u32,
+    pub dedupe_timeout: Option<u32>,
     /// Just the path part of the Server URL
     ///
-    /// The rest will be taken from the Server {host}:{port}
+    /// The rest will be taken from the Server {host}:{port}.
+    /// Required when switching to http.
     #[arg(short, long)]
-    pub path: String,
-    /// Authorization Header
+    pub path: Option<String>,
+    /// Authorization Header, pass "" to remove it
     #[arg(short, long)]
     pub auth_header: Option<String>,
 
+    /// Where gwmp mappings are kept while a route uses another protocol
+    #[arg(long, env = ENV_GWMP_HISTORY, default_value_os_t = server::default_gwmp_history())]
+    pub gwmp_history: PathBuf,
+
     #[arg(from_global)]
     pub keypair: PathBuf,
     #[arg(from_global)]
     pub config_host: String,
-    #[arg(long)]
+    #[command(flatten)]
+    pub connection: ConnectOpts,
+    #[arg(from_global)]
     pub commit: bool,
 }
 
 #[derive(Debug, Args)]
 pub struct UpdatePacketRouter {
-    #[arg(short, long)]
-    pub route_id: String,
+    #[arg(short, long, value_parser = validate_route_id)]
+    pub route_id: RouteId,
+    /// Where gwmp mappings are kept while a route uses another protocol
+    #[arg(long, env = ENV_GWMP_HISTORY, default_value_os_t = server::default_gwmp_history())]
+    pub gwmp_history: PathBuf,
+
     #[arg(from_global)]
     pub keypair: PathBuf,
     #[arg(from_global)]
     pub config_host: String,
-    #[arg(long)]
+    #[command(flatten)]
+    pub connection: ConnectOpts,
+    #[arg(from_global)]
     pub commit: bool,
 }
 
 #[derive(Debug, Args)]
 pub struct AddGwmpRegion {
-    #[arg(short, long)]
-    pub route_id: String,
+    #[arg(short, long, value_parser = validate_route_id)]
+    pub route_id: RouteId,
     #[arg(value_enum)]
     pub region: Region,
     pub region_port: u32,
 
+    /// Where gwmp mappings are kept while a route uses another protocol
+    #[arg(long, env = ENV_GWMP_HISTORY, default_value_os_t = server::default_gwmp_history())]
+    pub gwmp_history: PathBuf,
+
     #[arg(from_global)]
     pub keypair: PathBuf,
     #[arg(from_global)]
     pub config_host: String,
+    #[command(flatten)]
+    pub connection: ConnectOpts,
+    #[arg(from_global)]
+    pub commit: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct UpdateGwmp {
+    #[arg(short, long, value_parser = validate_route_id)]
+    pub route_id: RouteId,
+    /// Map every supported region, ports are assigned
+    /// `base_port + (step * n)` in the order regions are listed in `--help`.
+    #[arg(long, conflicts_with = "preset")]
+    pub all_regions: bool,
+    #[arg(long, default_value = "1700")]
+    pub base_port: u32,
+    #[arg(long, default_value = "1")]
+    pub step: u32,
+    /// Use a named region mapping.
+    ///
+    /// Builtin presets are `chirpstack-default`, `sequential` and
+    /// `single-port`.
     #[arg(long)]
+    pub preset: Option<String>,
+    /// JSON file of user defined presets, `{"name": {"US915": 1700}}`
+    #[arg(long, env = ENV_GWMP_PRESETS)]
+    pub presets_file: Option<PathBuf>,
+
+    #[arg(from_global)]
+    pub keypair: PathBuf,
+    #[arg(from_global)]
+    pub config_host: String,
+    #[command(flatten)]
+    pub connection: ConnectOpts,
+    #[arg(from_global)]
     pub commit: bool,
 }
 
 #[derive(Debug, Args)]
 pub struct RemoveGwmpRegion {
-    #[arg(short, long)]
-    pub route_id: String,
+    #[arg(short, long, value_parser = validate_route_id)]
+    pub route_id: RouteId,
     #[arg(value_enum)]
     pub region: Region,
 
@@ -311,7 +829,9 @@ pub struct RemoveGwmpRegion {
     pub keypair: PathBuf,
     #[arg(from_global)]
     pub config_host: String,
-    #[arg(long)]
+    #[command(flatten)]
+    pub connection: ConnectOpts,
+    #[arg(from_global)]
     pub commit: bool,
 }
 
@@ -323,8 +843,14 @@ pub enum EuiCommands {
     Add(AddEui),
     /// Remove EUI pair from Route
     Remove(RemoveEui),
+    /// Move EUI pairs from one Route to another
+    Move(MoveEuis),
     /// Remove ALL EUI Pairs from Route
     Clear(ClearEuis),
+    /// Make the EUI pairs of a Route match a file
+    Sync(SyncEuis),
+    /// Write the EUI pairs of a Route as devices for another network server
+    Export(ExportEuis),
 }
 
 #[derive(Debug, Subcommand)]
@@ -335,10 +861,21 @@ pub enum DevaddrCommands {
     Add(AddDevaddr),
     /// Remove Devaddr Range from Route
     Remove(RemoveDevaddr),
+    /// Move a Devaddr Range from one Route to another
+    Move(MoveDevaddr),
     /// Print subnet mask for all devaddr ranges in a Route.
     SubnetMask(RouteSubnetMask),
     /// Remove ALL Devaddr Ranges from Route
     Clear(ClearDevaddrs),
+    /// Make the Devaddr Ranges of a Route match a file
+    Sync(SyncDevaddrs),
+    /// Draw the devaddr space of an Org and the Routes it is assigned to
+    ///
+    /// Each Org constraint is a bar, unassigned devaddrs and ones held by
+    /// more than one Route stand out.
+    Map(DevaddrMap),
+    /// Split a Devaddr Range into one part per Route and add each part
+    Split(SplitDevaddrs),
 }
 
 #[derive(Debug, Subcommand)]
@@ -347,6 +884,18 @@ pub enum OrgCommands {
     List(ListOrgs),
     /// Get an Organization you own
     Get(GetOrg),
+    /// Markdown report of an Org with its Routes, EUIs, Devaddrs, and
+    /// Session Key Filters
+    ///
+    /// Session keys are left out, only their number per devaddr is shown.
+    Report(OrgReport),
+    /// Restore an Org to a snapshot directory, previewing the plan first
+    ///
+    /// The directory holds `routes/*.json` written by `route export` and
+    /// `skfs.json` written by `skf export`. Routes are created, updated, or
+    /// removed to match it, with their EUIs, Devaddrs, and Session Key
+    /// Filters.
+    Restore(RestoreOrg),
     /// Create a new Helium Organization
     CreateHelium(CreateHelium),
     /// Create a new Roaming Organization (admin only)
@@ -359,16 +908,36 @@ pub enum SessionKeyFilterCommands {
     Get(GetFilters),
     Add(AddFilter),
     Remove(RemoveFilter),
+    /// Remove all Session Key Filters inside a Devaddr Range
+    RemoveRange(RemoveFilterRange),
+    /// Replace the session key of a Session Key Filter
+    Replace(ReplaceFilter),
+    /// Write all Session Key Filters of an Org to a file
+    Export(ExportFilters),
+    /// Add Session Key Filters from a file written by `export`
+    Import(ImportFilters),
 }
 
 #[derive(Debug, Args)]
 pub struct ListFilters {
     #[arg(long, env = ENV_OUI)]
     pub oui: Oui,
+    /// Print only the number of filters
+    #[arg(long)]
+    pub count: bool,
+    /// With `--count`, the number of filters per devaddr
+    #[arg(long, requires = "count")]
+    pub by_devaddr: bool,
+    #[arg(from_global)]
+    pub format: OutputFormat,
+    #[arg(from_global)]
+    pub template: Option<String>,
     #[arg(from_global)]
     pub keypair: PathBuf,
     #[arg(from_global)]
     pub config_host: String,
+    #[command(flatten)]
+    pub connection: ConnectOpts,
 }
 
 #[derive(Debug, Args)]
@@ -381,123 +950,407 @@ pub struct GetFilters {
     pub keypair: PathBuf,
     #[arg(from_global)]
     pub config_host: String,
+    #[command(flatten)]
+    pub connection: ConnectOpts,
 }
 
 #[derive(Debug, Args)]
-pub struct AddFilter {
+pub struct ReplaceFilter {
     #[arg(long, env = ENV_OUI)]
     pub oui: Oui,
     #[arg(short, long, value_parser = hex_field::validate_devaddr)]
     pub devaddr: hex_field::HexDevAddr,
-    #[arg(short, long)]
-    pub session_key: String,
+    #[arg(long)]
+    pub old_key: String,
+    #[arg(long)]
+    pub new_key: String,
     #[arg(from_global)]
     pub config_host: String,
+    #[command(flatten)]
+    pub connection: ConnectOpts,
     #[arg(from_global)]
     pub keypair: PathBuf,
-    /// Add EUI entry to a Route
-    #[arg(short, long)]
+    #[arg(from_global)]
     pub commit: bool,
 }
 
 #[derive(Debug, Args)]
-pub struct RemoveFilter {
+pub struct RemoveFilterRange {
     #[arg(long, env = ENV_OUI)]
     pub oui: Oui,
-    #[arg(short, long, value_parser = hex_field::validate_devaddr)]
-    pub devaddr: hex_field::HexDevAddr,
-    #[arg(short, long)]
-    pub session_key: String,
+    #[arg(short, long, alias = "start", value_parser = hex_field::validate_devaddr)]
+    pub start_addr: hex_field::HexDevAddr,
+    #[arg(short, long, alias = "end", value_parser = hex_field::validate_devaddr)]
+    pub end_addr: hex_field::HexDevAddr,
+    /// Update streams open at once. Filters are removed in chunks of 5000,
+    /// with more than 1 in no particular order
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u16).range(1..))]
+    pub parallel: u16,
     #[arg(from_global)]
     pub config_host: String,
+    #[command(flatten)]
+    pub connection: ConnectOpts,
     #[arg(from_global)]
     pub keypair: PathBuf,
-    /// Add EUI entry to a Route
-    #[arg(short, long)]
+    #[arg(from_global)]
     pub commit: bool,
 }
 
 #[derive(Debug, Args)]
-pub struct ListEuis {
-    #[arg(short, long)]
-    pub route_id: String,
+pub struct ExportFilters {
+    #[arg(long, env = ENV_OUI)]
+    pub oui: Oui,
     #[arg(from_global)]
     pub keypair: PathBuf,
     #[arg(from_global)]
     pub config_host: String,
+    #[command(flatten)]
+    pub connection: ConnectOpts,
 }
 
 #[derive(Debug, Args)]
-pub struct AddEui {
-    #[arg(short, long, value_parser = hex_field::validate_eui)]
-    pub dev_eui: hex_field::HexEui,
-    #[arg(short, long, value_parser = hex_field::validate_eui)]
-    pub app_eui: hex_field::HexEui,
+pub struct ImportFilters {
+    /// Filters are added to the OUI recorded in the file
+    #[arg(short, long, required_unless_present = "from_file")]
+    pub file: Option<PathBuf>,
+    /// JSON list of {"devaddr", "session_key"} to add to `--oui`, sent in
+    /// chunks
+    #[arg(long, conflicts_with = "file", requires = "oui")]
+    pub from_file: Option<PathBuf>,
+    #[arg(long, env = ENV_OUI)]
+    pub oui: Option<Oui>,
+    /// Continue an interrupted import from the checkpoint next to the file
     #[arg(long)]
-    pub route_id: String,
+    pub resume: bool,
+    /// Update streams open at once. Chunks are handed out in file order, but
+    /// with more than 1 a later chunk can be applied before an earlier one
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u16).range(1..))]
+    pub parallel: u16,
+    #[arg(from_global)]
+    pub keypair: PathBuf,
     #[arg(from_global)]
     pub config_host: String,
+    #[command(flatten)]
+    pub connection: ConnectOpts,
     #[arg(from_global)]
-    pub keypair: PathBuf,
-    /// Add EUI entry to a Route
-    #[arg(short, long)]
     pub commit: bool,
 }
 
 #[derive(Debug, Args)]
-pub struct RemoveEui {
-    #[arg(short, long, value_parser = hex_field::validate_eui)]
-    pub dev_eui: hex_field::HexEui,
-    #[arg(short, long, value_parser = hex_field::validate_eui)]
-    pub app_eui: hex_field::HexEui,
-    #[arg(long)]
-    pub route_id: String,
+pub struct AddFilter {
+    #[arg(long, env = ENV_OUI)]
+    pub oui: Oui,
+    #[arg(short, long, value_parser = hex_field::validate_devaddr)]
+    pub devaddr: hex_field::HexDevAddr,
+    #[arg(short, long)]
+    pub session_key: String,
     #[arg(from_global)]
     pub config_host: String,
+    #[command(flatten)]
+    pub connection: ConnectOpts,
     #[arg(from_global)]
     pub keypair: PathBuf,
-    /// Remove EUI entry from the Route
-    #[arg(short, long)]
+    #[arg(from_global)]
     pub commit: bool,
 }
 
 #[derive(Debug, Args)]
-pub struct ClearEuis {
+pub struct RemoveFilter {
+    #[arg(long, env = ENV_OUI)]
+    pub oui: Oui,
+    #[arg(short, long, value_parser = hex_field::validate_devaddr)]
+    pub devaddr: hex_field::HexDevAddr,
     #[arg(short, long)]
-    pub route_id: String,
+    pub session_key: String,
+    #[arg(from_global)]
+    pub config_host: String,
+    #[command(flatten)]
+    pub connection: ConnectOpts,
+    #[arg(from_global)]
+    pub keypair: PathBuf,
+    #[arg(from_global)]
+    pub commit: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct ListEuis {
+    #[arg(short, long, value_parser = validate_route_id)]
+    pub route_id: RouteId,
+    /// Only list EUI pairs with this App EUI
+    #[arg(short, long, value_parser = hex_field::validate_eui)]
+    pub app_eui: Option<hex_field::HexEui>,
+    /// Only list EUI pairs with this Dev EUI
+    #[arg(short, long, value_parser = hex_field::validate_eui)]
+    pub dev_eui: Option<hex_field::HexEui>,
+    /// Only list wildcard pairs, whose Dev EUI of 0 matches every device of
+    /// the App EUI
+    #[arg(long, conflicts_with = "dev_eui")]
+    pub wildcards_only: bool,
+    /// Print only the number of EUI pairs
+    #[arg(long)]
+    pub count: bool,
+    /// With `--count`, the number of EUI pairs per App EUI
+    #[arg(long, requires = "count")]
+    pub by_app_eui: bool,
+    /// `--format csv` streams `app_eui,dev_eui` rows, the input of `euis sync`
+    #[arg(from_global)]
+    pub format: OutputFormat,
+    #[arg(from_global)]
+    pub template: Option<String>,
     #[arg(from_global)]
     pub keypair: PathBuf,
     #[arg(from_global)]
     pub config_host: String,
-    /// Remove ALL EUIs from a Route
+    #[command(flatten)]
+    pub connection: ConnectOpts,
+}
+
+#[derive(Debug, Args)]
+pub struct ExportEuis {
+    #[arg(short, long, value_parser = validate_route_id)]
+    pub route_id: RouteId,
+    /// Written as CSV, or as a JSON list of devices when the name ends in
+    /// `.json`
+    #[arg(from_global)]
+    pub out: Option<PathBuf>,
+    /// Application to create the devices in
+    #[arg(long, default_value = "")]
+    pub application_id: String,
+    /// Device profile of the devices
+    #[arg(long, default_value = "")]
+    pub device_profile_id: String,
+    #[arg(from_global)]
+    pub keypair: PathBuf,
+    #[arg(from_global)]
+    pub config_host: String,
+    #[command(flatten)]
+    pub connection: ConnectOpts,
+}
+
+#[derive(Debug, Args)]
+pub struct AddEui {
+    #[arg(
+        short,
+        long,
+        value_parser = hex_field::validate_eui,
+        required_unless_present_any = ["pair", "from_file"],
+        requires = "app_eui"
+    )]
+    pub dev_eui: Option<hex_field::HexEui>,
+    #[arg(
+        short,
+        long,
+        value_parser = hex_field::validate_eui,
+        required_unless_present_any = ["pair", "from_file"],
+        requires = "dev_eui"
+    )]
+    pub app_eui: Option<hex_field::HexEui>,
+    /// EUI pair as <app_eui>:<dev_eui>, may be repeated
+    #[arg(long, value_parser = hex_field::validate_eui_pair)]
+    pub pair: Vec<(hex_field::HexEui, hex_field::HexEui)>,
+    /// CSV of <app_eui>,<dev_eui> lines to add, sent in chunks
+    #[arg(long)]
+    pub from_file: Option<PathBuf>,
+    /// Continue an interrupted `--from-file` from the checkpoint next to it
+    #[arg(long, requires = "from_file")]
+    pub resume: bool,
+    /// Update streams open at once. Chunks are handed out in file order, but
+    /// with more than 1 a later chunk can be applied before an earlier one
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u16).range(1..))]
+    pub parallel: u16,
+    #[arg(long, value_parser = validate_route_id)]
+    pub route_id: RouteId,
+    #[arg(from_global)]
+    pub config_host: String,
+    #[command(flatten)]
+    pub connection: ConnectOpts,
+    #[arg(from_global)]
+    pub keypair: PathBuf,
+    #[arg(from_global)]
+    pub commit: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct RemoveEui {
+    #[arg(
+        short,
+        long,
+        value_parser = hex_field::validate_eui,
+        required_unless_present = "from_file",
+        requires = "app_eui"
+    )]
+    pub dev_eui: Option<hex_field::HexEui>,
+    #[arg(
+        short,
+        long,
+        value_parser = hex_field::validate_eui,
+        required_unless_present = "from_file",
+        requires = "dev_eui"
+    )]
+    pub app_eui: Option<hex_field::HexEui>,
+    /// EUI pairs to remove, a JSON list like the output of `euis list` for
+    /// `.json` files, CSV of <app_eui>,<dev_eui> lines otherwise
+    #[arg(long, conflicts_with_all = ["dev_eui", "app_eui"])]
+    pub from_file: Option<PathBuf>,
+    /// Update streams open at once. Chunks are handed out in file order, but
+    /// with more than 1 a later chunk can be applied before an earlier one
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u16).range(1..))]
+    pub parallel: u16,
+    #[arg(long, value_parser = validate_route_id)]
+    pub route_id: RouteId,
+    #[arg(from_global)]
+    pub config_host: String,
+    #[command(flatten)]
+    pub connection: ConnectOpts,
+    #[arg(from_global)]
+    pub keypair: PathBuf,
+    #[arg(from_global)]
+    pub commit: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct MoveEuis {
+    /// Route the EUI pairs are currently assigned to
+    #[arg(long, value_parser = validate_route_id)]
+    pub from: RouteId,
+    /// Route to assign the EUI pairs to
+    #[arg(long, value_parser = validate_route_id)]
+    pub to: RouteId,
+    /// Only move EUI pairs with this App EUI
+    #[arg(short, long, value_parser = hex_field::validate_eui)]
+    pub app_eui: Option<hex_field::HexEui>,
+    /// Only move EUI pairs with this Dev EUI
+    #[arg(short, long, value_parser = hex_field::validate_eui)]
+    pub dev_eui: Option<hex_field::HexEui>,
+    #[arg(from_global)]
+    pub config_host: String,
+    #[command(flatten)]
+    pub connection: ConnectOpts,
+    #[arg(from_global)]
+    pub keypair: PathBuf,
+    #[arg(from_global)]
+    pub commit: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct SyncEuis {
+    #[arg(short, long, value_parser = validate_route_id)]
+    pub route_id: RouteId,
+    /// CSV of <app_eui>,<dev_eui> lines, the complete list of pairs for the Route
     #[arg(short, long)]
+    pub file: PathBuf,
+    /// Sync a file without pairs, removing every pair of the Route
+    #[arg(long)]
+    pub allow_empty: bool,
+    #[arg(from_global)]
+    pub keypair: PathBuf,
+    #[arg(from_global)]
+    pub config_host: String,
+    #[command(flatten)]
+    pub connection: ConnectOpts,
+    #[arg(from_global)]
+    pub commit: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct ClearEuis {
+    #[arg(short, long, value_parser = validate_route_id)]
+    pub route_id: RouteId,
+    #[arg(from_global)]
+    pub keypair: PathBuf,
+    #[arg(from_global)]
+    pub config_host: String,
+    #[command(flatten)]
+    pub connection: ConnectOpts,
+    #[arg(from_global)]
     pub commit: bool,
 }
 
 #[derive(Debug, Args)]
 pub struct ListDevaddrs {
-    #[arg(short, long)]
-    pub route_id: String,
+    #[arg(short, long, value_parser = validate_route_id)]
+    pub route_id: RouteId,
+    /// Also print ranges of the Org constraints not assigned to any Route
+    #[arg(long)]
+    pub gaps: bool,
+    /// Print only the number of Devaddr Ranges
+    #[arg(long, conflicts_with = "gaps")]
+    pub count: bool,
+    #[arg(from_global)]
+    pub format: OutputFormat,
+    #[arg(from_global)]
+    pub template: Option<String>,
     #[arg(from_global)]
     pub keypair: PathBuf,
     #[arg(from_global)]
     pub config_host: String,
+    #[command(flatten)]
+    pub connection: ConnectOpts,
 }
 
 #[derive(Debug, Args)]
 pub struct AddDevaddr {
+    #[arg(
+        short,
+        long,
+        value_parser = hex_field::validate_devaddr,
+        required_unless_present_any = ["range", "from_file"]
+    )]
+    pub start_addr: Option<hex_field::HexDevAddr>,
+    #[arg(
+        short,
+        long,
+        value_parser = hex_field::validate_devaddr,
+        required_unless_present_any = ["range", "count", "from_file"],
+        requires = "start_addr",
+        conflicts_with = "count"
+    )]
+    pub end_addr: Option<hex_field::HexDevAddr>,
+    /// Number of devaddrs starting at <start_addr>, instead of <end_addr>
+    #[arg(long, requires = "start_addr", value_parser = clap::value_parser!(u32).range(1..))]
+    pub count: Option<u32>,
+    /// Devaddr Range as <start_addr>-<end_addr>, may be repeated
+    #[arg(long, value_parser = hex_field::validate_devaddr_range)]
+    pub range: Vec<DevaddrConstraint>,
+    /// JSON list of {"start_addr", "end_addr"} to add, each range must be
+    /// inside the devaddr constraints of the Org
+    #[arg(long)]
+    pub from_file: Option<PathBuf>,
+    /// Update streams open at once. Chunks are handed out in file order, but
+    /// with more than 1 a later chunk can be applied before an earlier one
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u16).range(1..))]
+    pub parallel: u16,
+    #[arg(long, value_parser = validate_route_id)]
+    pub route_id: RouteId,
+    #[arg(from_global)]
+    pub config_host: String,
+    #[command(flatten)]
+    pub connection: ConnectOpts,
+    #[arg(from_global)]
+    pub keypair: PathBuf,
+    #[arg(from_global)]
+    pub commit: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct MoveDevaddr {
     #[arg(short, long, value_parser = hex_field::validate_devaddr)]
     pub start_addr: hex_field::HexDevAddr,
     #[arg(short, long, value_parser = hex_field::validate_devaddr)]
     pub end_addr: hex_field::HexDevAddr,
-    #[arg(long)]
-    pub route_id: String,
+    /// Route the range is currently assigned to
+    #[arg(long, value_parser = validate_route_id)]
+    pub from: RouteId,
+    /// Route to assign the range to
+    #[arg(long, value_parser = validate_route_id)]
+    pub to: RouteId,
     #[arg(from_global)]
     pub config_host: String,
+    #[command(flatten)]
+    pub connection: ConnectOpts,
     #[arg(from_global)]
     pub keypair: PathBuf,
-    /// Add Devaddr entry to a Route
-    #[arg(short, long)]
+    #[arg(from_global)]
     pub commit: bool,
 }
 
@@ -507,52 +1360,144 @@ pub struct RemoveDevaddr {
     pub start_addr: hex_field::HexDevAddr,
     #[arg(short, long, value_parser = hex_field::validate_devaddr)]
     pub end_addr: hex_field::HexDevAddr,
-    #[arg(long)]
-    pub route_id: String,
+    #[arg(long, value_parser = validate_route_id)]
+    pub route_id: RouteId,
     #[arg(from_global)]
     pub config_host: String,
+    #[command(flatten)]
+    pub connection: ConnectOpts,
     #[arg(from_global)]
     pub keypair: PathBuf,
-    /// Remove Devaddr entry from a Route
+    #[arg(from_global)]
+    pub commit: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct SyncDevaddrs {
+    #[arg(short, long, value_parser = validate_route_id)]
+    pub route_id: RouteId,
+    /// JSON list of {"start_addr", "end_addr"}, the complete list of ranges for the Route
     #[arg(short, long)]
+    pub file: PathBuf,
+    /// Update streams open at once. Chunks are handed out in file order, but
+    /// with more than 1 a later chunk can be applied before an earlier one
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u16).range(1..))]
+    pub parallel: u16,
+    #[arg(from_global)]
+    pub keypair: PathBuf,
+    #[arg(from_global)]
+    pub config_host: String,
+    #[command(flatten)]
+    pub connection: ConnectOpts,
+    #[arg(from_global)]
     pub commit: bool,
 }
 
 #[derive(Debug, Args)]
 pub struct ClearDevaddrs {
-    #[arg(short, long)]
-    pub route_id: String,
+    #[arg(short, long, value_parser = validate_route_id)]
+    pub route_id: RouteId,
     #[arg(from_global)]
     pub keypair: PathBuf,
     #[arg(from_global)]
     pub config_host: String,
-    /// Remove ALL Devaddrs from a route
-    #[arg(short, long)]
+    #[command(flatten)]
+    pub connection: ConnectOpts,
+    #[arg(from_global)]
     pub commit: bool,
 }
 
+#[derive(Debug, Args)]
+pub struct DevaddrMap {
+    #[arg(long, env = ENV_OUI)]
+    pub oui: Oui,
+    /// Most cells in the bar of each constraint
+    #[arg(long, default_value = "64")]
+    pub width: u64,
+    #[arg(from_global)]
+    pub keypair: PathBuf,
+    #[arg(from_global)]
+    pub config_host: String,
+    #[command(flatten)]
+    pub connection: ConnectOpts,
+}
+
 #[derive(Debug, Args)]
 pub struct RouteSubnetMask {
-    #[arg(short, long)]
-    pub route_id: String,
+    #[arg(short, long, value_parser = validate_route_id)]
+    pub route_id: RouteId,
+    #[arg(from_global)]
+    pub format: OutputFormat,
     #[arg(from_global)]
     pub keypair: PathBuf,
     #[arg(from_global)]
     pub config_host: String,
+    #[command(flatten)]
+    pub connection: ConnectOpts,
 }
 
 #[derive(Debug, Args)]
 pub struct SubnetMask {
+    #[arg(value_parser = hex_field::validate_devaddr, requires = "end_addr")]
+    pub start_addr: Option<hex_field::HexDevAddr>,
     #[arg(value_parser = hex_field::validate_devaddr)]
-    pub start_addr: hex_field::HexDevAddr,
-    #[arg(value_parser = hex_field::validate_devaddr)]
-    pub end_addr: hex_field::HexDevAddr,
+    pub end_addr: Option<hex_field::HexDevAddr>,
+    /// Devaddr Range as <start_addr>-<end_addr>, may be repeated
+    #[arg(long, value_parser = hex_field::validate_devaddr_range)]
+    pub range: Vec<DevaddrConstraint>,
+    /// File with one <start_addr>-<end_addr> range per line
+    #[arg(long)]
+    pub file: Option<PathBuf>,
+    #[arg(from_global)]
+    pub format: OutputFormat,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum SubnetCommands {
+    /// Split a Devaddr Range into parts of the same size
+    ///
+    /// An aligned range split into a power of two parts gives parts that
+    /// are each a single subnet, ready to assign to different Routes.
+    Split(SubnetSplit),
+}
+
+#[derive(Debug, Args)]
+pub struct SubnetSplit {
+    #[arg(long, value_parser = hex_field::validate_devaddr)]
+    pub start: hex_field::HexDevAddr,
+    #[arg(long, value_parser = hex_field::validate_devaddr)]
+    pub end: hex_field::HexDevAddr,
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    pub parts: u32,
+    #[arg(from_global)]
+    pub format: OutputFormat,
+}
+
+#[derive(Debug, Args)]
+pub struct SplitDevaddrs {
+    #[arg(long, value_parser = hex_field::validate_devaddr)]
+    pub start: hex_field::HexDevAddr,
+    #[arg(long, value_parser = hex_field::validate_devaddr)]
+    pub end: hex_field::HexDevAddr,
+    /// Route to add the next part to, once per part
+    #[arg(long = "route-id", required = true, value_parser = validate_route_id)]
+    pub route_ids: Vec<RouteId>,
+    #[arg(from_global)]
+    pub keypair: PathBuf,
+    #[arg(from_global)]
+    pub config_host: String,
+    #[command(flatten)]
+    pub connection: ConnectOpts,
+    #[arg(from_global)]
+    pub commit: bool,
 }
 
 #[derive(Debug, Args)]
 pub struct EnvInfo {
     #[arg(long, env = ENV_CONFIG_HOST, default_value="unset")]
     pub config_host: Option<String>,
+    #[command(flatten)]
+    pub connection: ConnectOpts,
     #[arg(long, env = ENV_KEYPAIR_BIN, default_value="unset")]
     pub keypair: Option<PathBuf>,
     #[arg(long, env = ENV_NET_ID)]
@@ -563,20 +1508,61 @@ pub struct EnvInfo {
     pub max_copies: Option<u32>,
 }
 
+#[derive(Debug, Args)]
+pub struct Doctor {
+    #[arg(long, env = ENV_OUI)]
+    pub oui: Option<Oui>,
+    #[arg(from_global)]
+    pub keypair: PathBuf,
+    #[arg(from_global)]
+    pub config_host: String,
+    #[command(flatten)]
+    pub connection: ConnectOpts,
+}
+
+#[derive(Debug, Args)]
+pub struct ServerInfo {
+    #[arg(from_global)]
+    pub config_host: String,
+    #[command(flatten)]
+    pub connection: ConnectOpts,
+}
+
 #[derive(Debug, Args)]
 pub struct GenerateKeypair {
     #[arg(default_value = "./keypair.bin")]
     pub out_file: PathBuf,
 
     /// overwrite <out_file> if it already exists
-    #[arg(long)]
+    #[arg(from_global)]
     pub commit: bool,
 }
 
 #[derive(Debug, Args)]
 pub struct ListOrgs {
+    /// Print only OUIs, one per line
+    #[arg(long)]
+    pub ids: bool,
+    /// Print only these fields of each Org, e.g. `oui,owner,locked`
+    #[arg(long, value_delimiter = ',')]
+    pub fields: Vec<String>,
+    /// Only list Orgs owned by this key
+    #[arg(long)]
+    pub owner: Option<PublicKey>,
+    /// Skip this many Orgs, after `--owner`
+    #[arg(long, default_value_t = 0)]
+    pub offset: usize,
+    /// List at most this many Orgs
+    #[arg(long)]
+    pub limit: Option<usize>,
+    #[arg(from_global)]
+    pub format: OutputFormat,
+    #[arg(from_global)]
+    pub template: Option<String>,
     #[arg(from_global)]
     pub config_host: String,
+    #[command(flatten)]
+    pub connection: ConnectOpts,
 }
 
 #[derive(Debug, Args)]
@@ -585,6 +1571,37 @@ pub struct GetOrg {
     pub oui: Oui,
     #[arg(from_global)]
     pub config_host: String,
+    #[command(flatten)]
+    pub connection: ConnectOpts,
+}
+
+#[derive(Debug, Args)]
+pub struct OrgReport {
+    #[arg(long, env = ENV_OUI)]
+    pub oui: Oui,
+    #[arg(from_global)]
+    pub keypair: PathBuf,
+    #[arg(from_global)]
+    pub config_host: String,
+    #[command(flatten)]
+    pub connection: ConnectOpts,
+}
+
+#[derive(Debug, Args)]
+pub struct RestoreOrg {
+    #[arg(long, env = ENV_OUI)]
+    pub oui: Oui,
+    /// Snapshot directory
+    #[arg(long)]
+    pub from: PathBuf,
+    #[arg(from_global)]
+    pub keypair: PathBuf,
+    #[arg(from_global)]
+    pub config_host: String,
+    #[command(flatten)]
+    pub connection: ConnectOpts,
+    #[arg(from_global)]
+    pub commit: bool,
 }
 
 #[derive(Debug, Args)]
@@ -599,7 +1616,9 @@ pub struct CreateHelium {
     pub keypair: PathBuf,
     #[arg(from_global)]
     pub config_host: String,
-    #[arg(long)]
+    #[command(flatten)]
+    pub connection: ConnectOpts,
+    #[arg(from_global)]
     pub commit: bool,
 }
 
@@ -615,14 +1634,74 @@ pub struct CreateRoaming {
     pub keypair: PathBuf,
     #[arg(from_global)]
     pub config_host: String,
-    #[arg(long)]
+    #[command(flatten)]
+    pub connection: ConnectOpts,
+    #[arg(from_global)]
     pub commit: bool,
 }
 
+#[derive(Debug, Subcommand)]
+pub enum RegionCommands {
+    /// List all supported regions and the names accepted by `--region`
+    List(ListRegions),
+}
+
+#[derive(Debug, Args)]
+pub struct ListRegions {}
+
 #[derive(Debug, Subcommand)]
 pub enum RegionParamsCommands {
     /// Push a region params collection to the config service
     Push(PushRegionParams),
+    /// Generate a region params file from a channel plan
+    Generate(GenerateRegionParams),
+    /// Build and sign a push request to a file, for offline admin keys
+    Prepare(PrepareRegionParams),
+    /// Send a request made with `prepare` to the config service
+    Submit(SubmitRegionParams),
+}
+
+#[derive(Debug, Args)]
+pub struct PrepareRegionParams {
+    #[arg(value_enum)]
+    pub region: Region,
+    #[arg(long)]
+    pub params_file: PathBuf,
+    #[arg(long)]
+    pub index_file: Option<PathBuf>,
+    /// Sign params even if they exceed the regulatory limits of the region
+    #[arg(long)]
+    pub allow_noncompliant: bool,
+    /// Where to write the signed request
+    #[arg(long)]
+    pub out_file: PathBuf,
+    #[arg(from_global)]
+    pub keypair: PathBuf,
+}
+
+#[derive(Debug, Args)]
+pub struct SubmitRegionParams {
+    /// Signed request written by `prepare`
+    #[arg(long)]
+    pub request_file: PathBuf,
+    #[arg(from_global)]
+    pub config_host: String,
+    #[command(flatten)]
+    pub connection: ConnectOpts,
+    #[arg(from_global)]
+    pub commit: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct GenerateRegionParams {
+    #[arg(value_enum)]
+    pub region: Region,
+    /// Which block of 8 uplink channels to use (us915, au915)
+    #[arg(long)]
+    pub sub_band: Option<u8>,
+    /// Max EIRP in dBm, defaults to the regional limit
+    #[arg(long)]
+    pub max_eirp: Option<f32>,
 }
 
 #[derive(Debug, Args)]
@@ -633,17 +1712,255 @@ pub struct PushRegionParams {
     pub params_file: PathBuf,
     #[arg(long)]
     pub index_file: Option<PathBuf>,
+    /// Push params even if they exceed the regulatory limits of the region
+    #[arg(long)]
+    pub allow_noncompliant: bool,
+    #[arg(from_global)]
+    pub keypair: PathBuf,
+    #[arg(from_global)]
+    pub config_host: String,
+    #[command(flatten)]
+    pub connection: ConnectOpts,
+    #[arg(from_global)]
+    pub commit: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct Apply {
+    /// Desired state file
+    #[arg(short, long)]
+    pub file: PathBuf,
     #[arg(from_global)]
     pub keypair: PathBuf,
     #[arg(from_global)]
     pub config_host: String,
+    #[command(flatten)]
+    pub connection: ConnectOpts,
+    #[arg(from_global)]
+    pub commit: bool,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ImportCommands {
+    /// Add the devices of a ChirpStack device export to a Route
+    ///
+    /// DevEUI and JoinEUI become EUI pairs. Activated devices with a DevAddr
+    /// and network session key also get a Session Key Filter.
+    Chirpstack(ImportChirpstack),
+    /// Add the EUI pairs of The Things Stack end devices to a Route
+    ///
+    /// Reads the JSON of `ttn-lw-cli end-devices list`. Devices without a
+    /// DevEUI or JoinEUI are skipped.
+    Tts(ImportTts),
+}
+
+#[derive(Debug, Args)]
+pub struct ImportChirpstack {
+    #[arg(short, long, value_parser = validate_route_id)]
+    pub route_id: RouteId,
+    /// ChirpStack device export, CSV or a JSON list of devices
+    #[arg(short, long)]
+    pub file: PathBuf,
+    /// Column holding the DevEUI, when it is not named like ChirpStack's
+    #[arg(long)]
+    pub dev_eui_col: Option<String>,
+    /// Column holding the JoinEUI or AppEUI
+    #[arg(long)]
+    pub app_eui_col: Option<String>,
+    /// Column holding the devaddr of an activated device
     #[arg(long)]
+    pub devaddr_col: Option<String>,
+    /// Column holding the network session key of an activated device
+    #[arg(long)]
+    pub session_key_col: Option<String>,
+    #[arg(from_global)]
+    pub keypair: PathBuf,
+    #[arg(from_global)]
+    pub config_host: String,
+    #[command(flatten)]
+    pub connection: ConnectOpts,
+    #[arg(from_global)]
+    pub commit: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct ImportTts {
+    #[arg(short, long, value_parser = validate_route_id)]
+    pub route_id: RouteId,
+    /// JSON of `ttn-lw-cli end-devices list`
+    #[arg(short, long)]
+    pub file: PathBuf,
+    #[arg(from_global)]
+    pub keypair: PathBuf,
+    #[arg(from_global)]
+    pub config_host: String,
+    #[command(flatten)]
+    pub connection: ConnectOpts,
+    #[arg(from_global)]
     pub commit: bool,
 }
 
+/// A single range prints its subnets as before. Several ranges are merged
+/// first and print the minimal set of subnets covering all of them.
 pub fn subnet_mask(args: SubnetMask) -> Result<Msg> {
-    let devaddr_range = DevaddrConstraint::new(args.start_addr, args.end_addr)?;
-    Msg::ok(devaddr_range.to_subnet().pretty_json()?)
+    let mut ranges = args.range;
+    if let (Some(start_addr), Some(end_addr)) = (args.start_addr, args.end_addr) {
+        ranges.insert(0, DevaddrConstraint::new(start_addr, end_addr)?);
+    }
+    if let Some(file) = &args.file {
+        let contents =
+            std::fs::read_to_string(file).with_context(|| format!("reading {}", file.display()))?;
+        ranges.extend(subnet::parse_ranges(&contents)?);
+    }
+
+    match (ranges.len(), args.format) {
+        (0, _) => Msg::err("no devaddr ranges given".to_string()),
+        (_, OutputFormat::Cidr) => {
+            Msg::plain(subnet::cidr_lines(&subnet::aggregate_subnets(ranges)))
+        }
+        (1, _) => Msg::ok(ranges.remove(0).to_subnet().pretty_json()?),
+        (_, _) => Msg::ok(subnet::aggregate_subnets(ranges).pretty_json()?),
+    }
+}
+
+pub fn subnet_split(args: SubnetSplit) -> Result<Msg> {
+    let parts: Vec<_> = DevaddrConstraint::new(args.start, args.end)?
+        .split(args.parts)?
+        .into_iter()
+        .map(DevaddrConstraint::to_subnet)
+        .collect();
+    if args.format == OutputFormat::Cidr {
+        let lines: Vec<_> = parts.iter().map(|part| part.subnets.join(" ")).collect();
+        return Msg::plain(lines.join("\n"));
+    }
+
+    let unaligned = parts.iter().filter(|part| part.subnets.len() > 1).count();
+    if unaligned > 0 {
+        return Msg::ok(format!(
+            "warning: {unaligned} of {} parts need more than one subnet mask\n{}",
+            parts.len(),
+            parts.pretty_json()?
+        ));
+    }
+    Msg::ok(parts.pretty_json()?)
+}
+
+/// Send `chunks` with `send`, keeping up to `parallel` update streams open
+/// at once. Once one fails no more are started, but the ones already open
+/// are waited for, so the number of entries returned with the first failure
+/// counts every chunk the config service applied. `confirmed` gets each chunk
+/// applied before the first failure, in order.
+pub async fn send_chunks<'a, T: 'a, R, Fut: Future<Output = Result<R>>>(
+    chunks: impl Iterator<Item = &'a [T]>,
+    parallel: u16,
+    send: impl Fn(&'a [T]) -> Fut,
+    mut confirmed: impl FnMut(&'a [T]) -> Result,
+) -> Result<(usize, Option<anyhow::Error>)> {
+    let stopped = Cell::new(false);
+    let mut updates = futures::stream::iter(chunks)
+        .take_while(|_| future::ready(!stopped.get()))
+        .map(|chunk| send(chunk).map_ok(move |_| chunk))
+        .buffered(parallel.into());
+    let (mut applied, mut failure) = (0, None);
+    while let Some(sent) = updates.next().await {
+        match sent {
+            Ok(chunk) => {
+                applied += chunk.len();
+                if failure.is_none() {
+                    confirmed(chunk)?;
+                }
+            }
+            Err(err) => {
+                stopped.set(true);
+                failure.get_or_insert(err);
+            }
+        }
+    }
+    Ok((applied, failure))
+}
+
+/// Print each item of `stream` through `template` as soon as it arrives.
+pub async fn write_template<T: Serialize>(
+    stream: impl Stream<Item = Result<T>>,
+    template: &Template<'_>,
+) -> Result<Msg> {
+    let mut stream = Box::pin(stream);
+    let mut out = BufWriter::new(output::open()?);
+    while let Some(item) = stream.try_next().await? {
+        writeln!(out, "{}", template.render(&item)?)?;
+    }
+    out.flush()?;
+    Msg::plain(String::new())
+}
+
+/// Print each item of `stream` as a line of JSON as soon as it arrives, for
+/// `--format jsonl` on lists too large to hold in memory.
+pub async fn write_jsonl<T: Serialize>(stream: impl Stream<Item = Result<T>>) -> Result<Msg> {
+    let mut stream = Box::pin(stream);
+    let mut out = BufWriter::new(output::open()?);
+    while let Some(item) = stream.try_next().await? {
+        writeln!(out, "{}", output_json(&item, false)?)?;
+    }
+    out.flush()?;
+    Msg::plain(String::new())
+}
+
+/// Print the items of `stream` as a JSON list as they arrive, the same text
+/// as printing the whole list at once, for `--out` files of lists too large
+/// to hold in memory.
+pub async fn write_json_list<T: Serialize>(stream: impl Stream<Item = Result<T>>) -> Result<Msg> {
+    let mut stream = Box::pin(stream);
+    let mut out = BufWriter::new(output::open()?);
+    let mut first = true;
+    while let Some(item) = stream.try_next().await? {
+        let json = output_json(&item, true)?;
+        write!(
+            out,
+            "{}\n  {}",
+            if first { "[" } else { "," },
+            json.replace('\n', "\n  ")
+        )?;
+        first = false;
+    }
+    writeln!(out, "{}", if first { "[]" } else { "\n]" })?;
+    out.flush()?;
+    Msg::plain(String::new())
+}
+
+/// `item` as JSON with its hex fields as they are printed in output. Keys
+/// keep their order unless hex fields are restyled.
+fn output_json<T: Serialize>(item: &T, pretty: bool) -> Result<String> {
+    fn to_string<T: Serialize>(item: &T, pretty: bool) -> serde_json::Result<String> {
+        match pretty {
+            true => serde_json::to_string_pretty(item),
+            false => serde_json::to_string(item),
+        }
+    }
+    Ok(match output::hex_format() {
+        Some(_) => to_string(&output::restyle_hex(serde_json::to_value(item)?), pretty)?,
+        None => to_string(item, pretty)?,
+    })
+}
+
+/// The number of entries in `stream`, or the number per key with
+/// `group_by`, counted as they arrive.
+pub async fn count_entries<T>(
+    stream: impl Stream<Item = Result<T>>,
+    group_by: Option<fn(&T) -> String>,
+) -> Result<Msg> {
+    let mut stream = Box::pin(stream);
+    let mut total = 0u64;
+    let mut groups: BTreeMap<String, u64> = BTreeMap::new();
+    while let Some(entry) = stream.try_next().await? {
+        total += 1;
+        if let Some(key) = group_by {
+            *groups.entry(key(&entry)).or_default() += 1;
+        }
+    }
+    match group_by {
+        Some(_) => Msg::ok(groups.pretty_json()?),
+        None => Msg::ok(total.to_string()),
+    }
 }
 
 pub trait PathBufKeypair {
@@ -656,3 +1973,106 @@ impl PathBufKeypair for PathBuf {
         Ok(helium_crypto::Keypair::try_from(&data[..])?)
     }
 }
+
+/// Check every route id on the command line, of the leaf command and the
+/// commands above it, against `--allow-any-id`.
+pub fn check_route_ids(matches: &ArgMatches, allow_any_id: bool) -> Result {
+    for id in matches.ids() {
+        if let Ok(Some(route_ids)) = matches.try_get_many::<RouteId>(id.as_str()) {
+            for route_id in route_ids {
+                route_id.check(allow_any_id)?;
+            }
+        }
+    }
+    match matches.subcommand() {
+        Some((_, matches)) => check_route_ids(matches, allow_any_id),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_route_ids, count_entries, send_chunks, subnet_mask, Cli, Commands};
+    use crate::render::OutputFormat;
+    use anyhow::anyhow;
+    use clap::{CommandFactory, FromArgMatches};
+    use futures::{future, stream};
+    use std::cell::RefCell;
+
+    #[test]
+    fn route_ids_checked_after_parsing() {
+        let check = |args: &[&str]| {
+            let matches = Cli::command()
+                .try_get_matches_from(["helium-config-service-cli"].iter().chain(args))
+                .unwrap();
+            let allow_any_id = matches.get_flag("allow_any_id");
+            check_route_ids(&matches, allow_any_id)
+        };
+        let uuid = "5c1bfc5b-1d38-4a41-9b58-b16dc5b4c2a0";
+        assert!(check(&["route", "get", "--route-id", uuid]).is_ok());
+        assert!(check(&["route", "get", "--route-id", "route_id"]).is_err());
+        assert!(check(&["route", "get", "--route-id", "route_id", "--allow-any-id"]).is_ok());
+        assert!(check(&["--allow-any-id", "route", "get", "--route-id", "route_id"]).is_ok());
+    }
+
+    #[test]
+    fn subnet_mask_cidr_format() {
+        let matches = Cli::command()
+            .try_get_matches_from([
+                "helium-config-service-cli",
+                "subnet-mask",
+                "48000000",
+                "4800003F",
+                "--format",
+                "cidr",
+            ])
+            .unwrap();
+        let Commands::SubnetMask(args) = Cli::from_arg_matches(&matches).unwrap().command else {
+            panic!("not subnet-mask");
+        };
+        assert_eq!(OutputFormat::Cidr, args.format);
+        assert_eq!("48000000/26", subnet_mask(args).unwrap().into_inner());
+    }
+
+    #[tokio::test]
+    async fn count_stream_entries() {
+        let entries = || stream::iter(["a-1", "a-2", "b-1"].map(Ok));
+        let total = count_entries(entries(), None).await.unwrap();
+        assert_eq!("3", total.into_inner());
+
+        let prefix: fn(&&str) -> String = |entry| entry[..1].to_string();
+        let grouped = count_entries(entries(), Some(prefix)).await.unwrap();
+        let counts: serde_json::Value = serde_json::from_str(&grouped.into_inner()).unwrap();
+        assert_eq!(serde_json::json!({"a": 2, "b": 1}), counts);
+    }
+
+    #[tokio::test]
+    async fn chunks_after_a_failure() {
+        let entries: Vec<u32> = (1..=10).collect();
+        let (sent, confirmed) = (RefCell::new(vec![]), RefCell::new(vec![]));
+        let (applied, failure) = send_chunks(
+            entries.chunks(2),
+            3,
+            |chunk| {
+                sent.borrow_mut().push(chunk[0]);
+                future::ready(match chunk[0] {
+                    3 => Err(anyhow!("unavailable")),
+                    _ => Ok(()),
+                })
+            },
+            |chunk| {
+                confirmed.borrow_mut().push(chunk[0]);
+                Ok(())
+            },
+        )
+        .await
+        .unwrap();
+
+        // The chunk of 7 was already open when the one of 3 failed, 9 never
+        // went out.
+        assert_eq!(vec![1, 3, 5, 7], sent.into_inner());
+        assert_eq!(vec![1], confirmed.into_inner());
+        assert_eq!(6, applied);
+        assert_eq!("unavailable", failure.unwrap().to_string());
+    }
+}
@@ -1,60 +1,173 @@
-use super::{CreateHelium, CreateRoaming, GetOrg, ListOrgs, PathBufKeypair, ENV_NET_ID, ENV_OUI};
-use crate::{client, Msg, PrettyJson, Result};
+use super::{
+    apply, CreateHelium, CreateRoaming, GetOrg, ListOrgs, OrgReport, PathBufKeypair, RestoreOrg,
+    ENV_NET_ID, ENV_OUI,
+};
+use crate::{
+    client,
+    render::{self, OutputFormat, Template},
+    report::{self, RouteReport},
+    snapshot::OrgSnapshot,
+    Msg, PrettyJson, Result,
+};
+use serde_json::json;
 
 pub async fn list_orgs(args: ListOrgs) -> Result<Msg> {
-    let mut client = client::OrgClient::new(&args.config_host).await?;
-    let org = client.list().await?;
+    let mut client = client::OrgClient::new(&args.config_host, &args.connection).await?;
+    let mut org = client.list().await?;
+    if let Some(owner) = &args.owner {
+        org.retain_owner(owner);
+    }
+    org.page(args.offset, args.limit);
+
+    if args.ids {
+        return Msg::plain(
+            org.orgs
+                .iter()
+                .map(|org| org.oui.to_string())
+                .collect::<Vec<_>>()
+                .join("\n"),
+        );
+    }
+
+    if let Some(template) = &args.template {
+        return Msg::plain(Template::new(template, "org")?.render_all(&org.orgs)?);
+    }
+
+    if !args.fields.is_empty() {
+        let selected = render::select(&serde_json::to_value(&org)?, &args.fields)?;
+        return Msg::ok(selected.pretty_json()?);
+    }
+
+    if args.format == OutputFormat::Table {
+        let columns = ["oui", "owner", "payer", "delegate_keys", "locked"].map(String::from);
+        let rows: Vec<Vec<String>> = org
+            .orgs
+            .iter()
+            .map(|org| {
+                vec![
+                    org.oui.to_string(),
+                    org.owner.to_string(),
+                    org.payer.to_string(),
+                    org.delegate_keys.len().to_string(),
+                    org.locked.to_string(),
+                ]
+            })
+            .collect();
+        return Msg::plain(render::table(&columns, &rows));
+    }
 
     Msg::ok(org.pretty_json()?)
 }
 
 pub async fn get_org(args: GetOrg) -> Result<Msg> {
-    let mut client = client::OrgClient::new(&args.config_host).await?;
+    let mut client = client::OrgClient::new(&args.config_host, &args.connection).await?;
     let org = client.get(args.oui).await?;
 
     Msg::ok(org.pretty_json()?)
 }
 
+pub async fn org_report(args: OrgReport) -> Result<Msg> {
+    let keypair = args.keypair.to_keypair()?;
+    let org = client::OrgClient::new(&args.config_host, &args.connection)
+        .await?
+        .get(args.oui)
+        .await?;
+
+    let mut route_client = client::RouteClient::new(&args.config_host, &args.connection).await?;
+    let mut routes = vec![];
+    for route in route_client.list(args.oui, &keypair).await?.routes {
+        routes.push(RouteReport {
+            euis: route_client.get_euis(&route.id, &keypair).await?,
+            devaddrs: route_client.get_devaddrs(&route.id, &keypair).await?,
+            route,
+        });
+    }
+
+    let filters = client::SkfClient::new(&args.config_host, &args.connection)
+        .await?
+        .list_filters(args.oui, &keypair)
+        .await?;
+
+    Msg::plain(report::org_markdown(&org, &routes, &filters))
+}
+
+pub async fn restore_org(args: RestoreOrg) -> Result<Msg> {
+    let snapshot = OrgSnapshot::from_dir(&args.from, args.oui)?;
+    let keypair = args.keypair.to_keypair()?;
+    let (plan, mut route_client, mut skf_client) =
+        apply::plan(snapshot, &args.config_host, &args.connection, &keypair).await?;
+
+    let from = args.from.display();
+    if plan.is_empty() {
+        return Msg::ok(format!("org {} already matches {from}", args.oui));
+    }
+    let summary = plan.summary().pretty_json()?;
+    if !args.commit {
+        return Msg::dry_run(format!("restore org {} from {from}\n{summary}", args.oui));
+    }
+
+    apply::execute(plan, &mut route_client, &mut skf_client, &keypair).await?;
+    Msg::ok(format!("restored org {} from {from}\n{summary}", args.oui))
+}
+
 pub async fn create_helium_org(args: CreateHelium) -> Result<Msg> {
-    if args.commit {
-        let mut client = client::OrgClient::new(&args.config_host).await?;
-        let org = client
-            .create_helium(
-                &args.owner,
-                &args.payer,
-                args.devaddr_count,
-                &args.keypair.to_keypair()?,
-            )
-            .await?;
-        return Msg::ok(format!(
-            "Helium Organization Created: \n{}",
-            org.pretty_json()?
+    if !args.commit {
+        return Msg::dry_run(format!(
+            "Create Helium Organization\n{}",
+            json!({
+                "owner": args.owner.to_string(),
+                "payer": args.payer.to_string(),
+                "devaddr_count": args.devaddr_count,
+            })
+            .pretty_json()?
         ));
     }
-    Msg::ok("pass `--commit` to create Helium organization".to_string())
+
+    let mut client = client::OrgClient::new(&args.config_host, &args.connection).await?;
+    let org = client
+        .create_helium(
+            &args.owner,
+            &args.payer,
+            args.devaddr_count,
+            &args.keypair.to_keypair()?,
+        )
+        .await?;
+    Msg::ok(format!(
+        "Helium Organization Created: \n{}",
+        org.pretty_json()?
+    ))
 }
 
 pub async fn create_roaming_org(args: CreateRoaming) -> Result<Msg> {
-    if args.commit {
-        let mut client = client::OrgClient::new(&args.config_host).await?;
-        let created_org = client
-            .create_roamer(
-                &args.owner,
-                &args.payer,
-                args.net_id.into(),
-                args.keypair.to_keypair()?,
-            )
-            .await?;
-        return Msg::ok(
-            [
-                "== Roaming Organization Created ==".to_string(),
-                created_org.pretty_json()?,
-                "== Environment Variables ==".to_string(),
-                format!("{ENV_NET_ID}={}", created_org.net_id),
-                format!("{ENV_OUI}={}", created_org.org.oui),
-            ]
-            .join("\n"),
-        );
+    if !args.commit {
+        return Msg::dry_run(format!(
+            "Create Roaming Organization\n{}",
+            json!({
+                "owner": args.owner.to_string(),
+                "payer": args.payer.to_string(),
+                "net_id": args.net_id,
+            })
+            .pretty_json()?
+        ));
     }
-    Msg::ok("pass `--commit` to create Roaming organization".to_string())
+
+    let mut client = client::OrgClient::new(&args.config_host, &args.connection).await?;
+    let created_org = client
+        .create_roamer(
+            &args.owner,
+            &args.payer,
+            args.net_id.into(),
+            args.keypair.to_keypair()?,
+        )
+        .await?;
+    Msg::ok(
+        [
+            "== Roaming Organization Created ==".to_string(),
+            created_org.pretty_json()?,
+            "== Environment Variables ==".to_string(),
+            format!("{ENV_NET_ID}={}", created_org.net_id),
+            format!("{ENV_OUI}={}", created_org.org.oui),
+        ]
+        .join("\n"),
+    )
 }
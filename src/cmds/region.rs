@@ -0,0 +1,25 @@
+use crate::{region::Region, Msg, PrettyJson, Result};
+use clap::ValueEnum;
+use helium_proto::Region as ProtoRegion;
+use serde_json::json;
+
+use super::ListRegions;
+
+pub fn list_regions(_args: ListRegions) -> Result<Msg> {
+    let regions: Vec<_> = Region::value_variants()
+        .iter()
+        .map(|region| {
+            let name = region
+                .to_possible_value()
+                .map(|value| value.get_name().to_string());
+            json!({
+                "name": name,
+                "proto_name": ProtoRegion::from(region).to_string(),
+                "value": i32::from(region.clone()),
+                "frequency_plan": region.frequency_plan(),
+            })
+        })
+        .collect();
+
+    Msg::ok(regions.pretty_json()?)
+}
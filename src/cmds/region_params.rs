@@ -1,32 +1,30 @@
-use crate::{client, cmds::PathBufKeypair, region_params::RegionParams, Msg, PrettyJson, Result};
-use anyhow::Context;
-use helium_proto::Region as ProtoRegion;
-use std::{
-    fs::{self, File},
-    io::Read,
+use crate::{
+    client, cmds::PathBufKeypair, region::Region, region_params::RegionParams, Msg, PrettyJson,
+    Result,
 };
+use anyhow::{anyhow, Context};
+use helium_proto::{services::iot_config::GatewayLoadRegionReqV1, Message, Region as ProtoRegion};
+use std::{fs, path::PathBuf};
 
-use super::PushRegionParams;
+use super::{GenerateRegionParams, PrepareRegionParams, PushRegionParams, SubmitRegionParams};
+
+pub fn generate_params(args: GenerateRegionParams) -> Result<Msg> {
+    let params = RegionParams::generate(&args.region, args.sub_band, args.max_eirp)?;
+    Msg::plain(params.pretty_json()?)
+}
 
 pub async fn push_params(args: PushRegionParams) -> Result<Msg> {
-    let mut client = client::GatewayClient::new(&args.config_host).await?;
+    let mut client = client::GatewayClient::new(&args.config_host, &args.connection).await?;
     let params = RegionParams::from_file(&args.params_file)?;
+    let index_bytes = read_index_file(args.index_file.as_ref())?;
 
-    let index_bytes = if let Some(index_path) = &args.index_file {
-        let mut index_file = File::open(index_path).context("reading region h3 indices file")?;
-        let metadata = fs::metadata(index_path).context("reading index file metadata")?;
-        let mut byte_buf = vec![0; metadata.len() as usize];
-        index_file
-            .read(&mut byte_buf)
-            .context("reading index buffer")?;
-
-        byte_buf
-    } else {
-        vec![]
+    let warnings = match compliance_warnings(&params, &args.region, args.allow_noncompliant) {
+        Ok(warnings) => warnings,
+        Err(msg) => return Msg::err(msg),
     };
 
     if !args.commit {
-        return Msg::dry_run(params.pretty_json()?);
+        return Msg::dry_run(format!("{warnings}{}", params.pretty_json()?));
     }
 
     match client
@@ -39,10 +37,94 @@ pub async fn push_params(args: PushRegionParams) -> Result<Msg> {
         .await
     {
         Ok(_) => Msg::ok(format!(
-            "created region params {}\n{}",
+            "{warnings}created region params {}\n{}",
             ProtoRegion::from(args.region),
             params.pretty_json()?
         )),
-        Err(err) => Msg::err(format!("region params not created: {err}")),
+        Err(err) => Msg::failed("region params not created", err),
+    }
+}
+
+pub fn prepare_params(args: PrepareRegionParams) -> Result<Msg> {
+    let params = RegionParams::from_file(&args.params_file)?;
+    let index_bytes = read_index_file(args.index_file.as_ref())?;
+
+    let warnings = match compliance_warnings(&params, &args.region, args.allow_noncompliant) {
+        Ok(warnings) => warnings,
+        Err(msg) => return Msg::err(msg),
+    };
+
+    let request = client::load_region_request(
+        args.region.clone(),
+        params,
+        index_bytes,
+        &args.keypair.to_keypair()?,
+    )?;
+    if let Some(parent) = args.out_file.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&args.out_file, request.encode_to_vec()).context("writing prepared request")?;
+
+    Msg::ok(format!(
+        "{warnings}signed region params {} written to {}",
+        ProtoRegion::from(args.region),
+        args.out_file.display()
+    ))
+}
+
+pub async fn submit_params(args: SubmitRegionParams) -> Result<Msg> {
+    let data = fs::read(&args.request_file).context("reading prepared request")?;
+    let request = GatewayLoadRegionReqV1::decode(data.as_slice()).context(format!(
+        "decoding prepared request {}",
+        args.request_file.display()
+    ))?;
+    let region = ProtoRegion::from(Region::from_i32(request.region)?);
+    let params = request
+        .params
+        .clone()
+        .map(RegionParams::from)
+        .ok_or_else(|| anyhow!("prepared request has no region params"))?;
+
+    if !args.commit {
+        return Msg::dry_run(format!(
+            "submit region params {region}\n{}",
+            params.pretty_json()?
+        ));
+    }
+
+    let mut client = client::GatewayClient::new(&args.config_host, &args.connection).await?;
+    match client.submit_load_region(request).await {
+        Ok(_) => Msg::ok(format!(
+            "created region params {region}\n{}",
+            params.pretty_json()?
+        )),
+        Err(err) => Msg::failed("region params not created", err),
+    }
+}
+
+fn read_index_file(index_file: Option<&PathBuf>) -> Result<Vec<u8>> {
+    match index_file {
+        Some(path) => fs::read(path).context("reading region h3 indices file"),
+        None => Ok(vec![]),
+    }
+}
+
+/// Warnings to print for noncompliant params that are allowed through, or
+/// the error to return when they are not.
+fn compliance_warnings(
+    params: &RegionParams,
+    region: &Region,
+    allow_noncompliant: bool,
+) -> std::result::Result<String, String> {
+    let violations = params.compliance_violations(region);
+    if !violations.is_empty() && !allow_noncompliant {
+        return Err(format!(
+            "region params exceed regulatory limits, pass `--allow-noncompliant` to push anyway\n{}",
+            violations.join("\n")
+        ));
     }
+    Ok(violations
+        .iter()
+        .map(|violation| format!("warning: {violation}\n"))
+        .collect())
 }
@@ -1,33 +1,655 @@
 use crate::{
-    client, cmds::PathBufKeypair, route::Route, server::Protocol, Msg, PrettyJson, Result,
+    client,
+    cmds::PathBufKeypair,
+    diff, output,
+    render::{self, OutputFormat, Template},
+    route::{is_route_file, Route, RouteId, SyncAction, SYNC_BASE_DIR},
+    server::{self, FlowType, Gwmp, Http, Protocol, DEFAULT_DEDUPE_TIMEOUT},
+    split_present, sync_delta, DevaddrRange, Eui, ExitCode, Msg, PrettyJson, Result, RouteExport,
+};
+use anyhow::{anyhow, Context};
+use futures::{future, StreamExt, TryStreamExt};
+use helium_crypto::Keypair;
+use serde_json::json;
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs,
+    io::Write,
+    path::PathBuf,
 };
 
 use super::{
-    ActivateRoute, AddGwmpRegion, DeactivateRoute, DeleteRoute, GetRoute, ListRoutes, NewRoute,
-    RemoveGwmpRegion, UpdateHttp, UpdateMaxCopies, UpdatePacketRouter, UpdateServer,
+    ActivateRoute, AddGwmpRegion, DeactivateRoute, DeleteRoute, DiffRoute, ExportRoute, GetRoute,
+    ImportRoute, ListRoutes, MigrateRoute, NewRoute, PushAllRoutes, RemoveGwmpRegion, SyncRoutes,
+    UpdateGwmp, UpdateHttp, UpdateMaxCopies, UpdatePacketRouter, UpdateServer,
 };
 
 pub async fn list_routes(args: ListRoutes) -> Result<Msg> {
-    let mut client = client::RouteClient::new(&args.config_host).await?;
-    match client.list(args.oui, &args.keypair.to_keypair()?).await {
-        Ok(route_list) => Msg::ok(route_list.pretty_json()?),
-        Err(err) => Msg::err(format!("could not list routes: {err}")),
+    let mut client = client::RouteClient::new(&args.config_host, &args.connection).await?;
+    let keypair = args.keypair.to_keypair()?;
+    let mut route_list = match client.list(args.oui, &keypair).await {
+        Ok(route_list) => route_list,
+        Err(err) => return Msg::failed("could not list routes", err),
+    };
+    route_list.retain(&args.filter);
+    if let Some(key) = args.sort_by {
+        route_list.sort(key);
+    }
+    if let Some(template) = &args.template {
+        return Msg::plain(Template::new(template, "route")?.render_all(&route_list.routes)?);
+    }
+    if !args.fields.is_empty() {
+        let selected = render::select(&serde_json::to_value(&route_list)?, &args.fields)?;
+        return Msg::ok(selected.pretty_json()?);
+    }
+    match route_list {
+        route_list if args.ids => Msg::plain(
+            route_list
+                .routes
+                .into_iter()
+                .map(|route| route.id.into())
+                .collect::<Vec<String>>()
+                .join("\n"),
+        ),
+        route_list if args.format == OutputFormat::Table => {
+            let counts = args.with_counts.then_some((&mut client, &keypair));
+            Msg::plain(routes_table(route_list.routes, counts).await?)
+        }
+        route_list => Msg::ok(route_list.pretty_json()?),
     }
 }
 
-pub async fn get_route(args: GetRoute) -> Result<Msg> {
-    let mut client = client::RouteClient::new(&args.config_host).await?;
-    match client
-        .get(&args.route_id, &args.keypair.to_keypair()?)
-        .await
+/// One row per Route. With a client, also the number of EUI pairs and
+/// Devaddr Ranges it holds, counted as they stream in, which takes two
+/// requests per Route.
+async fn routes_table(
+    routes: Vec<Route>,
+    mut counts: Option<(&mut client::RouteClient, &Keypair)>,
+) -> Result<String> {
+    let mut columns = vec!["id", "oui", "max_copies", "active", "server", "protocol"];
+    if counts.is_some() {
+        columns.extend(["euis", "devaddrs"]);
+    }
+    let columns: Vec<String> = columns.into_iter().map(String::from).collect();
+    let mut rows = vec![];
+    for route in routes {
+        let mut row = vec![
+            route.id.to_string(),
+            route.oui.to_string(),
+            route.max_copies.to_string(),
+            route.active.to_string(),
+            format!("{}:{}", route.server.host, route.server.port),
+            route
+                .server
+                .protocol
+                .as_ref()
+                .map_or("", Protocol::name)
+                .to_string(),
+        ];
+        if let Some((client, keypair)) = counts.as_mut() {
+            let euis = client
+                .stream_euis(&route.id, keypair)
+                .await?
+                .try_fold(0, |count, _| async move { Ok(count + 1) })
+                .await?;
+            let devaddrs = client
+                .stream_devaddrs(&route.id, keypair)
+                .await?
+                .try_fold(0, |count, _| async move { Ok(count + 1) })
+                .await?;
+            row.extend([euis.to_string(), devaddrs.to_string()]);
+        }
+        rows.push(row);
+    }
+    Ok(render::table(&columns, &rows))
+}
+
+pub fn route_schema() -> Result<Msg> {
+    Msg::plain(Route::schema().pretty_json()?)
+}
+
+pub async fn export_route(args: ExportRoute) -> Result<Msg> {
+    let mut client = client::RouteClient::new(&args.config_host, &args.connection).await?;
+    let keypair = args.keypair.to_keypair()?;
+    let route = match client.get(&args.route_id, &keypair).await {
+        Ok(route) => route,
+        Err(err) => return Msg::failed("could not get route", err),
+    };
+    let euis = client.get_euis(&route.id, &keypair).await?;
+    let devaddrs = client.get_devaddrs(&route.id, &keypair).await?;
+    let export = RouteExport::new(route, euis, devaddrs);
+
+    if !args.proto {
+        return Msg::plain(export.pretty_json()?);
+    }
+    if output::is_terminal() {
+        return Err(anyhow!(
+            "refusing to write protobuf to a terminal, pass `--out <file>`"
+        ));
+    }
+    let mut out = output::open()?;
+    out.write_all(&export.to_proto()?)?;
+    out.flush()?;
+    Msg::plain(String::new())
+}
+
+pub async fn import_route(args: ImportRoute) -> Result<Msg> {
+    let export = if args.proto {
+        fs::read(&args.file)
+            .with_context(|| format!("reading {}", args.file.display()))
+            .and_then(|bytes| RouteExport::from_proto(&bytes))
+    } else {
+        fs::read_to_string(&args.file)
+            .with_context(|| format!("reading {}", args.file.display()))
+            .and_then(|json| RouteExport::from_json(&json))
+    };
+    let mut export = export.with_context(|| format!("parsing {}", args.file.display()))?;
+    let contents = format!(
+        "{} EUI pairs and {} Devaddr Ranges",
+        export.euis.len(),
+        export.devaddrs.len()
+    );
+    let mut route = export.route.clone();
+    route.id = RouteId::default();
+
+    if !args.commit {
+        return Msg::dry_run(format!(
+            "create route for oui {} with {contents}\n{}",
+            route.oui,
+            route.pretty_json()?
+        ));
+    }
+
+    let keypair = args.keypair.to_keypair()?;
+    let mut client = client::RouteClient::new(&args.config_host, &args.connection).await?;
+    let created = match client.create_route(route, &keypair).await {
+        Ok(created) => created,
+        Err(err) => return Msg::failed("route not created", err),
+    };
+    export.rebind(&created.id);
+    if !export.euis.is_empty() {
+        if let Err(err) = client.add_euis(export.euis, &keypair).await {
+            let context = format!("created route {} but could not add its euis", created.id);
+            return Msg::failed(context, err);
+        }
+    }
+    if !export.devaddrs.is_empty() {
+        if let Err(err) = client.add_devaddrs(export.devaddrs, &keypair).await {
+            let context = format!(
+                "created route {} but could not add its devaddrs",
+                created.id
+            );
+            return Msg::failed(context, err);
+        }
+    }
+
+    Msg::ok(format!(
+        "created route {} with {contents}\n{}",
+        created.id,
+        created.pretty_json()?
+    ))
+}
+
+pub async fn diff_route(args: DiffRoute) -> Result<Msg> {
+    // An export also holds the EUI pairs and Devaddr Ranges of the Route.
+    let export = fs::read_to_string(&args.file)
+        .ok()
+        .and_then(|json| RouteExport::from_json(&json).ok());
+    let (local, members) = match export {
+        Some(mut export) => {
+            if let Some(route_id) = &args.route_id {
+                export.rebind(route_id);
+            }
+            (export.route, Some((export.euis, export.devaddrs)))
+        }
+        None => (Route::from_file(&args.file)?, None),
+    };
+    let route_id = args.route_id.unwrap_or_else(|| local.id.clone());
+    if route_id.is_empty() {
+        return Msg::err(format!(
+            "{} has no route id, pass `--route-id`",
+            args.file.display()
+        ));
+    }
+    let local = Route {
+        id: route_id.clone(),
+        ..local
+    };
+
+    let mut client = client::RouteClient::new(&args.config_host, &args.connection).await?;
+    let keypair = args.keypair.to_keypair()?;
+    let remote = match client.get(&route_id, &keypair).await {
+        Ok(remote) => remote,
+        Err(err) => return Msg::failed("could not get route", err),
+    };
+    let mut changes = json!({
+        "route_id": route_id,
+        "fields": diff::fields(&serde_json::to_value(&remote)?, &serde_json::to_value(&local)?),
+    });
+
+    if let Some((euis, devaddrs)) = members {
+        let (added, removed) = sync_delta(euis, client.get_euis(&route_id, &keypair).await?);
+        changes["euis"] = json!({ "added": added, "removed": removed });
+        let (added, removed) =
+            sync_delta(devaddrs, client.get_devaddrs(&route_id, &keypair).await?);
+        changes["devaddrs"] = json!({ "added": added, "removed": removed });
+    }
+
+    Msg::ok(changes.pretty_json()?)
+}
+
+pub async fn sync_routes(args: SyncRoutes) -> Result<Msg> {
+    let keypair = args.keypair.to_keypair()?;
+    let mut client = client::RouteClient::new(&args.config_host, &args.connection).await?;
+    let remote: BTreeMap<RouteId, Route> = match client.list(args.oui, &keypair).await {
+        Ok(route_list) => route_list
+            .routes
+            .into_iter()
+            .map(|route| (route.id.clone(), route))
+            .collect(),
+        Err(err) => return Msg::failed("could not list routes", err),
+    };
+
+    let mut warnings = vec![];
+    let mut local: BTreeMap<RouteId, (PathBuf, Route)> = BTreeMap::new();
+    if args.dir.exists() {
+        for entry in
+            fs::read_dir(&args.dir).with_context(|| format!("reading {}", args.dir.display()))?
+        {
+            let path = entry?.path();
+            if !path.is_file() || !is_route_file(&path) {
+                continue;
+            }
+            let route = Route::from_file(&path)?;
+            if route.id.is_empty() {
+                warnings.push(format!(
+                    "warning: {} has no route id, create it with `route new` first",
+                    path.display()
+                ));
+                continue;
+            }
+            if route.oui == args.oui {
+                local.insert(route.id.clone(), (path, route));
+            }
+        }
+    }
+
+    let base_dir = args.dir.join(SYNC_BASE_DIR);
+    let base_path = |route_id: &RouteId| base_dir.join(format!("{route_id}.json"));
+    let mut ids: Vec<&RouteId> = remote.keys().chain(local.keys()).collect();
+    ids.sort();
+    ids.dedup();
+
+    let mut plan = vec![];
+    for route_id in ids {
+        let file = local.get(route_id);
+        let remote = remote.get(route_id);
+        let base = Route::from_file(&base_path(route_id)).ok();
+        let local_route = file.map(|(_, route)| route);
+        let action = SyncAction::of(local_route, remote, base.as_ref());
+        let path = file.map_or_else(
+            || args.dir.join(format!("{route_id}.json")),
+            |(path, _)| path.clone(),
+        );
+        if action == SyncAction::Conflict {
+            warnings.push(format!(
+                "warning: route {route_id} conflicts, compare with `route diff --file {}`",
+                path.display()
+            ));
+        }
+        plan.push((route_id.clone(), action, path, local_route, remote, base));
+    }
+
+    let count = |action| plan.iter().filter(|entry| entry.1 == action).count();
+    let summary = format!(
+        "pull: {}, push: {}, conflict: {}, in sync: {}",
+        count(SyncAction::Pull),
+        count(SyncAction::Push),
+        count(SyncAction::Conflict),
+        count(SyncAction::InSync)
+    );
+    let changes = plan
+        .iter()
+        .map(|(route_id, action, path, local, remote, _)| {
+            let fields = diff::fields(
+                &serde_json::to_value(remote)?,
+                &serde_json::to_value(local)?,
+            );
+            Ok(json!({
+                "route_id": route_id,
+                "action": action,
+                "file": path,
+                "fields": fields,
+            }))
+        })
+        .collect::<Result<Vec<_>>>()?
+        .pretty_json()?;
+    let output = warnings
+        .into_iter()
+        .chain([summary, changes])
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    if !args.commit {
+        return Msg::dry_run(output);
+    }
+
+    fs::create_dir_all(&base_dir)
+        .with_context(|| format!("creating directory {}", base_dir.display()))?;
+    for (route_id, action, path, local, remote, base) in plan {
+        let synced = match (action, local, remote) {
+            (SyncAction::Pull, _, Some(remote)) => {
+                remote.write(&path)?;
+                remote.clone()
+            }
+            (SyncAction::Push, Some(local), _) => client
+                .push(local.clone(), &keypair)
+                .await
+                .with_context(|| format!("pushing route {route_id}"))?,
+            (SyncAction::InSync, _, Some(remote)) if base.as_ref() != Some(remote) => {
+                remote.clone()
+            }
+            _ => continue,
+        };
+        synced.write(&base_path(&route_id))?;
+    }
+
+    Msg::ok(output)
+}
+
+pub async fn push_all_routes(args: PushAllRoutes) -> Result<Msg> {
+    let mut paths = vec![];
+    for entry in
+        fs::read_dir(&args.dir).with_context(|| format!("reading {}", args.dir.display()))?
     {
+        let path = entry?.path();
+        if path.is_file() && is_route_file(&path) {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    if paths.is_empty() {
+        return Msg::err(format!("no route files in {}", args.dir.display()));
+    }
+
+    let mut routes = vec![];
+    let mut invalid = vec![];
+    for path in paths {
+        match Route::from_file(&path).and_then(|route| route.validate().map(|_| route)) {
+            Ok(route) => routes.push((path, route)),
+            Err(err) => invalid.push(json!({ "file": path, "error": format!("{err:#}") })),
+        }
+    }
+    if !invalid.is_empty() {
+        return Msg::err(format!(
+            "{} of {} route files are invalid, nothing pushed\n{}",
+            invalid.len(),
+            invalid.len() + routes.len(),
+            invalid.pretty_json()?
+        ));
+    }
+
+    if !args.commit {
+        let files: Vec<_> = routes
+            .iter()
+            .map(|(path, route)| json!({ "file": path, "route_id": route.id }))
+            .collect();
+        return Msg::dry_run(format!(
+            "push {} routes from {}\n{}",
+            routes.len(),
+            args.dir.display(),
+            files.pretty_json()?
+        ));
+    }
+
+    let keypair = args.keypair.to_keypair()?;
+    let mut results: Vec<_> = futures::stream::iter(routes)
+        .map(|(path, route)| {
+            let (args, keypair) = (&args, &keypair);
+            async move {
+                let route_id = route.id.clone();
+                let pushed = async {
+                    let mut client =
+                        client::RouteClient::new(&args.config_host, &args.connection).await?;
+                    client.push(route, keypair).await
+                }
+                .await;
+                (path, route_id, pushed)
+            }
+        })
+        .buffer_unordered(args.jobs.into())
+        .collect()
+        .await;
+    results.sort_by(|(a, _, _), (b, _, _)| a.cmp(b));
+
+    let failed = results
+        .iter()
+        .filter(|(_, _, pushed)| pushed.is_err())
+        .count();
+    let report: Vec<_> = results
+        .into_iter()
+        .map(|(path, route_id, pushed)| match pushed {
+            Ok(_) => json!({ "file": path, "route_id": route_id, "status": "pushed" }),
+            Err(err) => json!({
+                "file": path,
+                "route_id": route_id,
+                "status": "failed",
+                "error": err.to_string(),
+            }),
+        })
+        .collect();
+    let summary = format!(
+        "pushed {} of {} routes\n{}",
+        report.len() - failed,
+        report.len(),
+        report.pretty_json()?
+    );
+    if failed > 0 {
+        return Ok(Msg::Error(summary, ExitCode::Failure));
+    }
+    Msg::ok(summary)
+}
+
+/// Entries sent per update stream by `route migrate`. A step that fails
+/// leaves at most this many entries partly applied.
+const MIGRATE_CHUNK_SIZE: usize = 5_000;
+
+/// A step of `route migrate`, one update stream of at most
+/// `MIGRATE_CHUNK_SIZE` entries.
+#[derive(Debug, PartialEq)]
+enum Migrated {
+    AddedDevaddrs(Vec<DevaddrRange>),
+    RemovedDevaddrs(Vec<DevaddrRange>),
+    AddedEuis(Vec<Eui>),
+    RemovedEuis(Vec<Eui>),
+}
+
+impl Migrated {
+    /// The step putting back what this one changed.
+    fn inverse(self) -> Self {
+        match self {
+            Self::AddedDevaddrs(ranges) => Self::RemovedDevaddrs(ranges),
+            Self::RemovedDevaddrs(ranges) => Self::AddedDevaddrs(ranges),
+            Self::AddedEuis(euis) => Self::RemovedEuis(euis),
+            Self::RemovedEuis(euis) => Self::AddedEuis(euis),
+        }
+    }
+
+    async fn apply(&self, client: &mut client::RouteClient, keypair: &Keypair) -> Result {
+        match self {
+            Self::AddedDevaddrs(ranges) => {
+                client.add_devaddrs(ranges.clone(), keypair).await.map(drop)
+            }
+            Self::RemovedDevaddrs(ranges) => client
+                .remove_devaddrs(ranges.clone(), keypair)
+                .await
+                .map(drop),
+            Self::AddedEuis(euis) => client.add_euis(euis.clone(), keypair).await.map(drop),
+            Self::RemovedEuis(euis) => client.remove_euis(euis.clone(), keypair).await.map(drop),
+        }
+    }
+}
+
+/// The steps of `route migrate` in the order they are sent. Devaddr Ranges
+/// leave their Route before joining another, EUI pairs join before they
+/// leave.
+fn migration_steps(
+    removed_devaddrs: Vec<DevaddrRange>,
+    added_devaddrs: Vec<DevaddrRange>,
+    added_euis: Vec<Eui>,
+    removed_euis: Vec<Eui>,
+) -> Vec<Migrated> {
+    fn chunked<T: Clone>(entries: Vec<T>, step: fn(Vec<T>) -> Migrated) -> Vec<Migrated> {
+        entries
+            .chunks(MIGRATE_CHUNK_SIZE)
+            .map(|chunk| step(chunk.to_vec()))
+            .collect()
+    }
+    [
+        chunked(removed_devaddrs, Migrated::RemovedDevaddrs),
+        chunked(added_devaddrs, Migrated::AddedDevaddrs),
+        chunked(added_euis, Migrated::AddedEuis),
+        chunked(removed_euis, Migrated::RemovedEuis),
+    ]
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+/// The steps putting back `applied` and the `failed` step after them, most
+/// recent first. The failed stream may have been applied in part, so all of
+/// it is undone, the entries it never reached are already as they were.
+fn undo_steps(applied: Vec<Migrated>, failed: Migrated) -> Vec<Migrated> {
+    applied
+        .into_iter()
+        .chain([failed])
+        .rev()
+        .map(Migrated::inverse)
+        .collect()
+}
+
+/// Undoing a failed migration is best-effort, a step that fails to undo is
+/// reported and the ones before it are still tried.
+pub async fn migrate_route(args: MigrateRoute) -> Result<Msg> {
+    if args.from == args.to {
+        return Msg::err("--from and --to are the same route".to_string());
+    }
+    let with_euis = args.euis || !args.devaddrs;
+    let with_devaddrs = args.devaddrs || !args.euis;
+    let keypair = args.keypair.to_keypair()?;
+    let mut client = client::RouteClient::new(&args.config_host, &args.connection).await?;
+
+    // Only the entries of `--to` also on `--from` are kept as they arrive.
+    let (mut source_euis, mut source_devaddrs) = (vec![], vec![]);
+    let (mut new_euis, mut present_euis) = (vec![], vec![]);
+    let (mut new_devaddrs, mut present_devaddrs) = (vec![], vec![]);
+    if with_euis {
+        source_euis = client.get_euis(&args.from, &keypair).await?;
+        let rebound: Vec<Eui> = source_euis
+            .iter()
+            .map(|eui| Eui {
+                route_id: args.to.clone(),
+                ..eui.clone()
+            })
+            .collect();
+        let wanted: HashSet<&Eui> = rebound.iter().collect();
+        let existing: Vec<Eui> = client
+            .stream_euis(&args.to, &keypair)
+            .await?
+            .try_filter(|eui| future::ready(wanted.contains(eui)))
+            .try_collect()
+            .await?;
+        (new_euis, present_euis) = split_present(rebound, existing);
+    }
+    if with_devaddrs {
+        source_devaddrs = client.get_devaddrs(&args.from, &keypair).await?;
+        let rebound: Vec<DevaddrRange> = source_devaddrs
+            .iter()
+            .map(|range| DevaddrRange {
+                route_id: args.to.clone(),
+                ..range.clone()
+            })
+            .collect();
+        let wanted: HashSet<&DevaddrRange> = rebound.iter().collect();
+        let existing: Vec<DevaddrRange> = client
+            .stream_devaddrs(&args.to, &keypair)
+            .await?
+            .try_filter(|range| future::ready(wanted.contains(range)))
+            .try_collect()
+            .await?;
+        (new_devaddrs, present_devaddrs) = split_present(rebound, existing);
+    }
+
+    let verb = if args.remove_source { "move" } else { "copy" };
+    let summary = format!(
+        "{verb} {} EUI pairs ({} already on {}) and {} Devaddr Ranges ({} already on {}) from {} to {}",
+        new_euis.len(),
+        present_euis.len(),
+        args.to,
+        new_devaddrs.len(),
+        present_devaddrs.len(),
+        args.to,
+        args.from,
+        args.to
+    );
+    if !args.commit {
+        return Msg::dry_run(summary);
+    }
+    if !args.remove_source {
+        source_euis.clear();
+        source_devaddrs.clear();
+    }
+
+    let mut migrated = vec![];
+    for step in migration_steps(source_devaddrs, new_devaddrs, new_euis, source_euis) {
+        if let Err(err) = step.apply(&mut client, &keypair).await {
+            let undone = undo_migration(&mut client, undo_steps(migrated, step), &keypair).await;
+            return Msg::failed(format!("{verb} failed, {undone}"), err);
+        }
+        migrated.push(step);
+    }
+
+    Msg::ok(summary)
+}
+
+async fn undo_migration(
+    client: &mut client::RouteClient,
+    steps: Vec<Migrated>,
+    keypair: &Keypair,
+) -> String {
+    let mut failures = vec![];
+    for step in steps {
+        if let Err(err) = step.apply(client, keypair).await {
+            failures.push(err.to_string());
+        }
+    }
+    if failures.is_empty() {
+        "earlier steps undone".to_string()
+    } else {
+        format!("could not undo earlier steps: {}", failures.join(", "))
+    }
+}
+
+pub async fn get_route(args: GetRoute) -> Result<Msg> {
+    let mut client = client::RouteClient::new(&args.config_host, &args.connection).await?;
+    let keypair = args.keypair.to_keypair()?;
+    match client.get(&args.route_id, &keypair).await {
+        Ok(route) if args.summary => {
+            let euis = client
+                .stream_euis(&route.id, &keypair)
+                .await?
+                .try_fold(0, |count, _| async move { Ok(count + 1) })
+                .await?;
+            let devaddrs = client.get_devaddrs(&route.id, &keypair).await?;
+            Msg::plain(route.summary(euis, &devaddrs))
+        }
         Ok(route) => Msg::ok(route.pretty_json()?),
-        Err(err) => Msg::err(format!("could not get route: {err}")),
+        Err(err) => Msg::failed("could not get route", err),
     }
 }
 
 pub async fn new_route(args: NewRoute) -> Result<Msg> {
-    let mut client = client::RouteClient::new(&args.config_host).await?;
+    let mut client = client::RouteClient::new(&args.config_host, &args.connection).await?;
     let route = Route::new(args.net_id, args.oui, args.max_copies);
 
     if !args.commit {
@@ -43,12 +665,12 @@ pub async fn new_route(args: NewRoute) -> Result<Msg> {
             created_route.id,
             created_route.pretty_json()?
         )),
-        Err(err) => Msg::err(format!("route not created: {err}")),
+        Err(err) => Msg::failed("route not created", err),
     }
 }
 
 pub async fn delete_route(args: DeleteRoute) -> Result<Msg> {
-    let mut client = client::RouteClient::new(&args.config_host).await?;
+    let mut client = client::RouteClient::new(&args.config_host, &args.connection).await?;
 
     if !args.commit {
         return Msg::dry_run(format!("delete {}", args.route_id));
@@ -59,12 +681,12 @@ pub async fn delete_route(args: DeleteRoute) -> Result<Msg> {
         .await
     {
         Ok(removed_route) => Msg::ok(format!("deleted route {}", removed_route.id)),
-        Err(err) => Msg::err(format!("route not deleted: {err}")),
+        Err(err) => Msg::failed("route not deleted", err),
     }
 }
 
 pub async fn update_max_copies(args: UpdateMaxCopies) -> Result<Msg> {
-    let mut client = client::RouteClient::new(&args.config_host).await?;
+    let mut client = client::RouteClient::new(&args.config_host, &args.connection).await?;
     let keypair = args.keypair.to_keypair()?;
 
     let mut route = client.get(&args.route_id, &keypair).await?;
@@ -73,27 +695,17 @@ pub async fn update_max_copies(args: UpdateMaxCopies) -> Result<Msg> {
     route.max_copies = args.max_copies;
 
     if !args.commit {
-        return Msg::dry_run(format!(
-            "Updated {}\n== Old\n{}\n== New\n{}",
-            route.id,
-            old_route.pretty_json()?,
-            route.pretty_json()?
-        ));
+        return Msg::dry_run(update_preview(&old_route, &route)?);
     }
 
     match client.push(route, &keypair).await {
-        Ok(updated_route) => Msg::ok(format!(
-            "Updated {}\n== Old\n{}\n== New\n{}",
-            updated_route.id,
-            old_route.pretty_json()?,
-            updated_route.pretty_json()?
-        )),
-        Err(err) => Msg::err(format!("could not update max_copies: {err}")),
+        Ok(updated_route) => Msg::ok(updated(&old_route, &updated_route)?),
+        Err(err) => Msg::failed("could not update max_copies", err),
     }
 }
 
 pub async fn update_server(args: UpdateServer) -> Result<Msg> {
-    let mut client = client::RouteClient::new(&args.config_host).await?;
+    let mut client = client::RouteClient::new(&args.config_host, &args.connection).await?;
     let keypair = args.keypair.to_keypair()?;
 
     let mut route = client.get(&args.route_id, &keypair).await?;
@@ -103,101 +715,164 @@ pub async fn update_server(args: UpdateServer) -> Result<Msg> {
     route.server.port = args.port;
 
     if !args.commit {
-        return Msg::dry_run(format!(
-            "Updated {}\n== Old\n{}\n== New\n{}",
-            route.id,
-            old_route.pretty_json()?,
-            route.pretty_json()?
-        ));
+        return Msg::dry_run(update_preview(&old_route, &route)?);
     }
 
     match client.push(route, &keypair).await {
-        Ok(updated_route) => Msg::ok(format!(
-            "Updated {}\n== Old\n{}\n== New\n{}",
-            updated_route.id,
-            old_route.pretty_json()?,
-            updated_route.pretty_json()?
-        )),
-
-        Err(err) => Msg::err(format!("could not update server host and port: {err}")),
+        Ok(updated_route) => Msg::ok(updated(&old_route, &updated_route)?),
+        Err(err) => Msg::failed("could not update server host and port", err),
     }
 }
 
 pub async fn update_http(args: UpdateHttp) -> Result<Msg> {
-    let mut client = client::RouteClient::new(&args.config_host).await?;
+    let mut client = client::RouteClient::new(&args.config_host, &args.connection).await?;
     let keypair = args.keypair.to_keypair()?;
 
     let mut route = client.get(&args.route_id, &keypair).await?;
     let old_route = route.clone();
 
-    let http = Protocol::make_http(args.dedupe_timeout, args.path, args.auth_header);
-    route.server.protocol = Some(http);
+    // Fields that are not given keep their current value.
+    let http = match route.server.protocol.as_ref().and_then(Protocol::as_http) {
+        Some(current) => current.update(
+            args.flow_type,
+            args.dedupe_timeout,
+            args.path,
+            args.auth_header,
+        )?,
+        None => {
+            let Some(path) = args.path else {
+                return Msg::err(format!(
+                    "route {} does not use http yet, --path is required",
+                    route.id
+                ));
+            };
+            Http::new(
+                args.flow_type.unwrap_or(FlowType::Async),
+                args.dedupe_timeout.unwrap_or(DEFAULT_DEDUPE_TIMEOUT),
+                path,
+                args.auth_header,
+            )?
+        }
+    };
+    route.server.protocol = Some(Protocol::Http(http));
+    let warning = gwmp_replaced_warning(&old_route, &args.gwmp_history);
 
     if !args.commit {
-        return Msg::dry_run(format!(
-            "Updated {}\n== Old\n{}\n== New\n{}",
-            route.id,
-            old_route.pretty_json()?,
-            route.pretty_json()?
-        ));
+        return Msg::dry_run(format!("{warning}{}", update_preview(&old_route, &route)?));
     }
 
     match client.push(route, &keypair).await {
-        Ok(updated_route) => Msg::ok(format!(
-            "Updated {}\n== Old\n{}\n== New\n{}",
-            updated_route.id,
-            old_route.pretty_json()?,
-            updated_route.pretty_json()?
-        )),
-        Err(err) => Msg::err(format!("Could not update http protocol: {err}")),
+        Ok(updated_route) => {
+            let not_saved = save_gwmp_mapping(&old_route, &args.gwmp_history);
+            Msg::ok(format!(
+                "{warning}{not_saved}{}",
+                updated(&old_route, &updated_route)?
+            ))
+        }
+        Err(err) => Msg::failed("Could not update http protocol", err),
     }
 }
 
 pub async fn add_gwmp_region(args: AddGwmpRegion) -> Result<Msg> {
-    let mut client = client::RouteClient::new(&args.config_host).await?;
+    let mut client = client::RouteClient::new(&args.config_host, &args.connection).await?;
     let keypair = args.keypair.to_keypair()?;
 
     let mut route = client.get(&args.route_id, &keypair).await?;
     let old_route = route.clone();
     let old_protocol = route.server.protocol;
+    let mut history = server::read_gwmp_history(&args.gwmp_history)?;
 
-    let gwmp = if let Some(protocol) = old_protocol.as_ref() {
-        if protocol.is_gwmp() {
+    // A route coming back to gwmp gets the mapping it had before it left.
+    let map = Protocol::make_gwmp_mapping(args.region.clone(), args.region_port);
+    let (gwmp, restored) = match old_protocol.as_ref() {
+        Some(protocol) if protocol.is_gwmp() => {
             let mut new_protocol = protocol.clone();
-            let map = Protocol::make_gwmp_mapping(args.region, args.region_port);
             new_protocol.gwmp_add_mapping(map)?;
-            new_protocol
-        } else {
-            Protocol::make_gwmp(args.region, args.region_port)?
+            (new_protocol, None)
         }
-    } else {
-        Protocol::make_gwmp(args.region, args.region_port)?
+        _ => match history.remove(&route.id) {
+            Some(previous) => {
+                let mut gwmp = Gwmp::new(previous.clone())?;
+                gwmp.add_mapping(map)?;
+                (Protocol::Gwmp(gwmp), Some(previous))
+            }
+            None => (Protocol::make_gwmp(args.region, args.region_port)?, None),
+        },
     };
 
     route.server.protocol = Some(gwmp);
 
-    if !args.commit {
-        return Msg::dry_run(format!(
-            "Updated {}\n== Old\n{}\n== New\n{}",
-            route.id,
-            old_route.pretty_json()?,
-            route.pretty_json()?
+    let mut warning = match &restored {
+        Some(previous) => format!(
+            "restored the previous gwmp mapping of {} regions from {}\n",
+            previous.len(),
+            args.gwmp_history.display()
+        ),
+        None => String::new(),
+    };
+    if route.server.port == args.region_port {
+        warning.push_str(&format!(
+            "warning: gwmp port {} is also the server port\n",
+            args.region_port
         ));
     }
 
+    if !args.commit {
+        return Msg::dry_run(format!("{warning}{}", update_preview(&old_route, &route)?));
+    }
+
     match client.push(route, &keypair).await {
-        Ok(updated_route) => Msg::ok(format!(
-            "Updated {}\n== Old\n{}\n== New\n{}",
-            updated_route.id,
-            old_route.pretty_json()?,
-            updated_route.pretty_json()?
-        )),
-        Err(err) => Msg::err(format!("Could not update gwmp protocol: {err}")),
+        Ok(updated_route) => {
+            if restored.is_some() {
+                if let Err(err) = server::write_gwmp_history(&args.gwmp_history, &history) {
+                    warning.push_str(&format!(
+                        "warning: could not remove the restored gwmp mapping of {} from {}: {err:#}\n",
+                        args.route_id,
+                        args.gwmp_history.display()
+                    ));
+                }
+            }
+            Msg::ok(format!("{warning}{}", updated(&old_route, &updated_route)?))
+        }
+        Err(err) => Msg::failed("Could not update gwmp protocol", err),
+    }
+}
+
+pub async fn update_gwmp(args: UpdateGwmp) -> Result<Msg> {
+    let gwmp = match (&args.preset, args.all_regions) {
+        (Some(preset), _) => {
+            let user_presets = match &args.presets_file {
+                Some(path) => server::read_gwmp_presets(path)?,
+                None => Default::default(),
+            };
+            Protocol::from_gwmp_preset(preset, &user_presets)?
+        }
+        (None, true) => Protocol::make_gwmp_all_regions(args.base_port, args.step)?,
+        (None, false) => {
+            return Msg::err("pass `--all-regions` or `--preset <name>`".to_string());
+        }
+    };
+
+    let mut client = client::RouteClient::new(&args.config_host, &args.connection).await?;
+    let keypair = args.keypair.to_keypair()?;
+
+    let mut route = client.get(&args.route_id, &keypair).await?;
+    let old_route = route.clone();
+
+    route.server.protocol = Some(gwmp);
+
+    if !args.commit {
+        return Msg::dry_run(update_preview(&old_route, &route)?);
+    }
+
+    match client.push(route, &keypair).await {
+        Ok(updated_route) => Msg::ok(updated(&old_route, &updated_route)?),
+        Err(err) => Msg::failed("Could not update gwmp protocol", err),
     }
 }
 
 pub async fn remove_gwmp_region(args: RemoveGwmpRegion) -> Result<Msg> {
-    let mut client = client::RouteClient::new(&args.config_host).await?;
+    let mut client = client::RouteClient::new(&args.config_host, &args.connection).await?;
     let keypair = args.keypair.to_keypair()?;
 
     let mut route = client.get(&args.route_id, &keypair).await?;
@@ -215,27 +890,17 @@ pub async fn remove_gwmp_region(args: RemoveGwmpRegion) -> Result<Msg> {
     route.server.protocol = Some(new_protocol);
 
     if !args.commit {
-        return Msg::dry_run(format!(
-            "Updated {}\n== Old\n{}\n== New\n{}",
-            route.id,
-            old_route.pretty_json()?,
-            route.pretty_json()?
-        ));
+        return Msg::dry_run(update_preview(&old_route, &route)?);
     }
 
     match client.push(route, &keypair).await {
-        Ok(updated_route) => Msg::ok(format!(
-            "Updated {}\n== Old\n{}\n== New\n{}",
-            updated_route.id,
-            old_route.pretty_json()?,
-            updated_route.pretty_json()?
-        )),
-        Err(err) => Msg::err(format!("Could not update gwmp protocol: {err}")),
+        Ok(updated_route) => Msg::ok(updated(&old_route, &updated_route)?),
+        Err(err) => Msg::failed("Could not update gwmp protocol", err),
     }
 }
 
 pub async fn update_packet_router(args: UpdatePacketRouter) -> Result<Msg> {
-    let mut client = client::RouteClient::new(&args.config_host).await?;
+    let mut client = client::RouteClient::new(&args.config_host, &args.connection).await?;
     let keypair = args.keypair.to_keypair()?;
 
     let mut route = client.get(&args.route_id, &keypair).await?;
@@ -243,29 +908,82 @@ pub async fn update_packet_router(args: UpdatePacketRouter) -> Result<Msg> {
 
     let new_protocol = Protocol::default_packet_router();
     route.server.protocol = Some(new_protocol);
+    let warning = gwmp_replaced_warning(&old_route, &args.gwmp_history);
 
     if !args.commit {
-        return Msg::dry_run(format!(
-            "Updated {}\n== Old\n{}\n== New\n{}",
-            route.id,
-            old_route.pretty_json()?,
-            route.pretty_json()?
-        ));
+        return Msg::dry_run(format!("{warning}{}", update_preview(&old_route, &route)?));
     }
 
     match client.push(route, &keypair).await {
-        Ok(updated_route) => Msg::ok(format!(
-            "Updated {}\n== Old\n{}\n== New\n{}",
-            updated_route.id,
-            old_route.pretty_json()?,
-            updated_route.pretty_json()?
-        )),
-        Err(_) => todo!(),
+        Ok(updated_route) => {
+            let not_saved = save_gwmp_mapping(&old_route, &args.gwmp_history);
+            Msg::ok(format!(
+                "{warning}{not_saved}{}",
+                updated(&old_route, &updated_route)?
+            ))
+        }
+        Err(err) => Msg::failed("Could not update packet router protocol", err),
+    }
+}
+
+/// Dry run of a Route update, the changed lines marked `-` and `+`.
+fn update_preview(old: &Route, new: &Route) -> Result<String> {
+    Ok(format!(
+        "Updated {}\n{}",
+        new.id,
+        diff::lines(&old.pretty_json()?, &new.pretty_json()?, diff::use_color())
+    ))
+}
+
+/// Report of a Route update, the whole Route before and after.
+fn updated(old: &Route, new: &Route) -> Result<String> {
+    Ok(format!(
+        "Updated {}\n== Old\n{}\n== New\n{}",
+        new.id,
+        old.pretty_json()?,
+        new.pretty_json()?
+    ))
+}
+
+/// Warn that switching `route` to another protocol replaces its gwmp mapping.
+fn gwmp_replaced_warning(route: &Route, history: &PathBuf) -> String {
+    match route.server.protocol.as_ref().and_then(Protocol::as_gwmp) {
+        Some(gwmp) if !gwmp.mapping.is_empty() => format!(
+            "warning: the gwmp mapping of {} is replaced, it is kept in {} for `update add-gwmp-region` to restore\n",
+            route.id,
+            history.display()
+        ),
+        _ => String::new(),
+    }
+}
+
+/// Keep the gwmp mapping of `route`, if it has one, in the history file.
+///
+/// The route has already been updated, so a history that cannot be written
+/// is returned as a warning rather than failing the command.
+fn save_gwmp_mapping(route: &Route, history: &PathBuf) -> String {
+    let save = || -> Result {
+        if let Some(gwmp) = route.server.protocol.as_ref().and_then(Protocol::as_gwmp) {
+            if !gwmp.mapping.is_empty() {
+                let mut saved = server::read_gwmp_history(history)?;
+                saved.insert(route.id.clone(), gwmp.mapping.clone());
+                server::write_gwmp_history(history, &saved)?;
+            }
+        }
+        Ok(())
+    };
+    match save() {
+        Ok(()) => String::new(),
+        Err(err) => format!(
+            "warning: could not keep the gwmp mapping of {} in {}: {err:#}\n",
+            route.id,
+            history.display()
+        ),
     }
 }
 
 pub async fn activate_route(args: ActivateRoute) -> Result<Msg> {
-    let mut client = client::RouteClient::new(&args.config_host).await?;
+    let mut client = client::RouteClient::new(&args.config_host, &args.connection).await?;
     let keypair = args.keypair.to_keypair()?;
 
     let mut route = client.get(&args.route_id, &keypair).await?;
@@ -274,27 +992,17 @@ pub async fn activate_route(args: ActivateRoute) -> Result<Msg> {
     route.active = true;
 
     if !args.commit {
-        return Msg::dry_run(format!(
-            "Updated {}\n== Old\n{}\n== New\n{}",
-            route.id,
-            old_route.pretty_json()?,
-            route.pretty_json()?
-        ));
+        return Msg::dry_run(update_preview(&old_route, &route)?);
     }
 
     match client.push(route, &keypair).await {
-        Ok(updated_route) => Msg::ok(format!(
-            "Updated {}\n== Old\n{}\n== New\n{}",
-            updated_route.id,
-            old_route.pretty_json()?,
-            updated_route.pretty_json()?
-        )),
-        Err(err) => Msg::err(format!("Could not activate route: {err}")),
+        Ok(updated_route) => Msg::ok(updated(&old_route, &updated_route)?),
+        Err(err) => Msg::failed("Could not activate route", err),
     }
 }
 
 pub async fn deactivate_route(args: DeactivateRoute) -> Result<Msg> {
-    let mut client = client::RouteClient::new(&args.config_host).await?;
+    let mut client = client::RouteClient::new(&args.config_host, &args.connection).await?;
     let keypair = args.keypair.to_keypair()?;
 
     let mut route = client.get(&args.route_id, &keypair).await?;
@@ -303,59 +1011,367 @@ pub async fn deactivate_route(args: DeactivateRoute) -> Result<Msg> {
     route.active = false;
 
     if !args.commit {
-        return Msg::dry_run(format!(
-            "Updated {}\n== Old\n{}\n== New\n{}",
-            route.id,
-            old_route.pretty_json()?,
-            route.pretty_json()?
-        ));
+        return Msg::dry_run(update_preview(&old_route, &route)?);
     }
 
     match client.push(route, &keypair).await {
-        Ok(updated_route) => Msg::ok(format!(
-            "Updated {}\n== Old\n{}\n== New\n{}",
-            updated_route.id,
-            old_route.pretty_json()?,
-            updated_route.pretty_json()?
-        )),
-        Err(err) => Msg::err(format!("Could not deactivate route: {err}")),
+        Ok(updated_route) => Msg::ok(updated(&old_route, &updated_route)?),
+        Err(err) => Msg::failed("Could not deactivate route", err),
+    }
+}
+
+pub mod bundle {
+    use crate::{
+        bundle::RouteBundle,
+        client,
+        cmds::{ExportBundle, ImportBundle, PathBufKeypair},
+        output,
+        route::{Route, RouteId},
+        split_present, Msg, PrettyJson, Result,
+    };
+    use anyhow::{anyhow, Context};
+    use std::{fs, io::Write};
+
+    pub async fn export_bundle(args: ExportBundle) -> Result<Msg> {
+        if output::is_terminal() {
+            return Err(anyhow!(
+                "refusing to write a tar archive to a terminal, pass `--out <file>`"
+            ));
+        }
+        let keypair = args.keypair.to_keypair()?;
+        let mut client = client::RouteClient::new(&args.config_host, &args.connection).await?;
+        let route = match client.get(&args.route_id, &keypair).await {
+            Ok(route) => route,
+            Err(err) => return Msg::failed("could not get route", err),
+        };
+        let euis = client.get_euis(&route.id, &keypair).await?;
+        let devaddrs = client.get_devaddrs(&route.id, &keypair).await?;
+        let filters = client::SkfClient::new(&args.config_host, &args.connection)
+            .await?
+            .list_filters(route.oui, &keypair)
+            .await?;
+
+        let bundle = RouteBundle::new(route, euis, devaddrs, filters);
+        let mut out = output::open()?;
+        out.write_all(&bundle.to_tar()?)?;
+        out.flush()?;
+        Msg::plain(String::new())
+    }
+
+    pub async fn import_bundle(args: ImportBundle) -> Result<Msg> {
+        let tar =
+            fs::read(&args.file).with_context(|| format!("reading {}", args.file.display()))?;
+        let mut bundle = RouteBundle::from_tar(&tar)
+            .with_context(|| format!("parsing {}", args.file.display()))?;
+        let route = Route {
+            id: RouteId::default(),
+            ..bundle.route.clone()
+        };
+        let oui = route.oui;
+
+        let keypair = args.keypair.to_keypair()?;
+        let mut skf_client = client::SkfClient::new(&args.config_host, &args.connection).await?;
+        let existing = skf_client.list_filters(oui, &keypair).await?;
+        let (missing, present) = split_present(bundle.filters.clone(), existing);
+        let contents = format!(
+            "{} EUI pairs, {} Devaddr Ranges, and {} Session Key Filters ({} already present)",
+            bundle.euis.len(),
+            bundle.devaddrs.len(),
+            missing.len(),
+            present.len()
+        );
+
+        if !args.commit {
+            return Msg::dry_run(format!(
+                "create route for oui {oui} with {contents}\n{}",
+                route.pretty_json()?
+            ));
+        }
+
+        let mut client = client::RouteClient::new(&args.config_host, &args.connection).await?;
+        let created = match client.create_route(route, &keypair).await {
+            Ok(created) => created,
+            Err(err) => return Msg::failed("route not created", err),
+        };
+        bundle.rebind(&created.id);
+        let created_but =
+            |what| format!("created route {} but could not add its {what}", created.id);
+        if !bundle.euis.is_empty() {
+            if let Err(err) = client.add_euis(bundle.euis, &keypair).await {
+                return Msg::failed(created_but("euis"), err);
+            }
+        }
+        if !bundle.devaddrs.is_empty() {
+            if let Err(err) = client.add_devaddrs(bundle.devaddrs, &keypair).await {
+                return Msg::failed(created_but("devaddrs"), err);
+            }
+        }
+        if !missing.is_empty() {
+            if let Err(err) = skf_client.add_filters(missing, &keypair).await {
+                return Msg::failed(created_but("session key filters"), err);
+            }
+        }
+
+        Msg::ok(format!(
+            "created route {} with {contents}\n{}",
+            created.id,
+            created.pretty_json()?
+        ))
     }
 }
 
 pub mod euis {
     use crate::{
+        checkpoint::Checkpoint,
+        chirpstack::{self, ExportedDevice},
         client,
-        cmds::{AddEui, ClearEuis, ListEuis, PathBufKeypair, RemoveEui},
-        Eui, Msg, PrettyJson, Result,
+        cmds::{
+            count_entries, send_chunks, write_json_list, write_jsonl, write_template, AddEui,
+            ClearEuis, ExportEuis, ListEuis, MoveEuis, PathBufKeypair, RemoveEui, SyncEuis,
+        },
+        diff, missing_positions, output,
+        render::{OutputFormat, Template},
+        split_present, sync_delta, wildcard_overlaps, AddReport, Eui, ImportDiff, Msg, PrettyJson,
+        Result,
+    };
+    use anyhow::{anyhow, Context};
+    use futures::{future, TryStreamExt};
+    use helium_crypto::Keypair;
+    use serde_json::json;
+    use std::{
+        collections::HashSet,
+        fs,
+        io::{BufWriter, Write},
     };
 
+    /// EUI pairs sent per update stream by `euis add --from-file` and
+    /// `euis remove --from-file`.
+    const EUI_CHUNK_SIZE: usize = 5_000;
+
+    /// A warning per App EUI with both a wildcard and specific pairs among
+    /// `euis`, the specific ones never match anything the wildcard does not.
+    fn wildcard_warnings<'a>(euis: impl IntoIterator<Item = &'a Eui>) -> Vec<String> {
+        wildcard_overlaps(euis)
+            .into_iter()
+            .map(|(app_eui, count)| {
+                format!("warning: app_eui {app_eui} has a wildcard pair and {count} specific pairs")
+            })
+            .collect()
+    }
+
     pub async fn list_euis(args: ListEuis) -> Result<Msg> {
-        let mut client = client::EuiClient::new(&args.config_host).await?;
-        let euis_for_route = client
-            .get_euis(&args.route_id, &args.keypair.to_keypair()?)
-            .await?;
+        let mut client = client::EuiClient::new(&args.config_host, &args.connection).await?;
+        let keypair = args.keypair.to_keypair()?;
+        let wanted = |eui: &Eui| {
+            (args.app_eui.is_none() || args.app_eui == Some(eui.app_eui))
+                && (args.dev_eui.is_none() || args.dev_eui == Some(eui.dev_eui))
+                && (!args.wildcards_only || eui.is_wildcard())
+        };
+
+        if args.count {
+            let stream = client
+                .stream_euis(&args.route_id, &keypair)
+                .await?
+                .try_filter(|eui| future::ready(wanted(eui)));
+            let group_by: Option<fn(&Eui) -> String> =
+                args.by_app_eui.then_some(|eui| eui.app_eui.to_string());
+            return count_entries(stream, group_by).await;
+        }
+
+        // Without streaming, `--porcelain` and the other outputs that wrap
+        // the whole list get all of it as JSON, whatever `--format` says.
+        if output::streams() {
+            if let Some(template) = &args.template {
+                let stream = client
+                    .stream_euis(&args.route_id, &keypair)
+                    .await?
+                    .try_filter(|eui| future::ready(wanted(eui)));
+                return write_template(stream, &Template::new(template, "eui")?).await;
+            }
+
+            if args.format == OutputFormat::Jsonl {
+                let stream = client
+                    .stream_euis(&args.route_id, &keypair)
+                    .await?
+                    .try_filter(|eui| future::ready(wanted(eui)));
+                return write_jsonl(stream).await;
+            }
+
+            if args.format == OutputFormat::Json && output::streams_to_file() {
+                let stream = client
+                    .stream_euis(&args.route_id, &keypair)
+                    .await?
+                    .try_filter(|eui| future::ready(wanted(eui)));
+                return write_json_list(stream).await;
+            }
+
+            if args.format == OutputFormat::Csv {
+                // Written as the pairs arrive, a Route can hold more than fits in memory.
+                let mut stream = client.stream_euis(&args.route_id, &keypair).await?;
+                let mut out = BufWriter::new(output::open()?);
+                writeln!(out, "app_eui,dev_eui")?;
+                while let Some(eui) = stream.try_next().await? {
+                    if wanted(&eui) {
+                        writeln!(
+                            out,
+                            "{},{}",
+                            output::hex(eui.app_eui),
+                            output::hex(eui.dev_eui)
+                        )?;
+                    }
+                }
+                out.flush()?;
+                return Msg::plain(String::new());
+            }
+        }
+
+        let euis_for_route: Vec<Eui> = client
+            .get_euis(&args.route_id, &keypair)
+            .await?
+            .into_iter()
+            .filter(wanted)
+            .collect();
 
+        if let Some(template) = &args.template {
+            return Msg::plain(Template::new(template, "eui")?.render_all(&euis_for_route)?);
+        }
         Msg::ok(euis_for_route.pretty_json()?)
     }
 
+    /// The whole file is parsed before connecting, a file with bad lines is
+    /// rejected listing all of them.
     pub async fn add_eui(args: AddEui) -> Result<Msg> {
-        let mut client = client::EuiClient::new(&args.config_host).await?;
-        let eui_pair = Eui::new(args.route_id.clone(), args.app_eui, args.dev_eui)?;
+        let mut pairs = args.pair.clone();
+        if let (Some(app_eui), Some(dev_eui)) = (args.app_eui, args.dev_eui) {
+            pairs.insert(0, (app_eui, dev_eui));
+        }
+        let mut eui_pairs = pairs
+            .into_iter()
+            .map(|(app_eui, dev_eui)| Eui::new(args.route_id.clone(), app_eui, dev_eui))
+            .collect::<Result<Vec<_>>>()?;
+        if let Some(path) = &args.from_file {
+            let csv =
+                fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+            eui_pairs.extend(
+                Eui::from_csv(&args.route_id, &csv)
+                    .with_context(|| format!("parsing {}", path.display()))?,
+            );
+        }
+
+        let checkpoint = args
+            .from_file
+            .as_ref()
+            .map(|path| Checkpoint::new(path, &args.route_id, eui_pairs.len()));
+        let confirmed = match &checkpoint {
+            Some(checkpoint) if args.resume => checkpoint.confirmed()?,
+            _ => 0,
+        };
+        let pending = &eui_pairs[confirmed..];
+
+        let keypair = args.keypair.to_keypair()?;
+        let mut client = client::EuiClient::new(&args.config_host, &args.connection).await?;
+        // Only the pairs of the App EUIs being added are kept, a Route can
+        // hold more than fits in memory and the wildcard warnings need no
+        // others.
+        let app_euis: HashSet<u64> = pending.iter().map(|eui| eui.app_eui.0).collect();
+        let existing: Vec<Eui> = client
+            .stream_euis(&args.route_id, &keypair)
+            .await?
+            .try_filter(|eui| future::ready(app_euis.contains(&eui.app_eui.0)))
+            .try_collect()
+            .await?;
+        let overlaps = wildcard_warnings(existing.iter().chain(pending));
+        let present: HashSet<&Eui> = existing.iter().collect();
+        let missing = missing_positions(pending, existing.iter().cloned());
+        let diff = ImportDiff::new(pending, &missing);
+        let report =
+            AddReport::new(diff.to_add, diff.already_present).with_duplicates(diff.duplicates);
+        let mut lines = vec![];
+        // A file can hold too many pairs to list them one by one.
+        if args.from_file.is_none() {
+            let sent: HashSet<usize> = missing.iter().copied().collect();
+            let (added, skipped): (Vec<usize>, Vec<usize>) =
+                (0..pending.len()).partition(|i| sent.contains(i));
+            let (already_present, repeated): (Vec<usize>, Vec<usize>) = skipped
+                .into_iter()
+                .partition(|&i| present.contains(&pending[i]));
+            lines.extend(
+                added
+                    .into_iter()
+                    .map(|i| format!("added {:?} to {}", pending[i], args.route_id)),
+            );
+            lines.extend(
+                already_present
+                    .into_iter()
+                    .map(|i| format!("already present {:?} on {}", pending[i], args.route_id)),
+            );
+            lines.extend(
+                repeated
+                    .into_iter()
+                    .map(|i| format!("duplicate {:?} in the input", pending[i])),
+            );
+        }
+        lines.extend(overlaps);
+        if let Some(checkpoint) = checkpoint.as_ref().filter(|_| confirmed > 0) {
+            lines.push(format!(
+                "resumed after {confirmed} of {} pairs in {}",
+                eui_pairs.len(),
+                checkpoint.path().display()
+            ));
+        }
+        if args.from_file.is_some() {
+            lines.push(diff.to_string());
+        } else {
+            lines.push(report.to_string());
+        }
+        let summary = lines.join("\n");
 
         if !args.commit {
-            return Msg::dry_run(format!("added {eui_pair:?} to {}", args.route_id));
+            return Msg::dry_run(summary);
         }
 
-        client
-            .add_euis(vec![eui_pair.clone()], &args.keypair.to_keypair()?)
-            .await?;
+        // Chunks are confirmed in order, so the checkpoint never skips a
+        // chunk still in flight.
+        let (added, failure) = send_chunks(
+            missing.chunks(EUI_CHUNK_SIZE),
+            args.parallel,
+            |chunk| {
+                let batch = chunk.iter().map(|&i| pending[i].clone()).collect();
+                let (mut client, keypair) = (client.clone(), &keypair);
+                async move { client.add_euis(batch, keypair).await }
+            },
+            |chunk| match &checkpoint {
+                Some(checkpoint) => checkpoint.save(confirmed + chunk[chunk.len() - 1] + 1),
+                None => Ok(()),
+            },
+        )
+        .await?;
+        if let Some(err) = failure {
+            let report = AddReport {
+                added,
+                failed: missing.len() - added,
+                ..report
+            };
+            let hint = if checkpoint.is_some() {
+                ", pass --resume to continue"
+            } else {
+                ""
+            };
+            return Msg::failed(format!("{report}{hint}\ncould not add euis"), err);
+        }
+        if let Some(checkpoint) = &checkpoint {
+            checkpoint.finish()?;
+        }
 
-        Msg::ok(format!("added {eui_pair:?} to {}", args.route_id))
+        Msg::ok(summary)
     }
 
     pub async fn remove_eui(args: RemoveEui) -> Result<Msg> {
-        let mut client = client::EuiClient::new(&args.config_host).await?;
-        let eui_pair = Eui::new(args.route_id.clone(), args.app_eui, args.dev_eui)?;
+        let (Some(app_eui), Some(dev_eui)) = (args.app_eui, args.dev_eui) else {
+            return remove_euis_from_file(args).await;
+        };
+        let mut client = client::EuiClient::new(&args.config_host, &args.connection).await?;
+        let eui_pair = Eui::new(args.route_id.clone(), app_eui, dev_eui)?;
 
         if !args.commit {
             return Msg::dry_run(format!("removed {eui_pair:?} from {}", args.route_id));
@@ -368,8 +1384,142 @@ pub mod euis {
         Msg::ok(format!("removed {eui_pair:?} from {}", args.route_id))
     }
 
+    /// Only the pairs of the file that are on the Route are sent, the dry run
+    /// tells how many that is.
+    async fn remove_euis_from_file(args: RemoveEui) -> Result<Msg> {
+        let path = args
+            .from_file
+            .as_ref()
+            .ok_or_else(|| anyhow!("pass --app-eui and --dev-eui, or --from-file"))?;
+        let contents =
+            fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+        let listed = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => Eui::from_json(&args.route_id, &contents),
+            _ => Eui::from_csv(&args.route_id, &contents),
+        }
+        .with_context(|| format!("parsing {}", path.display()))?;
+
+        let keypair = args.keypair.to_keypair()?;
+        let mut client = client::EuiClient::new(&args.config_host, &args.connection).await?;
+        let existing = client.get_euis(&args.route_id, &keypair).await?;
+        let (absent, present) = split_present(listed, existing);
+        let summary = |removed: usize| {
+            format!(
+                "removed: {removed}, not on {}: {}",
+                args.route_id,
+                absent.len()
+            )
+        };
+
+        if !args.commit {
+            return Msg::dry_run(summary(present.len()));
+        }
+
+        let (removed, failure) = send_chunks(
+            present.chunks(EUI_CHUNK_SIZE),
+            args.parallel,
+            |chunk| {
+                let (mut client, keypair) = (client.clone(), &keypair);
+                async move { client.remove_euis(chunk.to_vec(), keypair).await }
+            },
+            |_| Ok(()),
+        )
+        .await?;
+        if let Some(err) = failure {
+            return Msg::failed(format!("{}\ncould not remove euis", summary(removed)), err);
+        }
+
+        Msg::ok(summary(removed))
+    }
+
+    /// Pairs are only removed from the source once the destination has
+    /// acknowledged adding them. On failure both Routes are read back to
+    /// report pairs that ended up on neither or both.
+    pub async fn move_euis(args: MoveEuis) -> Result<Msg> {
+        let keypair = args.keypair.to_keypair()?;
+        let mut client = client::EuiClient::new(&args.config_host, &args.connection).await?;
+        let pairs: Vec<Eui> = client
+            .get_euis(&args.from, &keypair)
+            .await?
+            .into_iter()
+            .filter(|eui| args.app_eui.is_none() || args.app_eui == Some(eui.app_eui))
+            .filter(|eui| args.dev_eui.is_none() || args.dev_eui == Some(eui.dev_eui))
+            .collect();
+
+        if pairs.is_empty() {
+            return Msg::err(format!("no matching EUI pairs on {}", args.from));
+        }
+
+        let summary = format!(
+            "moved {} EUI pairs from {} to {}\n{}",
+            pairs.len(),
+            args.from,
+            args.to,
+            pairs.pretty_json()?
+        );
+        if !args.commit {
+            return Msg::dry_run(summary);
+        }
+
+        let destination: Vec<Eui> = pairs
+            .iter()
+            .map(|eui| Eui {
+                route_id: args.to.clone(),
+                ..eui.clone()
+            })
+            .collect();
+        let result = match client.add_euis(destination, &keypair).await {
+            Ok(_) => client.remove_euis(pairs.clone(), &keypair).await,
+            Err(err) => Err(err),
+        };
+
+        if let Err(err) = result {
+            let report = reconcile(&mut client, &args, &pairs, &keypair).await?;
+            return Msg::err(format!("move failed: {err}\n{report}"));
+        }
+
+        Msg::ok(summary)
+    }
+
+    async fn reconcile(
+        client: &mut client::EuiClient,
+        args: &MoveEuis,
+        pairs: &[Eui],
+        keypair: &Keypair,
+    ) -> Result<String> {
+        let on_source: HashSet<_> = client
+            .get_euis(&args.from, keypair)
+            .await?
+            .into_iter()
+            .map(|eui| (eui.app_eui, eui.dev_eui))
+            .collect();
+        let on_destination: HashSet<_> = client
+            .get_euis(&args.to, keypair)
+            .await?
+            .into_iter()
+            .map(|eui| (eui.app_eui, eui.dev_eui))
+            .collect();
+
+        let mut neither = vec![];
+        let mut both = vec![];
+        for eui in pairs {
+            let pair = (eui.app_eui, eui.dev_eui);
+            match (on_source.contains(&pair), on_destination.contains(&pair)) {
+                (false, false) => neither.push(eui),
+                (true, true) => both.push(eui),
+                _ => (),
+            }
+        }
+
+        json!({
+            "on_neither_route": neither,
+            "on_both_routes": both,
+        })
+        .pretty_json()
+    }
+
     pub async fn clear_euis(args: ClearEuis) -> Result<Msg> {
-        let mut client = client::EuiClient::new(&args.config_host).await?;
+        let mut client = client::EuiClient::new(&args.config_host, &args.connection).await?;
 
         if !args.commit {
             return Msg::dry_run(format!("All Euis removed from {}", args.route_id));
@@ -380,45 +1530,294 @@ pub mod euis {
             .await?;
         Msg::ok(format!("All Euis removed from {}", args.route_id))
     }
+
+    /// The file is authoritative: pairs missing from the Route are added and
+    /// pairs the file does not list are removed. Adds are sent first.
+    pub async fn sync_euis(args: SyncEuis) -> Result<Msg> {
+        let csv = fs::read_to_string(&args.file)
+            .with_context(|| format!("reading {}", args.file.display()))?;
+        let desired = Eui::from_csv(&args.route_id, &csv)
+            .with_context(|| format!("parsing {}", args.file.display()))?;
+        // An empty or truncated export would otherwise clear the Route.
+        if desired.is_empty() && !args.allow_empty {
+            return Msg::err(format!(
+                "{} has no eui pairs, pass --allow-empty to remove every pair of {}",
+                args.file.display(),
+                args.route_id
+            ));
+        }
+
+        let keypair = args.keypair.to_keypair()?;
+        let mut client = client::EuiClient::new(&args.config_host, &args.connection).await?;
+        let existing = client.get_euis(&args.route_id, &keypair).await?;
+        let existing_count = existing.len();
+        let overlaps = wildcard_warnings(&desired);
+        let (to_add, to_remove) = sync_delta(desired, existing);
+        let unchanged = existing_count - to_remove.len();
+
+        let color = diff::use_color();
+        let summary = to_add
+            .iter()
+            .map(|eui_pair| diff::added(&format!("{eui_pair:?}"), color))
+            .chain(
+                to_remove
+                    .iter()
+                    .map(|eui_pair| diff::removed(&format!("{eui_pair:?}"), color)),
+            )
+            .chain(overlaps)
+            .chain([format!(
+                "added: {}, removed: {}, unchanged: {unchanged}",
+                to_add.len(),
+                to_remove.len()
+            )])
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        if !args.commit {
+            return Msg::dry_run(summary);
+        }
+
+        if !to_add.is_empty() {
+            client.add_euis(to_add, &keypair).await?;
+        }
+        if !to_remove.is_empty() {
+            client.remove_euis(to_remove, &keypair).await?;
+        }
+
+        Msg::ok(summary)
+    }
+
+    /// CSV rows are written to `--out`, or stdout, as the pairs arrive, a
+    /// JSON list once all of them have.
+    pub async fn export_euis(args: ExportEuis) -> Result<Msg> {
+        let mut client = client::EuiClient::new(&args.config_host, &args.connection).await?;
+        let mut stream = client
+            .stream_euis(&args.route_id, &args.keypair.to_keypair()?)
+            .await?
+            // A wildcard is not a device another network server can create.
+            .try_filter(|eui| future::ready(!eui.is_wildcard()))
+            .map_ok(|eui| ExportedDevice::new(&eui, &args.application_id, &args.device_profile_id));
+        let mut out = BufWriter::new(output::open()?);
+
+        let extension = args.out.as_ref().and_then(|out| out.extension());
+        if extension.and_then(|ext| ext.to_str()) == Some("json") {
+            let devices: Vec<ExportedDevice> = stream.try_collect().await?;
+            serde_json::to_writer_pretty(&mut out, &devices)?;
+            writeln!(out)?;
+        } else {
+            writeln!(out, "{}", chirpstack::CSV_HEADER)?;
+            while let Some(device) = stream.try_next().await? {
+                writeln!(out, "{}", device.csv_row())?;
+            }
+        }
+        out.flush()?;
+
+        Msg::plain(String::new())
+    }
 }
 
 pub mod devaddrs {
     use crate::{
         client,
         cmds::{
-            AddDevaddr, ClearDevaddrs, ListDevaddrs, PathBufKeypair, RemoveDevaddr, RouteSubnetMask,
+            count_entries, send_chunks, write_json_list, write_jsonl, write_template, AddDevaddr,
+            ClearDevaddrs, DevaddrMap, ListDevaddrs, MoveDevaddr, PathBufKeypair, RemoveDevaddr,
+            RouteSubnetMask, SplitDevaddrs, SyncDevaddrs,
         },
-        subnet::DevaddrSubnet,
-        DevaddrRange, Msg, PrettyJson, Result,
+        dedup_entries, diff, output,
+        render::{OutputFormat, Template},
+        split_present,
+        subnet::{self, DevaddrConstraint, DevaddrSubnet},
+        sync_delta, AddReport, DevaddrRange, ExitCode, Msg, PrettyJson, Result,
     };
+    use anyhow::{anyhow, Context};
+    use futures::{future, TryStreamExt};
+    use serde_json::json;
+    use std::{collections::HashSet, fs};
+
+    /// Devaddr Ranges sent per update stream by `devaddrs add` and
+    /// `devaddrs sync`.
+    const DEVADDR_CHUNK_SIZE: usize = 5_000;
 
     pub async fn list_devaddrs(args: ListDevaddrs) -> Result<Msg> {
-        let mut client = client::DevaddrClient::new(&args.config_host).await?;
-        let devaddrs_for_route = client
-            .get_devaddrs(&args.route_id, &args.keypair.to_keypair()?)
-            .await?;
+        let keypair = args.keypair.to_keypair()?;
+        let mut client = client::DevaddrClient::new(&args.config_host, &args.connection).await?;
+        if args.count {
+            let stream = client.stream_devaddrs(&args.route_id, &keypair).await?;
+            return count_entries(stream, None).await;
+        }
+        if let (Some(template), false, true) = (&args.template, args.gaps, output::streams()) {
+            let stream = client.stream_devaddrs(&args.route_id, &keypair).await?;
+            return write_template(stream, &Template::new(template, "devaddr")?).await;
+        }
+        if args.format == OutputFormat::Jsonl && !args.gaps && output::streams() {
+            return write_jsonl(client.stream_devaddrs(&args.route_id, &keypair).await?).await;
+        }
+        // Streamed in the order the config service sends them, not sorted.
+        if args.format == OutputFormat::Json && !args.gaps && output::streams_to_file() {
+            let stream = client.stream_devaddrs(&args.route_id, &keypair).await?;
+            return write_json_list(stream).await;
+        }
+        let mut devaddrs_for_route = client.get_devaddrs(&args.route_id, &keypair).await?;
+        devaddrs_for_route.sort_by_key(|range| range.start_addr.0);
+
+        if let (Some(template), false) = (&args.template, args.gaps) {
+            return Msg::plain(
+                Template::new(template, "devaddr")?.render_all(&devaddrs_for_route)?,
+            );
+        }
+        if !args.gaps {
+            return Msg::ok(devaddrs_for_route.pretty_json()?);
+        }
+
+        // Gaps are relative to every Route in the Org, not only this one.
+        let oui = client.get(&args.route_id, &keypair).await?.oui;
+        let mut assigned: Vec<DevaddrConstraint> = vec![];
+        for route in client.list(oui, &keypair).await?.routes {
+            let ranges = client.get_devaddrs(&route.id, &keypair).await?;
+            assigned.extend(ranges.into_iter().map(DevaddrConstraint::from));
+        }
+
+        let mut org_client = client::OrgClient::new(&args.config_host, &args.connection).await?;
+        let gaps: Vec<DevaddrConstraint> = org_client
+            .get(oui)
+            .await?
+            .devaddr_constraints
+            .iter()
+            .flat_map(|constraint| constraint.gaps(&assigned))
+            .collect();
 
-        Msg::ok(devaddrs_for_route.pretty_json()?)
+        Msg::ok(
+            json!({
+                "devaddrs": devaddrs_for_route,
+                "gaps": gaps,
+            })
+            .pretty_json()?,
+        )
     }
 
     pub async fn add_devaddr(args: AddDevaddr) -> Result<Msg> {
-        let mut client = client::DevaddrClient::new(&args.config_host).await?;
-        let devaddr_range =
-            DevaddrRange::new(args.route_id.clone(), args.start_addr, args.end_addr)?;
+        let mut ranges = args.range.clone();
+        let mut warnings = vec![];
+        match (args.start_addr, args.end_addr, args.count) {
+            (Some(start_addr), Some(end_addr), _) => {
+                ranges.insert(0, DevaddrConstraint::new(start_addr, end_addr)?);
+            }
+            (Some(start_addr), None, Some(count)) => {
+                let range = start_addr.to_range(count);
+                if range.end_addr.0 > u32::MAX as u64 {
+                    return Err(anyhow!("{count} devaddrs from {start_addr} overflows"));
+                }
+                let subnets = range.clone().to_subnet().subnets;
+                if subnets.len() > 1 {
+                    warnings.push(format!(
+                        "warning: {start_addr} is not aligned to {count}, the range needs {} subnet masks",
+                        subnets.len()
+                    ));
+                }
+                ranges.insert(0, range);
+            }
+            (Some(_), None, None) => {
+                return Err(anyhow!("--start-addr needs either --end-addr or --count"));
+            }
+            _ => (),
+        }
+        let mut devaddr_ranges = ranges
+            .into_iter()
+            .map(|range| DevaddrRange::new(args.route_id.clone(), range.start_addr, range.end_addr))
+            .collect::<Result<Vec<_>>>()?;
+
+        let from_file = match &args.from_file {
+            Some(path) => {
+                let json = fs::read_to_string(path)
+                    .with_context(|| format!("reading {}", path.display()))?;
+                DevaddrRange::from_json(&args.route_id, &json)
+                    .with_context(|| format!("parsing {}", path.display()))?
+            }
+            None => vec![],
+        };
+
+        let keypair = args.keypair.to_keypair()?;
+        let mut client = client::DevaddrClient::new(&args.config_host, &args.connection).await?;
+        if !from_file.is_empty() {
+            let oui = client.get(&args.route_id, &keypair).await?.oui;
+            let constraints = client::OrgClient::new(&args.config_host, &args.connection)
+                .await?
+                .get(oui)
+                .await?
+                .devaddr_constraints;
+            let outside: Vec<String> = from_file
+                .iter()
+                .filter(|range| {
+                    !constraints.iter().any(|constraint| {
+                        constraint.contains(range.start_addr) && constraint.contains(range.end_addr)
+                    })
+                })
+                .map(|range| format!("{}-{}", range.start_addr, range.end_addr))
+                .collect();
+            if !outside.is_empty() {
+                return Msg::err(format!(
+                    "ranges outside the devaddr constraints of oui {oui}: {}",
+                    outside.join(", ")
+                ));
+            }
+            devaddr_ranges.extend(from_file);
+        }
+        let (devaddr_ranges, duplicates) = dedup_entries(devaddr_ranges);
+        // Only the ranges being added are kept, a Route can hold more than
+        // fits in memory.
+        let existing: Vec<DevaddrRange> = {
+            let wanted: HashSet<&DevaddrRange> = devaddr_ranges.iter().collect();
+            client
+                .stream_devaddrs(&args.route_id, &keypair)
+                .await?
+                .try_filter(|range| future::ready(wanted.contains(range)))
+                .try_collect()
+                .await?
+        };
+        let (missing, present) = split_present(devaddr_ranges, existing);
+        let report = AddReport::new(missing.len(), present.len()).with_duplicates(duplicates);
+        let summary = missing
+            .iter()
+            .map(|devaddr_range| format!("added {devaddr_range:?}"))
+            .chain(
+                present
+                    .iter()
+                    .map(|devaddr_range| format!("already present {devaddr_range:?}")),
+            )
+            .chain(warnings)
+            .chain([report.to_string()])
+            .collect::<Vec<_>>()
+            .join("\n");
 
         if !args.commit {
-            return Msg::dry_run(format!("added {devaddr_range:?}"));
+            return Msg::dry_run(summary);
         }
 
-        client
-            .add_devaddrs(vec![devaddr_range.clone()], &args.keypair.to_keypair()?)
-            .await?;
+        let (added, failure) = send_chunks(
+            missing.chunks(DEVADDR_CHUNK_SIZE),
+            args.parallel,
+            |chunk| {
+                let (mut client, keypair) = (client.clone(), &keypair);
+                async move { client.add_devaddrs(chunk.to_vec(), keypair).await }
+            },
+            |_| Ok(()),
+        )
+        .await?;
+        if let Some(err) = failure {
+            let report = AddReport {
+                added,
+                failed: missing.len() - added,
+                ..report
+            };
+            return Msg::failed(format!("{report}\ncould not add devaddrs"), err);
+        }
 
-        Msg::ok(format!("added {devaddr_range:?}"))
+        Msg::ok(summary)
     }
 
     pub async fn remove_devaddr(args: RemoveDevaddr) -> Result<Msg> {
-        let mut client = client::DevaddrClient::new(&args.config_host).await?;
+        let mut client = client::DevaddrClient::new(&args.config_host, &args.connection).await?;
         let devaddr_range =
             DevaddrRange::new(args.route_id.clone(), args.start_addr, args.end_addr)?;
 
@@ -433,8 +1832,144 @@ pub mod devaddrs {
         Msg::ok(format!("removed {devaddr_range:?} from {}", args.route_id))
     }
 
+    /// The range is removed from the source first so the two Routes never
+    /// hold it at the same time. If adding it to the destination fails, it
+    /// is put back on the source.
+    pub async fn move_devaddr(args: MoveDevaddr) -> Result<Msg> {
+        let keypair = args.keypair.to_keypair()?;
+        let mut client = client::DevaddrClient::new(&args.config_host, &args.connection).await?;
+        let source = DevaddrRange::new(args.from.clone(), args.start_addr, args.end_addr)?;
+        let destination = DevaddrRange::new(args.to.clone(), args.start_addr, args.end_addr)?;
+
+        let source_ranges = client.get_devaddrs(&args.from, &keypair).await?;
+        if !source_ranges.contains(&source) {
+            return Msg::err(format!(
+                "{} does not have devaddr range {}-{}",
+                args.from, args.start_addr, args.end_addr
+            ));
+        }
+
+        let summary = format!(
+            "moved {}-{} from {} to {}",
+            args.start_addr, args.end_addr, args.from, args.to
+        );
+        if !args.commit {
+            return Msg::dry_run(summary);
+        }
+
+        client
+            .remove_devaddrs(vec![source.clone()], &keypair)
+            .await?;
+        if let Err(add_err) = client.add_devaddrs(vec![destination], &keypair).await {
+            return match client.add_devaddrs(vec![source], &keypair).await {
+                Ok(_) => Msg::failed(
+                    format!(
+                        "could not add range to {}, restored it on {}",
+                        args.to, args.from
+                    ),
+                    add_err,
+                ),
+                Err(rollback_err) => Ok(Msg::Error(
+                    format!(
+                        "could not add range to {}: {add_err}\n\
+                         could not restore range on {}: {rollback_err}\n\
+                         range {}-{} is assigned to neither route",
+                        args.to, args.from, args.start_addr, args.end_addr
+                    ),
+                    ExitCode::Failure,
+                )),
+            };
+        }
+
+        Msg::ok(summary)
+    }
+
+    /// Every Route has to belong to the same Org, and the range has to be
+    /// inside one of its devaddr constraints.
+    pub async fn split_devaddrs(args: SplitDevaddrs) -> Result<Msg> {
+        let keypair = args.keypair.to_keypair()?;
+        let range = DevaddrConstraint::new(args.start, args.end)?;
+        let parts = range.split(args.route_ids.len() as u32)?;
+
+        let mut client = client::DevaddrClient::new(&args.config_host, &args.connection).await?;
+        let mut ouis = vec![];
+        for route_id in &args.route_ids {
+            ouis.push(client.get(route_id, &keypair).await?.oui);
+        }
+        ouis.dedup();
+        let [oui] = ouis[..] else {
+            return Msg::err(format!(
+                "routes belong to different orgs: {}",
+                ouis.iter()
+                    .map(|oui| oui.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        };
+        let constraints = client::OrgClient::new(&args.config_host, &args.connection)
+            .await?
+            .get(oui)
+            .await?
+            .devaddr_constraints;
+        if !constraints.iter().any(|constraint| {
+            constraint.contains(range.start_addr) && constraint.contains(range.end_addr)
+        }) {
+            return Msg::err(format!(
+                "{}-{} is outside the devaddr constraints of oui {oui}",
+                range.start_addr, range.end_addr
+            ));
+        }
+
+        let assigned = args
+            .route_ids
+            .iter()
+            .zip(parts)
+            .map(|(route_id, part)| {
+                DevaddrRange::new(route_id.clone(), part.start_addr, part.end_addr)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let report: Vec<_> = assigned
+            .iter()
+            .map(|range| {
+                json!({
+                    "route_id": range.route_id,
+                    "start_addr": range.start_addr,
+                    "end_addr": range.end_addr,
+                    "subnets": range.clone().to_subnet().subnets,
+                })
+            })
+            .collect();
+        let summary = format!(
+            "split {}-{} over {} routes\n{}",
+            range.start_addr,
+            range.end_addr,
+            assigned.len(),
+            report.pretty_json()?
+        );
+        if !args.commit {
+            return Msg::dry_run(summary);
+        }
+
+        for (added, range) in assigned.iter().enumerate() {
+            if let Err(err) = client.add_devaddrs(vec![range.clone()], &keypair).await {
+                return Msg::failed(
+                    format!(
+                        "added {added} of {} parts\ncould not add {}-{} to {}",
+                        assigned.len(),
+                        range.start_addr,
+                        range.end_addr,
+                        range.route_id
+                    ),
+                    err,
+                );
+            }
+        }
+
+        Msg::ok(summary)
+    }
+
     pub async fn clear_devaddrs(args: ClearDevaddrs) -> Result<Msg> {
-        let mut client = client::DevaddrClient::new(&args.config_host).await?;
+        let mut client = client::DevaddrClient::new(&args.config_host, &args.connection).await?;
 
         if !args.commit {
             return Msg::dry_run(format!("All Devadddrs removed from {}", args.route_id));
@@ -447,14 +1982,180 @@ pub mod devaddrs {
         Msg::ok(format!("All Devaddrs removed from {}", args.route_id))
     }
 
+    /// Like `euis sync` the file is authoritative, but removes are sent
+    /// before adds.
+    pub async fn sync_devaddrs(args: SyncDevaddrs) -> Result<Msg> {
+        let json = fs::read_to_string(&args.file)
+            .with_context(|| format!("reading {}", args.file.display()))?;
+        let desired = DevaddrRange::from_json(&args.route_id, &json)
+            .with_context(|| format!("parsing {}", args.file.display()))?;
+
+        let keypair = args.keypair.to_keypair()?;
+        let mut client = client::DevaddrClient::new(&args.config_host, &args.connection).await?;
+        let existing = client.get_devaddrs(&args.route_id, &keypair).await?;
+        let existing_count = existing.len();
+        let (to_add, to_remove) = sync_delta(desired, existing);
+        let unchanged = existing_count - to_remove.len();
+
+        let color = diff::use_color();
+        let summary = to_add
+            .iter()
+            .map(|devaddr_range| diff::added(&format!("{devaddr_range:?}"), color))
+            .chain(
+                to_remove
+                    .iter()
+                    .map(|devaddr_range| diff::removed(&format!("{devaddr_range:?}"), color)),
+            )
+            .chain([format!(
+                "added: {}, removed: {}, unchanged: {unchanged}",
+                to_add.len(),
+                to_remove.len()
+            )])
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        if !args.commit {
+            return Msg::dry_run(summary);
+        }
+
+        // Removed first, like `route migrate`, so a new range overlapping a
+        // replaced one is not sent while the old one is still on the Route.
+        let (removed, failure) = send_chunks(
+            to_remove.chunks(DEVADDR_CHUNK_SIZE),
+            args.parallel,
+            |chunk| {
+                let (mut client, keypair) = (client.clone(), &keypair);
+                async move { client.remove_devaddrs(chunk.to_vec(), keypair).await }
+            },
+            |_| Ok(()),
+        )
+        .await?;
+        if let Some(err) = failure {
+            return Msg::failed(
+                format!(
+                    "removed {removed} of {} ranges\ncould not sync devaddrs",
+                    to_remove.len()
+                ),
+                err,
+            );
+        }
+        let (added, failure) = send_chunks(
+            to_add.chunks(DEVADDR_CHUNK_SIZE),
+            args.parallel,
+            |chunk| {
+                let (mut client, keypair) = (client.clone(), &keypair);
+                async move { client.add_devaddrs(chunk.to_vec(), keypair).await }
+            },
+            |_| Ok(()),
+        )
+        .await?;
+        if let Some(err) = failure {
+            return Msg::failed(
+                format!(
+                    "removed {removed} ranges, added {added} of {}\ncould not sync devaddrs",
+                    to_add.len()
+                ),
+                err,
+            );
+        }
+
+        Msg::ok(summary)
+    }
+
+    pub async fn devaddr_map(args: DevaddrMap) -> Result<Msg> {
+        let keypair = args.keypair.to_keypair()?;
+        let mut client = client::DevaddrClient::new(&args.config_host, &args.connection).await?;
+        let mut routes = vec![];
+        for route in client.list(args.oui, &keypair).await?.routes {
+            let ranges = client.get_devaddrs(&route.id, &keypair).await?;
+            routes.push((
+                route.id,
+                ranges.into_iter().map(DevaddrConstraint::from).collect(),
+            ));
+        }
+
+        let constraints = client::OrgClient::new(&args.config_host, &args.connection)
+            .await?
+            .get(args.oui)
+            .await?
+            .devaddr_constraints;
+
+        Msg::plain(subnet::usage_map(&constraints, &routes, args.width))
+    }
+
     pub async fn subnet_mask(args: RouteSubnetMask) -> Result<Msg> {
-        let mut client = client::DevaddrClient::new(&args.config_host).await?;
+        let mut client = client::DevaddrClient::new(&args.config_host, &args.connection).await?;
         let devaddrs_for_route: Vec<DevaddrSubnet> = client
             .get_devaddrs(&args.route_id, &args.keypair.to_keypair()?)
             .await?
             .into_iter()
             .map(|range| range.to_subnet())
             .collect();
-        Msg::ok(devaddrs_for_route.pretty_json()?)
+        match args.format {
+            OutputFormat::Cidr => Msg::plain(subnet::cidr_lines(&devaddrs_for_route)),
+            _ => Msg::ok(devaddrs_for_route.pretty_json()?),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{migration_steps, undo_steps, Migrated, MIGRATE_CHUNK_SIZE};
+    use crate::{hex_field, route::RouteId, DevaddrRange, Eui};
+
+    #[test]
+    fn migration_rollback() {
+        let (from, to) = (RouteId::new_unchecked("from"), RouteId::new_unchecked("to"));
+        let range = |route_id: &RouteId| {
+            DevaddrRange::new(
+                route_id.clone(),
+                hex_field::devaddr(0x4800_0000),
+                hex_field::devaddr(0x4800_00ff),
+            )
+            .unwrap()
+        };
+        let euis = |route_id: &RouteId| -> Vec<Eui> {
+            (0..MIGRATE_CHUNK_SIZE as u64 + 1)
+                .map(|n| Eui::new(route_id.clone(), hex_field::eui(1), hex_field::eui(n)).unwrap())
+                .collect()
+        };
+
+        let steps = migration_steps(vec![range(&from)], vec![range(&to)], euis(&to), euis(&from));
+        let sizes: Vec<(&str, usize)> = steps
+            .iter()
+            .map(|step| match step {
+                Migrated::RemovedDevaddrs(ranges) => ("remove devaddrs", ranges.len()),
+                Migrated::AddedDevaddrs(ranges) => ("add devaddrs", ranges.len()),
+                Migrated::AddedEuis(euis) => ("add euis", euis.len()),
+                Migrated::RemovedEuis(euis) => ("remove euis", euis.len()),
+            })
+            .collect();
+        assert_eq!(
+            vec![
+                ("remove devaddrs", 1),
+                ("add devaddrs", 1),
+                ("add euis", MIGRATE_CHUNK_SIZE),
+                ("add euis", 1),
+                ("remove euis", MIGRATE_CHUNK_SIZE),
+                ("remove euis", 1),
+            ],
+            sizes
+        );
+
+        // The second chunk of new pairs failed, the first and the chunk that
+        // failed are taken off `--to` before the ranges go back.
+        let mut steps = steps.into_iter();
+        let applied: Vec<Migrated> = steps.by_ref().take(3).collect();
+        let failed = steps.next().unwrap();
+        let new_euis = euis(&to);
+        assert_eq!(
+            vec![
+                Migrated::RemovedEuis(new_euis[MIGRATE_CHUNK_SIZE..].to_vec()),
+                Migrated::RemovedEuis(new_euis[..MIGRATE_CHUNK_SIZE].to_vec()),
+                Migrated::RemovedDevaddrs(vec![range(&to)]),
+                Migrated::AddedDevaddrs(vec![range(&from)]),
+            ],
+            undo_steps(applied, failed)
+        );
     }
 }
@@ -0,0 +1,96 @@
+use super::ServerInfo;
+use crate::{client, ExitCode, Msg, PrettyJson, Result};
+use serde_json::{json, Map};
+use tonic::{Code, Status};
+
+/// The config service does not expose its version, so compatibility is
+/// inferred from how it answers the RPCs this binary was compiled against.
+pub async fn server_info(args: ServerInfo) -> Result<Msg> {
+    let probes = client::probe_rpcs(&args.config_host, &args.connection).await?;
+
+    let mut rpcs = Map::new();
+    let mut warnings = vec![];
+    for (rpc, status) in probes {
+        warnings.extend(probe_warning(rpc, &status));
+        rpcs.insert(rpc.to_string(), json!(classify(&status)));
+    }
+
+    let output = json!({
+        "config_host": args.config_host,
+        "cli_version": env!("CARGO_PKG_VERSION"),
+        "server_version": "unknown, the config service does not expose a version",
+        "rpcs": rpcs,
+        "warnings": warnings,
+    });
+
+    if warnings.is_empty() {
+        Msg::ok(output.pretty_json()?)
+    } else {
+        Ok(Msg::Error(output.pretty_json()?, ExitCode::Failure))
+    }
+}
+
+/// Why the answer of `rpc` makes the server incompatible, `None` when it
+/// is available.
+pub fn probe_warning(rpc: &str, status: &Status) -> Option<String> {
+    match classify(status) {
+        "missing" => Some(format!(
+            "{rpc} is not implemented by the server, {}",
+            missing_hint(rpc)
+        )),
+        "incompatible" => Some(format!(
+            "{rpc} could not be decoded, the server uses a different helium-proto revision: {}",
+            status.message()
+        )),
+        _ => None,
+    }
+}
+
+/// Any answer other than `Unimplemented` or a decode failure means the
+/// server knows the RPC, even if it rejected the empty request.
+fn classify(status: &Status) -> &'static str {
+    match status.code() {
+        Code::Unimplemented => "missing",
+        Code::Internal | Code::Unknown if status.message().contains("decode") => "incompatible",
+        _ => "available",
+    }
+}
+
+fn missing_hint(rpc: &str) -> &'static str {
+    match rpc {
+        "org.list" => "this may not be an iot config service",
+        "route.list" | "route.get_euis" | "route.get_devaddr_ranges" => {
+            "`route` commands will fail"
+        }
+        "session_key_filter.list" => "`session-key-filter` commands will fail",
+        "gateway.location" => "`region-params push` and `submit` will likely fail",
+        _ => "commands using it will fail",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classify_probe_status() {
+        assert_eq!("available", classify(&Status::new(Code::Ok, "")));
+        assert_eq!(
+            "available",
+            classify(&Status::permission_denied("invalid signature"))
+        );
+        assert_eq!("missing", classify(&Status::unimplemented("")));
+        assert_eq!(
+            "incompatible",
+            classify(&Status::internal(
+                "failed to decode Protobuf message: invalid wire type"
+            ))
+        );
+
+        assert_eq!(None, probe_warning("org.list", &Status::new(Code::Ok, "")));
+        assert_eq!(
+            Some("route.list is not implemented by the server, `route` commands will fail"),
+            probe_warning("route.list", &Status::unimplemented("")).as_deref()
+        );
+    }
+}
@@ -1,17 +1,47 @@
-use super::{AddFilter, GetFilters, ListFilters, PathBufKeypair, RemoveFilter};
-use crate::{client, Msg, PrettyJson, Result, SessionKeyFilter};
+use super::{
+    count_entries, send_chunks, write_jsonl, write_template, AddFilter, ExportFilters, GetFilters,
+    ImportFilters, ListFilters, PathBufKeypair, RemoveFilter, RemoveFilterRange, ReplaceFilter,
+};
+use crate::{
+    checkpoint::Checkpoint,
+    client, missing_positions, output,
+    render::{OutputFormat, Template},
+    split_present, AddReport, ExportedFilter, Msg, PrettyJson, Result, SessionKeyFilter,
+    SessionKeyFilterExport, SKF_EXPORT_VERSION,
+};
+use anyhow::{anyhow, Context};
+use futures::{future, TryStreamExt};
+use std::fs;
+
+/// Filters sent per update stream by `skf import` and `skf remove-range`.
+const SKF_CHUNK_SIZE: usize = 5_000;
 
 pub async fn list_filters(args: ListFilters) -> Result<Msg> {
-    let mut client = client::SkfClient::new(&args.config_host).await?;
-    let filters = client
-        .list_filters(args.oui, &args.keypair.to_keypair()?)
-        .await?;
+    let mut client = client::SkfClient::new(&args.config_host, &args.connection).await?;
+    let keypair = args.keypair.to_keypair()?;
+    if args.count {
+        let group_by: Option<fn(&SessionKeyFilter) -> String> = args
+            .by_devaddr
+            .then_some(|filter| filter.devaddr.to_string());
+        return count_entries(client.stream_filters(args.oui, &keypair).await?, group_by).await;
+    }
+    if let (Some(template), true) = (&args.template, output::streams()) {
+        let stream = client.stream_filters(args.oui, &keypair).await?;
+        return write_template(stream, &Template::new(template, "filter")?).await;
+    }
+    if args.format == OutputFormat::Jsonl && output::streams() {
+        return write_jsonl(client.stream_filters(args.oui, &keypair).await?).await;
+    }
+    let filters = client.list_filters(args.oui, &keypair).await?;
+    if let Some(template) = &args.template {
+        return Msg::plain(Template::new(template, "filter")?.render_all(&filters)?);
+    }
 
     Msg::ok(filters.pretty_json()?)
 }
 
 pub async fn get_filters(args: GetFilters) -> Result<Msg> {
-    let mut client = client::SkfClient::new(&args.config_host).await?;
+    let mut client = client::SkfClient::new(&args.config_host, &args.connection).await?;
     let filters = client
         .get_filters(args.oui, args.devaddr, &args.keypair.to_keypair()?)
         .await?;
@@ -20,22 +50,34 @@ pub async fn get_filters(args: GetFilters) -> Result<Msg> {
 }
 
 pub async fn add_filter(args: AddFilter) -> Result<Msg> {
-    let mut client = client::SkfClient::new(&args.config_host).await?;
+    let mut client = client::SkfClient::new(&args.config_host, &args.connection).await?;
     let filter = SessionKeyFilter::new(args.oui, args.devaddr, args.session_key);
 
+    let keypair = args.keypair.to_keypair()?;
+    let existing = client.get_filters(args.oui, args.devaddr, &keypair).await?;
+    let (missing, present) = split_present(vec![filter.clone()], existing);
+    let report = AddReport::new(missing.len(), present.len());
+    let summary = if missing.is_empty() {
+        format!("already present {filter:?}\n{report}")
+    } else {
+        format!("added {filter:?}\n{report}")
+    };
+
     if !args.commit {
-        return Msg::dry_run(format!("added {filter:?}"));
+        return Msg::dry_run(summary);
     }
 
-    client
-        .add_filters(vec![filter.clone()], &args.keypair.to_keypair()?)
-        .await?;
+    if !missing.is_empty() {
+        if let Err(err) = client.add_filters(missing, &keypair).await {
+            return Msg::failed(format!("{}\ncould not add filter", report.failed()), err);
+        }
+    }
 
-    Msg::ok(format!("added {filter:?}"))
+    Msg::ok(summary)
 }
 
 pub async fn remove_filter(args: RemoveFilter) -> Result<Msg> {
-    let mut client = client::SkfClient::new(&args.config_host).await?;
+    let mut client = client::SkfClient::new(&args.config_host, &args.connection).await?;
     let filter = SessionKeyFilter::new(args.oui, args.devaddr, args.session_key);
 
     if !args.commit {
@@ -48,3 +90,172 @@ pub async fn remove_filter(args: RemoveFilter) -> Result<Msg> {
 
     Msg::ok(format!("removed {filter:?}"))
 }
+
+pub async fn replace_filter(args: ReplaceFilter) -> Result<Msg> {
+    let keypair = args.keypair.to_keypair()?;
+    let mut client = client::SkfClient::new(&args.config_host, &args.connection).await?;
+    let old = SessionKeyFilter::new(args.oui, args.devaddr, args.old_key);
+    let new = SessionKeyFilter::new(args.oui, args.devaddr, args.new_key);
+
+    let existing = client.get_filters(args.oui, args.devaddr, &keypair).await?;
+    if !existing.contains(&old) {
+        return Msg::err(format!("no filter {old:?} to replace"));
+    }
+
+    if !args.commit {
+        return Msg::dry_run(format!("replaced {old:?} with {new:?}"));
+    }
+
+    client
+        .replace_filter(old.clone(), new.clone(), &keypair)
+        .await?;
+
+    Msg::ok(format!("replaced {old:?} with {new:?}"))
+}
+
+pub async fn remove_filter_range(args: RemoveFilterRange) -> Result<Msg> {
+    if args.end_addr < args.start_addr {
+        return Err(anyhow!("start_addr cannot be greater than end_addr"));
+    }
+
+    let keypair = args.keypair.to_keypair()?;
+    let mut client = client::SkfClient::new(&args.config_host, &args.connection).await?;
+    // Only the filters in the range are kept as the Org's filters arrive.
+    let filters: Vec<SessionKeyFilter> = client
+        .stream_filters(args.oui, &keypair)
+        .await?
+        .try_filter(|filter| {
+            future::ready(filter.devaddr >= args.start_addr && filter.devaddr <= args.end_addr)
+        })
+        .try_collect()
+        .await?;
+
+    let summary = format!(
+        "removed {} filters in {}-{}\n{}",
+        filters.len(),
+        args.start_addr,
+        args.end_addr,
+        filters.pretty_json()?
+    );
+    if !args.commit {
+        return Msg::dry_run(summary);
+    }
+
+    let (removed, failure) = send_chunks(
+        filters.chunks(SKF_CHUNK_SIZE),
+        args.parallel,
+        |chunk| {
+            let (mut client, keypair) = (client.clone(), &keypair);
+            async move { client.remove_filters(chunk.to_vec(), keypair).await }
+        },
+        |_| Ok(()),
+    )
+    .await?;
+    if let Some(err) = failure {
+        return Msg::failed(
+            format!(
+                "removed {removed} of {} filters in {}-{}\ncould not remove filters",
+                filters.len(),
+                args.start_addr,
+                args.end_addr
+            ),
+            err,
+        );
+    }
+
+    Msg::ok(summary)
+}
+
+/// Written to `--out`, or stdout, like every other output.
+pub async fn export_filters(args: ExportFilters) -> Result<Msg> {
+    let mut client = client::SkfClient::new(&args.config_host, &args.connection).await?;
+    let filters = client
+        .list_filters(args.oui, &args.keypair.to_keypair()?)
+        .await?;
+
+    let export = SessionKeyFilterExport::new(args.oui, filters);
+    Msg::plain(export.pretty_json()?)
+}
+
+/// Filters already present on the Org are skipped, so importing the same
+/// file twice is harmless.
+pub async fn import_filters(args: ImportFilters) -> Result<Msg> {
+    let (input, export) = match (&args.file, &args.from_file, args.oui) {
+        (Some(file), _, _) => {
+            let json =
+                fs::read_to_string(file).with_context(|| format!("reading {}", file.display()))?;
+            let export = SessionKeyFilterExport::from_json(&json)
+                .with_context(|| format!("parsing {}", file.display()))?;
+            (file, export)
+        }
+        (None, Some(from_file), Some(oui)) => {
+            let json = fs::read_to_string(from_file)
+                .with_context(|| format!("reading {}", from_file.display()))?;
+            let filters = ExportedFilter::from_json(&json)
+                .with_context(|| format!("parsing {}", from_file.display()))?;
+            let export = SessionKeyFilterExport {
+                version: SKF_EXPORT_VERSION,
+                oui,
+                filters,
+            };
+            (from_file, export)
+        }
+        _ => return Err(anyhow!("pass --file, or --from-file and --oui")),
+    };
+    let oui = export.oui;
+
+    let keypair = args.keypair.to_keypair()?;
+    let mut client = client::SkfClient::new(&args.config_host, &args.connection).await?;
+    let filters = export.into_filters();
+    let checkpoint = Checkpoint::new(input, format!("oui {oui}"), filters.len());
+    let confirmed = if args.resume {
+        checkpoint.confirmed()?
+    } else {
+        0
+    };
+    let pending = &filters[confirmed..];
+    let existing = client.list_filters(oui, &keypair).await?;
+    let missing = missing_positions(pending, existing);
+
+    let mut summary = format!(
+        "added {} filters to oui {oui}, {} already present",
+        missing.len(),
+        pending.len() - missing.len()
+    );
+    if confirmed > 0 {
+        summary.push_str(&format!(
+            "\nresumed after {confirmed} of {} filters in {}",
+            filters.len(),
+            checkpoint.path().display()
+        ));
+    }
+    if !args.commit {
+        return Msg::dry_run(summary);
+    }
+
+    // Chunks are confirmed in order, so the checkpoint never skips a chunk
+    // still in flight.
+    let (added, failure) = send_chunks(
+        missing.chunks(SKF_CHUNK_SIZE),
+        args.parallel,
+        |chunk| {
+            let batch = chunk.iter().map(|&i| pending[i].clone()).collect();
+            let (mut client, keypair) = (client.clone(), &keypair);
+            async move { client.add_filters(batch, keypair).await }
+        },
+        |chunk| checkpoint.save(confirmed + chunk[chunk.len() - 1] + 1),
+    )
+    .await?;
+    if let Some(err) = failure {
+        return Msg::failed(
+            format!(
+                "added {added} of {} filters to oui {oui}, pass --resume to continue\ncould not add filters",
+                missing.len()
+            ),
+            err,
+        );
+    }
+    checkpoint.finish()?;
+
+    Msg::ok(summary)
+}
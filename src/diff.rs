@@ -0,0 +1,171 @@
+//! `+`/`-` line diffs for dry run previews, and field diffs of JSON values.
+//!
+//! Removed lines are red and added lines green when `color` is set, see
+//! [`use_color`].
+
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeSet;
+
+const RED: &str = "\x1b[31m";
+const GREEN: &str = "\x1b[32m";
+const RESET: &str = "\x1b[0m";
+
+/// Above this many line pairs the diff is not worth computing, every line
+/// is shown removed and added.
+const MAX_DIFF_CELLS: usize = 4_000_000;
+
+/// Color diffs when output goes to a terminal and `NO_COLOR` is not set.
+pub fn use_color() -> bool {
+    std::env::var_os("NO_COLOR").is_none() && crate::output::is_terminal()
+}
+
+/// Every line of `old` and `new`, unchanged lines indented, removed lines
+/// with `- ` and added lines with `+ `.
+pub fn lines(old: &str, new: &str, color: bool) -> String {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+
+    if old.len().saturating_mul(new.len()) > MAX_DIFF_CELLS {
+        return old
+            .iter()
+            .map(|line| removed(line, color))
+            .chain(new.iter().map(|line| added(line, color)))
+            .collect::<Vec<_>>()
+            .join("\n");
+    }
+
+    // Longest common subsequence of the lines after each position.
+    let mut common = vec![vec![0u32; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            common[i][j] = if old[i] == new[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let mut out = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            out.push(format!("  {}", old[i]));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || common[i + 1][j] >= common[i][j + 1]) {
+            out.push(removed(old[i], color));
+            i += 1;
+        } else {
+            out.push(added(new[j], color));
+            j += 1;
+        }
+    }
+    out.join("\n")
+}
+
+pub fn added(line: &str, color: bool) -> String {
+    paint(format!("+ {line}"), GREEN, color)
+}
+
+pub fn removed(line: &str, color: bool) -> String {
+    paint(format!("- {line}"), RED, color)
+}
+
+fn paint(line: String, code: &str, color: bool) -> String {
+    if color {
+        format!("{code}{line}{RESET}")
+    } else {
+        line
+    }
+}
+
+/// A field that differs between two JSON documents, `None` on the side
+/// where it is missing.
+#[derive(Debug, Serialize, PartialEq)]
+pub struct FieldChange {
+    pub field: String,
+    pub old: Option<Value>,
+    pub new: Option<Value>,
+}
+
+/// The fields that differ between `old` and `new` with dotted paths like
+/// `server.port`. Objects are compared key by key, anything else as a
+/// whole.
+pub fn fields(old: &Value, new: &Value) -> Vec<FieldChange> {
+    let mut changes = vec![];
+    field_changes(String::new(), Some(old), Some(new), &mut changes);
+    changes
+}
+
+fn field_changes(
+    path: String,
+    old: Option<&Value>,
+    new: Option<&Value>,
+    changes: &mut Vec<FieldChange>,
+) {
+    match (old, new) {
+        (Some(Value::Object(old)), Some(Value::Object(new))) => {
+            let keys: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+            for key in keys {
+                let path = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{path}.{key}")
+                };
+                field_changes(path, old.get(key), new.get(key), changes);
+            }
+        }
+        (old, new) if old != new => changes.push(FieldChange {
+            field: path,
+            old: old.cloned(),
+            new: new.cloned(),
+        }),
+        _ => (),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{fields, lines, FieldChange};
+    use serde_json::json;
+
+    #[test]
+    fn line_diff() {
+        let old = "{\n  \"host\": \"a\",\n  \"port\": 1\n}";
+        let new = "{\n  \"host\": \"b\",\n  \"port\": 1\n}";
+        assert_eq!(
+            "  {\n-   \"host\": \"a\",\n+   \"host\": \"b\",\n    \"port\": 1\n  }",
+            lines(old, new, false)
+        );
+        assert_eq!("  a\n\x1b[32m+ b\x1b[0m", lines("a", "a\nb", true));
+    }
+
+    #[test]
+    fn field_diff() {
+        let old = json!({"max_copies": 1, "server": {"host": "a", "port": 1, "protocol": null}});
+        let new = json!({"max_copies": 1, "server": {"host": "a", "port": 2, "path": "/up"}});
+        assert_eq!(
+            vec![
+                FieldChange {
+                    field: "server.path".to_string(),
+                    old: None,
+                    new: Some(json!("/up")),
+                },
+                FieldChange {
+                    field: "server.port".to_string(),
+                    old: Some(json!(1)),
+                    new: Some(json!(2)),
+                },
+                FieldChange {
+                    field: "server.protocol".to_string(),
+                    old: Some(json!(null)),
+                    new: None,
+                },
+            ],
+            fields(&old, &new)
+        );
+        assert!(fields(&old, &old).is_empty());
+    }
+}
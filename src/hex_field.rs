@@ -3,6 +3,87 @@ use anyhow::anyhow;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::{fmt::Display, str::FromStr};
 
+/// Case of the letters in hex fields of output, see [`restyle`].
+///
+/// Parsing accepts either case.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum HexCase {
+    #[default]
+    Upper,
+    Lower,
+}
+
+/// How the digits of hex fields in output are laid out, see [`restyle`].
+///
+/// Parsing accepts every style.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum HexStyle {
+    /// `48000001`
+    #[default]
+    Plain,
+    /// `0x48000001`
+    Prefixed,
+    /// `48:00:00:01`
+    Colon,
+}
+
+/// Keys of the hex fields in JSON output.
+const HEX_KEYS: &[&str] = &[
+    "net_id",
+    "devaddr",
+    "start_addr",
+    "end_addr",
+    "app_eui",
+    "dev_eui",
+    "join_eui",
+];
+
+/// Rewrite the hex fields of JSON `value` in `case` and `style`.
+///
+/// A [`HexField`] always displays and serializes as plain uppercase, so
+/// files written by one invocation parse the same in any other; the case
+/// and style are only applied to output.
+pub fn restyle(value: &mut serde_json::Value, case: HexCase, style: HexStyle) {
+    use serde_json::Value;
+    match value {
+        Value::Array(values) => values
+            .iter_mut()
+            .for_each(|value| restyle(value, case, style)),
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                match value {
+                    Value::String(digits)
+                        if HEX_KEYS.contains(&key.as_str())
+                            && [6, 8, 16].contains(&digits.len())
+                            && digits.chars().all(|c| c.is_ascii_hexdigit()) =>
+                    {
+                        *digits = style_digits(digits, case, style);
+                    }
+                    value => restyle(value, case, style),
+                }
+            }
+        }
+        _ => (),
+    }
+}
+
+fn style_digits(digits: &str, case: HexCase, style: HexStyle) -> String {
+    let digits = match case {
+        HexCase::Upper => digits.to_uppercase(),
+        HexCase::Lower => digits.to_lowercase(),
+    };
+    match style {
+        HexStyle::Plain => digits,
+        HexStyle::Prefixed => format!("0x{digits}"),
+        HexStyle::Colon => digits
+            .as_bytes()
+            .chunks(2)
+            .map(|byte| String::from_utf8_lossy(byte))
+            .collect::<Vec<_>>()
+            .join(":"),
+    }
+}
+
 #[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Hash)]
 pub struct HexField<const WIDTH: usize>(pub u64);
 
@@ -40,10 +121,30 @@ impl<const WIDTH: usize> From<u32> for HexField<WIDTH> {
     }
 }
 
+impl<const WIDTH: usize> HexField<WIDTH> {
+    pub fn to_string_with_case(&self, case: HexCase) -> String {
+        self.to_string_with_style(case, HexStyle::Plain)
+    }
+
+    pub fn to_string_with_style(&self, case: HexCase, style: HexStyle) -> String {
+        // pad with 0s to the left up to WIDTH
+        style_digits(&format!("{:0>width$X}", self.0, width = WIDTH), case, style)
+    }
+}
+
+impl<const WIDTH: usize> HexField<WIDTH> {
+    /// JSON Schema of the serialized field, `WIDTH` hex digits in either case.
+    pub fn schema() -> serde_json::Value {
+        serde_json::json!({
+            "type": "string",
+            "pattern": format!("^[0-9A-Fa-f]{{{WIDTH}}}$"),
+        })
+    }
+}
+
 impl<const WIDTH: usize> Display for HexField<WIDTH> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        // pad with 0s to the left up to WIDTH
-        write!(f, "{:0>width$X}", self.0, width = WIDTH)
+        f.write_str(&self.to_string_with_case(HexCase::Upper))
     }
 }
 
@@ -53,8 +154,13 @@ impl<const WIDTH: usize> FromStr for HexField<WIDTH> {
         if "*" == s {
             return Ok(HexField::<WIDTH>(0));
         }
-        verify_len(s, WIDTH)?;
-        Ok(HexField::<WIDTH>(u64::from_str_radix(s, 16)?))
+        let digits = s
+            .strip_prefix("0x")
+            .or_else(|| s.strip_prefix("0X"))
+            .unwrap_or(s)
+            .replace(':', "");
+        verify_len(&digits, WIDTH)?;
+        Ok(HexField::<WIDTH>(u64::from_str_radix(&digits, 16)?))
     }
 }
 
@@ -114,6 +220,30 @@ pub fn validate_eui(s: &str) -> Result<HexEui> {
     HexEui::from_str(s).map_err(|e| anyhow!("could not parse {s} into eui, {e}"))
 }
 
+/// A network session key is 16 bytes, written as 32 hex characters.
+pub fn validate_session_key(s: &str) -> Result<&str> {
+    if s.len() != 32 || !s.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(anyhow!("session key {s} is not 32 hex characters"));
+    }
+    Ok(s)
+}
+
+/// Parse `<app_eui>:<dev_eui>`.
+pub fn validate_eui_pair(s: &str) -> Result<(HexEui, HexEui)> {
+    let (app_eui, dev_eui) = s
+        .split_once(':')
+        .ok_or_else(|| anyhow!("expected <app_eui>:<dev_eui>, got {s}"))?;
+    Ok((validate_eui(app_eui)?, validate_eui(dev_eui)?))
+}
+
+/// Parse `<start_addr>-<end_addr>`, both inclusive.
+pub fn validate_devaddr_range(s: &str) -> Result<DevaddrConstraint> {
+    let (start_addr, end_addr) = s
+        .split_once('-')
+        .ok_or_else(|| anyhow!("expected <start_addr>-<end_addr>, got {s}"))?;
+    DevaddrConstraint::new(validate_devaddr(start_addr)?, validate_devaddr(end_addr)?)
+}
+
 pub fn devaddr(val: u64) -> HexDevAddr {
     val.into()
 }
@@ -133,6 +263,18 @@ fn verify_len(input: &str, expected_len: usize) -> Result<()> {
     }
 }
 
+impl HexDevAddr {
+    /// The devaddr `n` addresses after this one.
+    ///
+    /// `None` when it would fall outside the 32 bit devaddr space.
+    pub fn offset(self, n: u64) -> Option<Self> {
+        self.0
+            .checked_add(n)
+            .filter(|addr| *addr <= u32::MAX as u64)
+            .map(HexField)
+    }
+}
+
 impl HexNetID {
     fn netid_type(&self) -> u32 {
         const BIT_WIDTH: usize = 24;
@@ -235,12 +377,15 @@ mod tests {
     use std::str::FromStr;
 
     use crate::{
-        hex_field::{devaddr, eui, net_id},
+        hex_field::{
+            devaddr, eui, net_id, restyle, validate_devaddr_range, validate_eui_pair, HexCase,
+            HexStyle,
+        },
         DevaddrConstraint, NetId,
     };
     use pretty_assertions::assert_eq;
 
-    use super::HexEui;
+    use super::{HexDevAddr, HexEui};
 
     #[test]
     fn range_from_net_id() {
@@ -327,4 +472,90 @@ mod tests {
         let val: HexEui = serde_json::from_str(r#""*""#).expect("serde_json from_str");
         assert_eq!(0, val.0);
     }
+
+    #[test]
+    fn parse_multi_entry_flags() {
+        assert_eq!(
+            (eui(0x1122334455667788), eui(0x0000000000000001)),
+            validate_eui_pair("1122334455667788:0000000000000001").unwrap()
+        );
+        assert!(validate_eui_pair("1122334455667788").is_err());
+        assert!(validate_eui_pair("1122:0000000000000001").is_err());
+
+        assert_eq!(
+            DevaddrConstraint::new(devaddr(0x48000000), devaddr(0x4800003F)).unwrap(),
+            validate_devaddr_range("48000000-4800003F").unwrap()
+        );
+        assert!(validate_devaddr_range("48000000").is_err());
+        assert!(validate_devaddr_range("4800003F-48000000").is_err());
+    }
+
+    #[test]
+    fn devaddr_offset() {
+        assert_eq!(Some(devaddr(0x48000040)), devaddr(0x48000000).offset(64));
+        assert_eq!(Some(devaddr(0x48000000)), devaddr(0x48000000).offset(0));
+        assert_eq!(Some(devaddr(0xFFFFFFFF)), devaddr(0xFFFFFFFE).offset(1));
+        assert_eq!(None, devaddr(0xFFFFFFFF).offset(1));
+        assert_eq!(None, devaddr(0).offset(u64::MAX));
+    }
+
+    #[test]
+    fn hex_field_case() {
+        let field = eui(0xAB_CDEF);
+        assert_eq!(
+            "0000000000ABCDEF",
+            field.to_string_with_case(HexCase::Upper)
+        );
+        assert_eq!(
+            "0000000000abcdef",
+            field.to_string_with_case(HexCase::Lower)
+        );
+        assert_eq!(field, HexEui::from_str("0000000000abcdef").unwrap());
+    }
+
+    #[test]
+    fn hex_field_style() {
+        let field = devaddr(0x4800_00AB);
+        assert_eq!(
+            "0x480000ab",
+            field.to_string_with_style(HexCase::Lower, HexStyle::Prefixed)
+        );
+        assert_eq!(
+            "48:00:00:AB",
+            field.to_string_with_style(HexCase::Upper, HexStyle::Colon)
+        );
+        assert_eq!(
+            "480000AB",
+            field.to_string_with_style(HexCase::Upper, HexStyle::Plain)
+        );
+        for styled in ["0x480000ab", "0X480000AB", "48:00:00:ab"] {
+            assert_eq!(field, HexDevAddr::from_str(styled).unwrap());
+        }
+        assert!(HexDevAddr::from_str("0x4800").is_err());
+    }
+
+    #[test]
+    fn restyle_json_output() {
+        let mut value = serde_json::json!({
+            "id": "0000000000ABCDEF",
+            "euis": [{"app_eui": "0000000000ABCDEF", "dev_eui": "0000000000000001"}],
+            "devaddr_ranges": [{"start_addr": "480000AB", "end_addr": "480000FF"}],
+            "net_id": "C00053",
+        });
+        restyle(&mut value, HexCase::Lower, HexStyle::Colon);
+        assert_eq!(
+            serde_json::json!({
+                "id": "0000000000ABCDEF",
+                "euis": [{"app_eui": "00:00:00:00:00:ab:cd:ef", "dev_eui": "00:00:00:00:00:00:00:01"}],
+                "devaddr_ranges": [{"start_addr": "48:00:00:ab", "end_addr": "48:00:00:ff"}],
+                "net_id": "c0:00:53",
+            }),
+            value
+        );
+        // Serialized fields do not depend on the output options.
+        assert_eq!(
+            r#""480000AB""#,
+            serde_json::to_string(&devaddr(0x4800_00AB)).unwrap()
+        );
+    }
 }
@@ -1,23 +1,47 @@
+#[cfg(feature = "client")]
+pub mod bundle;
+pub mod checkpoint;
+pub mod chirpstack;
+#[cfg(feature = "client")]
 pub mod client;
+#[cfg(feature = "client")]
 pub mod cmds;
+pub mod diff;
 pub mod hex_field;
+pub mod output;
+#[cfg(feature = "client")]
+pub mod progress;
 pub mod region;
+#[cfg(feature = "client")]
 pub mod region_params;
+pub mod render;
+pub mod report;
 pub mod route;
 pub mod server;
+pub mod snapshot;
 pub mod subnet;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod tts;
+#[cfg(feature = "client")]
+pub mod vcr;
 
 use anyhow::{anyhow, Error};
 use helium_crypto::PublicKey;
-use route::Route;
+use route::{Route, RouteId};
 use serde::{Deserialize, Serialize};
-use std::fmt::Display;
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt::Display,
+    hash::Hash,
+};
 use subnet::DevaddrConstraint;
 
+#[cfg(feature = "client")]
 pub mod proto {
     pub use helium_proto::services::iot_config::{
         DevaddrConstraintV1, DevaddrRangeV1, EuiPairV1, OrgListResV1, OrgResV1, OrgV1,
-        RouteListResV1, SessionKeyFilterV1,
+        RouteListResV1, RouteV1, SessionKeyFilterV1,
     };
 }
 
@@ -30,26 +54,317 @@ type NetId = u32;
 pub enum Msg {
     DryRun(String),
     Success(String),
-    Error(String),
+    Error(String, ExitCode),
+    /// Output meant for other programs, printed without decoration.
+    Plain(String),
 }
 
 impl Msg {
     pub fn ok(msg: String) -> Result<Self> {
         Ok(Self::Success(msg))
     }
+    /// A request the command refused to send, exits with
+    /// [`ExitCode::Invalid`].
     pub fn err(msg: String) -> Result<Self> {
-        Ok(Self::Error(msg))
+        Ok(Self::Error(msg, ExitCode::Invalid))
+    }
+    /// A request that failed, exits with the code of its gRPC status.
+    pub fn failed(context: impl Display, err: Error) -> Result<Self> {
+        let code = ExitCode::from_error(&err);
+        Ok(Self::Error(format!("{context}: {err}"), code))
     }
     pub fn dry_run(msg: String) -> Result<Self> {
         Ok(Self::DryRun(msg))
     }
+    pub fn plain(msg: String) -> Result<Self> {
+        Ok(Self::Plain(msg))
+    }
     pub fn into_inner(self) -> String {
         match self {
             Msg::DryRun(s) => s,
             Msg::Success(s) => s,
-            Msg::Error(s) => s,
+            Msg::Error(s, _) => s,
+            Msg::Plain(s) => s,
+        }
+    }
+    pub fn exit_code(&self) -> ExitCode {
+        match self {
+            Msg::Error(_, code) => *code,
+            _ => ExitCode::Success,
+        }
+    }
+}
+
+/// Process exit codes, stable between releases.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum ExitCode {
+    Success = 0,
+    /// Any failure without a more specific code.
+    Failure = 1,
+    NotFound = 2,
+    Unauthorized = 3,
+    /// The request was rejected as invalid, by the cli or the server.
+    Invalid = 4,
+}
+
+impl ExitCode {
+    #[cfg(feature = "client")]
+    pub fn from_status(status: &tonic::Status) -> Self {
+        use tonic::Code;
+        match status.code() {
+            Code::Ok => Self::Success,
+            Code::NotFound => Self::NotFound,
+            Code::Unauthenticated | Code::PermissionDenied => Self::Unauthorized,
+            Code::InvalidArgument
+            | Code::FailedPrecondition
+            | Code::OutOfRange
+            | Code::AlreadyExists => Self::Invalid,
+            _ => Self::Failure,
+        }
+    }
+
+    /// The code of the first gRPC status in the chain of `err`.
+    pub fn from_error(err: &Error) -> Self {
+        err.chain()
+            .find_map(Self::from_cause)
+            .unwrap_or(Self::Failure)
+    }
+
+    #[cfg(feature = "client")]
+    fn from_cause(cause: &(dyn std::error::Error + 'static)) -> Option<Self> {
+        cause.downcast_ref::<tonic::Status>().map(Self::from_status)
+    }
+
+    #[cfg(not(feature = "client"))]
+    fn from_cause(_cause: &(dyn std::error::Error + 'static)) -> Option<Self> {
+        None
+    }
+
+    pub fn exit(self) -> ! {
+        std::process::exit(self as i32)
+    }
+}
+
+/// Version of the `--porcelain` output format.
+///
+/// Bump this whenever the shape of [`Porcelain`] changes.
+pub const PORCELAIN_VERSION: u32 = 1;
+
+/// Stable single-line JSON representation of a [`Msg`].
+///
+/// `message` is always the raw command output. When that output is itself
+/// JSON, it is also provided parsed in `data`, otherwise `data` is null.
+#[derive(Debug, Serialize)]
+pub struct Porcelain<'a> {
+    pub version: u32,
+    pub status: &'static str,
+    pub message: &'a str,
+    pub data: Option<serde_json::Value>,
+}
+
+impl Msg {
+    pub fn status(&self) -> &'static str {
+        match self {
+            Msg::DryRun(_) => "dry_run",
+            Msg::Success(_) => "success",
+            Msg::Error(..) => "error",
+            Msg::Plain(_) => "success",
+        }
+    }
+
+    fn message(&self) -> &str {
+        match self {
+            Msg::DryRun(s) | Msg::Success(s) | Msg::Error(s, _) | Msg::Plain(s) => s,
         }
     }
+
+    pub fn porcelain(&self) -> Result<String> {
+        let message = self.message();
+        let porcelain = Porcelain {
+            version: PORCELAIN_VERSION,
+            status: self.status(),
+            message,
+            data: serde_json::from_str(message).ok(),
+        };
+        Ok(serde_json::to_string(&porcelain)?)
+    }
+
+    /// The output of `command` for an explicit `--format json`.
+    ///
+    /// Lines starting with `warning: ` go to `warnings`. The rest of the
+    /// output is `data`, parsed when it is JSON or holds JSON after its
+    /// first line, a string otherwise.
+    pub fn envelope(&self, command: &str) -> Result<String> {
+        let (warnings, rest): (Vec<&str>, Vec<&str>) = self
+            .message()
+            .lines()
+            .partition(|line| line.starts_with("warning: "));
+        let rest = rest.join("\n");
+        let envelope = Envelope {
+            status: self.status(),
+            command,
+            data: json_body(&rest).unwrap_or(serde_json::Value::String(rest)),
+            warnings: warnings
+                .iter()
+                .map(|warning| warning.trim_start_matches("warning: "))
+                .collect(),
+        };
+        Ok(serde_json::to_string_pretty(&envelope)?)
+    }
+}
+
+/// See [`Msg::envelope`].
+#[derive(Debug, Serialize)]
+pub struct Envelope<'a> {
+    pub status: &'static str,
+    pub command: &'a str,
+    pub data: serde_json::Value,
+    pub warnings: Vec<&'a str>,
+}
+
+/// `msg` as JSON, or the JSON following a first line like
+/// `created route <id>`.
+fn json_body(msg: &str) -> Option<serde_json::Value> {
+    serde_json::from_str(msg).ok().or_else(|| {
+        let (_, rest) = msg.split_once('\n')?;
+        serde_json::from_str(rest).ok()
+    })
+}
+
+impl Msg {
+    /// Rename the snake_case keys of JSON output to the protobuf JSON
+    /// names, `route_id` becomes `routeId`.
+    ///
+    /// Output that is not JSON is returned unchanged.
+    pub fn with_proto_names(self) -> Result<Self> {
+        let rename = |msg: String| -> Result<String> {
+            match serde_json::from_str::<serde_json::Value>(&msg) {
+                Ok(mut value @ (serde_json::Value::Object(_) | serde_json::Value::Array(_))) => {
+                    rename_proto_names(&mut value);
+                    value.pretty_json()
+                }
+                _ => Ok(msg),
+            }
+        };
+        Ok(match self {
+            Msg::DryRun(msg) => Msg::DryRun(rename(msg)?),
+            Msg::Success(msg) => Msg::Success(rename(msg)?),
+            Msg::Error(msg, code) => Msg::Error(rename(msg)?, code),
+            Msg::Plain(msg) => Msg::Plain(rename(msg)?),
+        })
+    }
+
+    /// Hex fields of JSON output in the `--hex-case` and `--hex-style` of
+    /// [`output`], see [`hex_field::restyle`].
+    ///
+    /// Plain output is kept as is, it is what export commands write to be
+    /// read back.
+    pub fn with_hex_format(self) -> Result<Self> {
+        let restyle = |msg: String| -> Result<String> {
+            match serde_json::from_str::<serde_json::Value>(&msg) {
+                Ok(value @ (serde_json::Value::Object(_) | serde_json::Value::Array(_))) => {
+                    output::restyle_hex(value).pretty_json()
+                }
+                _ => Ok(msg),
+            }
+        };
+        Ok(match self {
+            Msg::DryRun(msg) => Msg::DryRun(restyle(msg)?),
+            Msg::Success(msg) => Msg::Success(restyle(msg)?),
+            Msg::Error(msg, code) => Msg::Error(restyle(msg)?, code),
+            msg @ Msg::Plain(_) => msg,
+        })
+    }
+
+    /// Render JSON output in `format`, see [`render`].
+    ///
+    /// Output that is not JSON is returned unchanged.
+    pub fn with_format(self, format: render::OutputFormat) -> Result<Self> {
+        let convert = |msg: String| -> Result<String> {
+            match serde_json::from_str::<serde_json::Value>(&msg) {
+                Ok(value @ (serde_json::Value::Object(_) | serde_json::Value::Array(_))) => {
+                    render::render(&value, format)
+                }
+                _ => Ok(msg),
+            }
+        };
+        Ok(match self {
+            Msg::DryRun(msg) => Msg::DryRun(convert(msg)?),
+            Msg::Success(msg) => Msg::Success(convert(msg)?),
+            Msg::Error(msg, code) => Msg::Error(convert(msg)?, code),
+            Msg::Plain(msg) => Msg::Plain(convert(msg)?),
+        })
+    }
+
+    /// Output for `--quiet`: the ids of Routes and the ouis of Orgs in
+    /// JSON output, one per line. Plain output is kept as is, everything
+    /// else is dropped.
+    pub fn quiet(&self) -> Option<String> {
+        match self {
+            Msg::Plain(msg) => Some(msg.clone()),
+            Msg::Success(msg) => {
+                let values = essential_values(&json_body(msg)?);
+                (!values.is_empty()).then(|| values.join("\n"))
+            }
+            Msg::DryRun(_) | Msg::Error(..) => None,
+        }
+    }
+}
+
+fn essential_values(value: &serde_json::Value) -> Vec<String> {
+    use serde_json::Value;
+    match value {
+        Value::Array(values) => values.iter().flat_map(essential_values).collect(),
+        Value::Object(map) => match map.get("id").or_else(|| map.get("oui")) {
+            Some(Value::String(id)) => vec![id.clone()],
+            Some(id) => vec![id.to_string()],
+            None => map.values().flat_map(essential_values).collect(),
+        },
+        _ => vec![],
+    }
+}
+
+fn rename_proto_names(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            *map = std::mem::take(map)
+                .into_iter()
+                .map(|(key, mut value)| {
+                    rename_proto_names(&mut value);
+                    (proto_json_name(&key).unwrap_or(key), value)
+                })
+                .collect();
+        }
+        serde_json::Value::Array(values) => values.iter_mut().for_each(rename_proto_names),
+        _ => (),
+    }
+}
+
+/// The protobuf JSON name of a snake_case field, `None` for any other key.
+///
+/// Keys that are data rather than field names, like the regions of a gwmp
+/// mapping, are not snake_case and keep their name.
+fn proto_json_name(key: &str) -> Option<String> {
+    let is_field = key.starts_with(|c: char| c.is_ascii_lowercase())
+        && key
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+    if !is_field || !key.contains('_') {
+        return None;
+    }
+    let mut name = String::with_capacity(key.len());
+    let mut upper = false;
+    for c in key.chars() {
+        match c {
+            '_' => upper = true,
+            c if upper => {
+                name.push(c.to_ascii_uppercase());
+                upper = false;
+            }
+            c => name.push(c),
+        }
+    }
+    Some(name)
 }
 
 impl Display for Msg {
@@ -57,7 +372,8 @@ impl Display for Msg {
         match self {
             Msg::DryRun(msg) => write!(f, "== DRY RUN == (pass `--commit`)\n{msg}"),
             Msg::Success(msg) => write!(f, "\u{2713} {msg}"),
-            Msg::Error(msg) => write!(f, "\u{2717} {msg}"),
+            Msg::Error(msg, _) => write!(f, "\u{2717} {msg}"),
+            Msg::Plain(msg) => write!(f, "{msg}"),
         }
     }
 }
@@ -85,6 +401,7 @@ pub struct OrgResponse {
     pub devaddr_constraints: Vec<DevaddrConstraint>,
 }
 
+#[cfg(feature = "client")]
 impl From<proto::OrgResV1> for OrgResponse {
     fn from(res: proto::OrgResV1) -> Self {
         Self {
@@ -104,6 +421,21 @@ pub struct OrgList {
     pub orgs: Vec<Org>,
 }
 
+impl OrgList {
+    pub fn retain_owner(&mut self, owner: &PublicKey) {
+        self.orgs.retain(|org| org.owner == *owner);
+    }
+
+    /// Keep `limit` Orgs, all of them without one, after skipping `offset`.
+    pub fn page(&mut self, offset: usize, limit: Option<usize>) {
+        self.orgs = std::mem::take(&mut self.orgs)
+            .into_iter()
+            .skip(offset)
+            .take(limit.unwrap_or(usize::MAX))
+            .collect();
+    }
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct Org {
     pub oui: Oui,
@@ -118,16 +450,93 @@ pub struct RouteList {
     pub routes: Vec<Route>,
 }
 
+impl RouteList {
+    /// Keep the Routes matching every filter.
+    pub fn retain(&mut self, filters: &[RouteFilter]) {
+        self.routes
+            .retain(|route| filters.iter().all(|filter| filter.matches(route)));
+    }
+
+    pub fn sort(&mut self, key: RouteSort) {
+        match key {
+            RouteSort::Id => self.routes.sort_by(|a, b| a.id.cmp(&b.id)),
+            RouteSort::MaxCopies => self.routes.sort_by_key(|route| route.max_copies),
+            RouteSort::Host => self
+                .routes
+                .sort_by(|a, b| a.server.host.cmp(&b.server.host)),
+        }
+    }
+}
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RouteSort {
+    Id,
+    MaxCopies,
+    /// The server host
+    Host,
+}
+
+/// A `key=value` condition on a Route, `active=true` or `protocol=http`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RouteFilter {
+    Active(bool),
+    Locked(bool),
+    Protocol(String),
+    Host(String),
+    MaxCopies(u32),
+}
+
+impl RouteFilter {
+    pub fn matches(&self, route: &Route) -> bool {
+        match self {
+            RouteFilter::Active(active) => route.active == *active,
+            RouteFilter::Locked(locked) => route.locked == *locked,
+            RouteFilter::Protocol(name) => route
+                .server
+                .protocol
+                .as_ref()
+                .map_or(false, |protocol| protocol.name() == name),
+            RouteFilter::Host(host) => route.server.host == *host,
+            RouteFilter::MaxCopies(max_copies) => route.max_copies == *max_copies,
+        }
+    }
+}
+
+impl std::str::FromStr for RouteFilter {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (key, value) = s
+            .split_once('=')
+            .ok_or_else(|| anyhow!("expected key=value, got {s:?}"))?;
+        Ok(match key {
+            "active" => RouteFilter::Active(value.parse()?),
+            "locked" => RouteFilter::Locked(value.parse()?),
+            "protocol" => match value {
+                "gwmp" | "http" | "packet_router" => RouteFilter::Protocol(value.to_string()),
+                _ => return Err(anyhow!("protocol must be gwmp, http, or packet_router")),
+            },
+            "host" => RouteFilter::Host(value.to_string()),
+            "max_copies" => RouteFilter::MaxCopies(value.parse()?),
+            _ => {
+                return Err(anyhow!(
+                    "unknown filter {key:?}, expected active, locked, protocol, host, or max_copies"
+                ))
+            }
+        })
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize, PartialEq, Eq, Clone, Hash)]
 pub struct DevaddrRange {
-    pub route_id: String,
+    pub route_id: RouteId,
     pub start_addr: hex_field::HexDevAddr,
     pub end_addr: hex_field::HexDevAddr,
 }
 
 impl DevaddrRange {
     pub fn new(
-        route_id: String,
+        route_id: RouteId,
         start_addr: hex_field::HexDevAddr,
         end_addr: hex_field::HexDevAddr,
     ) -> Result<Self> {
@@ -141,18 +550,58 @@ impl DevaddrRange {
             end_addr,
         })
     }
+
+    /// Parse a JSON list of `{"start_addr": .., "end_addr": ..}` for
+    /// `route_id`. Other fields are ignored, so the output of `devaddrs list`
+    /// can be read back.
+    pub fn from_json(route_id: &RouteId, json: &str) -> Result<Vec<Self>> {
+        let ranges: Vec<DevaddrConstraint> = from_json_list(json)?;
+        collect_all(ranges.into_iter().enumerate().map(|(index, range)| {
+            Self::new(route_id.clone(), range.start_addr, range.end_addr)
+                .map_err(|err| anyhow!("entry {index}: {err}"))
+        }))
+    }
+
+    /// Parse `<start_addr>,<end_addr>` lines for `route_id`.
+    ///
+    /// Blank lines, `#` comments and a `start_addr,end_addr` header are
+    /// skipped.
+    pub fn from_csv(route_id: &RouteId, csv: &str) -> Result<Vec<Self>> {
+        let entries = csv
+            .lines()
+            .enumerate()
+            .map(|(index, line)| (index + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+            .filter(|(_, line)| !line.starts_with("start_addr"))
+            .map(|(number, line)| {
+                let (start_addr, end_addr) = line
+                    .split_once(',')
+                    .ok_or_else(|| anyhow!("line {number}: expected <start_addr>,<end_addr>"))?;
+                Self::new(
+                    route_id.clone(),
+                    hex_field::validate_devaddr(start_addr.trim())
+                        .map_err(|err| anyhow!("line {number}: {err}"))?,
+                    hex_field::validate_devaddr(end_addr.trim())
+                        .map_err(|err| anyhow!("line {number}: {err}"))?,
+                )
+                .map_err(|err| anyhow!("line {number}: {err}"))
+            });
+        collect_all(entries)
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
 pub struct Eui {
-    pub route_id: String,
+    pub route_id: RouteId,
     pub app_eui: hex_field::HexEui,
     pub dev_eui: hex_field::HexEui,
 }
 
 impl Eui {
+    /// A `dev_eui` of 0, written `*` on the command line and in files, is a
+    /// wildcard: the pair matches every device joining with `app_eui`.
     pub fn new(
-        route_id: String,
+        route_id: RouteId,
         app_eui: hex_field::HexEui,
         dev_eui: hex_field::HexEui,
     ) -> Result<Self> {
@@ -162,6 +611,61 @@ impl Eui {
             dev_eui,
         })
     }
+
+    /// The pair matching every device of `app_eui`.
+    pub fn wildcard(route_id: RouteId, app_eui: hex_field::HexEui) -> Self {
+        Self {
+            route_id,
+            app_eui,
+            dev_eui: hex_field::HexEui::default(),
+        }
+    }
+
+    pub fn is_wildcard(&self) -> bool {
+        self.dev_eui.0 == 0
+    }
+
+    /// Parse a JSON list of `{"app_eui": .., "dev_eui": ..}` for `route_id`.
+    /// Other fields are ignored, so the output of `euis list` can be read
+    /// back.
+    pub fn from_json(route_id: &RouteId, json: &str) -> Result<Vec<Self>> {
+        #[derive(Deserialize)]
+        struct Pair {
+            app_eui: hex_field::HexEui,
+            dev_eui: hex_field::HexEui,
+        }
+        let pairs: Vec<Pair> = from_json_list(json)?;
+        collect_all(
+            pairs
+                .into_iter()
+                .map(|pair| Self::new(route_id.clone(), pair.app_eui, pair.dev_eui)),
+        )
+    }
+
+    /// Parse `<app_eui>,<dev_eui>` lines for `route_id`.
+    ///
+    /// Blank lines, `#` comments and an `app_eui,dev_eui` header are skipped.
+    pub fn from_csv(route_id: &RouteId, csv: &str) -> Result<Vec<Self>> {
+        let entries = csv
+            .lines()
+            .enumerate()
+            .map(|(index, line)| (index + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+            .filter(|(_, line)| !line.starts_with("app_eui"))
+            .map(|(number, line)| {
+                let (app_eui, dev_eui) = line
+                    .split_once(',')
+                    .ok_or_else(|| anyhow!("line {number}: expected <app_eui>,<dev_eui>"))?;
+                Self::new(
+                    route_id.clone(),
+                    hex_field::validate_eui(app_eui.trim())
+                        .map_err(|err| anyhow!("line {number}: {err}"))?,
+                    hex_field::validate_eui(dev_eui.trim())
+                        .map_err(|err| anyhow!("line {number}: {err}"))?,
+                )
+            });
+        collect_all(entries)
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
@@ -181,6 +685,369 @@ impl SessionKeyFilter {
     }
 }
 
+pub const SKF_EXPORT_VERSION: u32 = 1;
+
+/// Portable backup of the Session Key Filters of an Org.
+///
+/// Written by `skf export` and read by `skf import`.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SessionKeyFilterExport {
+    pub version: u32,
+    pub oui: Oui,
+    pub filters: Vec<ExportedFilter>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
+pub struct ExportedFilter {
+    pub devaddr: hex_field::HexDevAddr,
+    pub session_key: String,
+}
+
+impl ExportedFilter {
+    /// A JSON list of `{"devaddr", "session_key"}`, every entry checked.
+    pub fn from_json(json: &str) -> Result<Vec<Self>> {
+        Self::from_values(serde_json::from_str(json)?)
+    }
+
+    fn from_values(values: Vec<serde_json::Value>) -> Result<Vec<Self>> {
+        collect_all(values.into_iter().enumerate().map(|(index, value)| {
+            serde_json::from_value::<Self>(value)
+                .map_err(Error::from)
+                .and_then(|filter| {
+                    hex_field::validate_session_key(&filter.session_key)?;
+                    Ok(filter)
+                })
+                .map_err(|err| anyhow!("entry {index}: {err}"))
+        }))
+    }
+}
+
+impl SessionKeyFilterExport {
+    pub fn new(oui: Oui, filters: Vec<SessionKeyFilter>) -> Self {
+        Self {
+            version: SKF_EXPORT_VERSION,
+            oui,
+            filters: filters
+                .into_iter()
+                .map(|filter| ExportedFilter {
+                    devaddr: filter.devaddr,
+                    session_key: filter.session_key,
+                })
+                .collect(),
+        }
+    }
+
+    /// Every filter is checked, a file with bad ones is rejected listing all
+    /// of them.
+    pub fn from_json(json: &str) -> Result<Self> {
+        #[derive(Deserialize)]
+        struct Export {
+            version: u32,
+            oui: Oui,
+            filters: Vec<serde_json::Value>,
+        }
+        let export: Export = serde_json::from_str(json)?;
+        if export.version != SKF_EXPORT_VERSION {
+            return Err(anyhow!(
+                "unsupported session key filter export version {}, expected {SKF_EXPORT_VERSION}",
+                export.version
+            ));
+        }
+        Ok(Self {
+            version: export.version,
+            oui: export.oui,
+            filters: ExportedFilter::from_values(export.filters)?,
+        })
+    }
+
+    pub fn into_filters(self) -> Vec<SessionKeyFilter> {
+        self.filters
+            .into_iter()
+            .map(|filter| SessionKeyFilter::new(self.oui, filter.devaddr, filter.session_key))
+            .collect()
+    }
+}
+
+pub const ROUTE_EXPORT_VERSION: u32 = 1;
+
+/// A Route with its EUI pairs and Devaddr Ranges, for moving it between
+/// environments.
+///
+/// Written by `route export` and read by `route import`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct RouteExport {
+    pub version: u32,
+    pub route: Route,
+    pub euis: Vec<Eui>,
+    pub devaddrs: Vec<DevaddrRange>,
+}
+
+impl RouteExport {
+    pub fn new(route: Route, euis: Vec<Eui>, devaddrs: Vec<DevaddrRange>) -> Self {
+        Self {
+            version: ROUTE_EXPORT_VERSION,
+            route,
+            euis,
+            devaddrs,
+        }
+    }
+
+    pub fn from_json(json: &str) -> Result<Self> {
+        let export: Self = serde_json::from_str(json)?;
+        if export.version != ROUTE_EXPORT_VERSION {
+            return Err(anyhow!(
+                "unsupported route export version {}, expected {ROUTE_EXPORT_VERSION}",
+                export.version
+            ));
+        }
+        Ok(export)
+    }
+
+    /// Length-delimited protobuf messages: the `RouteV1`, the number of EUI
+    /// pairs as a varint followed by the `EuiPairV1`s, then the same for the
+    /// `DevaddrRangeV1`s.
+    #[cfg(feature = "client")]
+    pub fn to_proto(&self) -> Result<Vec<u8>> {
+        use prost::Message;
+
+        let mut buf = vec![];
+        proto::RouteV1::from(self.route.clone()).encode_length_delimited(&mut buf)?;
+        prost::encoding::encode_varint(self.euis.len() as u64, &mut buf);
+        for eui in &self.euis {
+            proto::EuiPairV1::from(eui.clone()).encode_length_delimited(&mut buf)?;
+        }
+        prost::encoding::encode_varint(self.devaddrs.len() as u64, &mut buf);
+        for range in &self.devaddrs {
+            proto::DevaddrRangeV1::from(range.clone()).encode_length_delimited(&mut buf)?;
+        }
+        Ok(buf)
+    }
+
+    #[cfg(feature = "client")]
+    pub fn from_proto(mut buf: &[u8]) -> Result<Self> {
+        use prost::Message;
+
+        let route = proto::RouteV1::decode_length_delimited(&mut buf)?.into();
+        let count = prost::encoding::decode_varint(&mut buf)?;
+        let euis = (0..count)
+            .map(|_| Ok(proto::EuiPairV1::decode_length_delimited(&mut buf)?.into()))
+            .collect::<Result<_>>()?;
+        let count = prost::encoding::decode_varint(&mut buf)?;
+        let devaddrs = (0..count)
+            .map(|_| Ok(proto::DevaddrRangeV1::decode_length_delimited(&mut buf)?.into()))
+            .collect::<Result<_>>()?;
+        if !buf.is_empty() {
+            return Err(anyhow!("{} unexpected bytes after the route", buf.len()));
+        }
+        Ok(Self::new(route, euis, devaddrs))
+    }
+
+    /// The EUI pairs and Devaddr Ranges moved to `route_id`, the id of the
+    /// Route once it is created in another environment.
+    pub fn rebind(&mut self, route_id: &RouteId) {
+        for eui in &mut self.euis {
+            eui.route_id = route_id.clone();
+        }
+        for range in &mut self.devaddrs {
+            range.route_id = route_id.clone();
+        }
+    }
+}
+
+/// Counts reported by the `add` commands, which skip entries that already
+/// exist so provisioning scripts can be rerun. Entries repeated in the input
+/// are counted as duplicates, not as present.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct AddReport {
+    pub added: usize,
+    pub already_present: usize,
+    pub duplicates: usize,
+    pub failed: usize,
+}
+
+impl AddReport {
+    pub fn new(added: usize, already_present: usize) -> Self {
+        Self {
+            added,
+            already_present,
+            duplicates: 0,
+            failed: 0,
+        }
+    }
+
+    pub fn with_duplicates(self, duplicates: usize) -> Self {
+        Self { duplicates, ..self }
+    }
+
+    /// The entries that were going to be added could not be.
+    pub fn failed(self) -> Self {
+        Self {
+            added: 0,
+            failed: self.added + self.failed,
+            ..self
+        }
+    }
+}
+
+impl Display for AddReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "added: {}, already-present: {}, duplicates: {}, failed: {}",
+            self.added, self.already_present, self.duplicates, self.failed
+        )
+    }
+}
+
+/// What a file import would do, told by the dry run before anything is sent:
+/// every entry of the file is either new, already present, or a repeat of an
+/// earlier line.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ImportDiff {
+    pub entries: usize,
+    pub to_add: usize,
+    pub already_present: usize,
+    pub duplicates: usize,
+}
+
+impl ImportDiff {
+    /// `missing` are the positions in `entries` from [`missing_positions`].
+    pub fn new<T: Eq + Hash>(entries: &[T], missing: &[usize]) -> Self {
+        let distinct = entries.iter().collect::<HashSet<_>>().len();
+        Self {
+            entries: entries.len(),
+            to_add: missing.len(),
+            already_present: distinct - missing.len(),
+            duplicates: entries.len() - distinct,
+        }
+    }
+}
+
+impl Display for ImportDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "entries: {}, to-add: {}, already-present: {}, duplicates: {}",
+            self.entries, self.to_add, self.already_present, self.duplicates
+        )
+    }
+}
+
+/// App EUIs with a wildcard pair and specific pairs too, with the number of
+/// specific pairs the wildcard makes redundant, sorted by App EUI.
+pub fn wildcard_overlaps<'a>(
+    euis: impl IntoIterator<Item = &'a Eui>,
+) -> Vec<(hex_field::HexEui, usize)> {
+    let mut wildcards = HashSet::new();
+    let mut specific: BTreeMap<u64, HashSet<u64>> = BTreeMap::new();
+    for eui in euis {
+        if eui.is_wildcard() {
+            wildcards.insert(eui.app_eui.0);
+        } else {
+            specific
+                .entry(eui.app_eui.0)
+                .or_default()
+                .insert(eui.dev_eui.0);
+        }
+    }
+    specific
+        .into_iter()
+        .filter(|(app_eui, _)| wildcards.contains(app_eui))
+        .map(|(app_eui, dev_euis)| (hex_field::HexEui::from(app_eui), dev_euis.len()))
+        .collect()
+}
+
+/// Errors listed by [`collect_all`] before the rest are only counted.
+pub const MAX_LISTED_ERRORS: usize = 50;
+
+/// All the entries, or an error listing every entry that failed, so a file is
+/// fixed in one go instead of one bad line at a time.
+pub fn collect_all<T>(entries: impl IntoIterator<Item = Result<T>>) -> Result<Vec<T>> {
+    let mut valid = vec![];
+    let mut errors = vec![];
+    for entry in entries {
+        match entry {
+            Ok(entry) => valid.push(entry),
+            Err(err) => errors.push(err.to_string()),
+        }
+    }
+    if errors.is_empty() {
+        return Ok(valid);
+    }
+    let count = errors.len();
+    if count > MAX_LISTED_ERRORS {
+        errors.truncate(MAX_LISTED_ERRORS);
+        errors.push(format!("and {} more", count - MAX_LISTED_ERRORS));
+    }
+    Err(anyhow!(
+        "invalid entries: {count} of {}\n{}",
+        count + valid.len(),
+        errors.join("\n")
+    ))
+}
+
+/// Deserialize each entry of a JSON list on its own, so every bad entry is
+/// reported by its index.
+pub fn from_json_list<T: serde::de::DeserializeOwned>(json: &str) -> Result<Vec<T>> {
+    let values: Vec<serde_json::Value> = serde_json::from_str(json)?;
+    collect_all(values.into_iter().enumerate().map(|(index, value)| {
+        serde_json::from_value(value).map_err(|err| anyhow!("entry {index}: {err}"))
+    }))
+}
+
+/// Split `entries` into the ones missing from `existing` and the ones already
+/// there. An entry repeated in `entries` is only added once.
+pub fn split_present<T: Eq + Hash + Clone>(
+    entries: Vec<T>,
+    existing: impl IntoIterator<Item = T>,
+) -> (Vec<T>, Vec<T>) {
+    let mut seen: HashSet<T> = existing.into_iter().collect();
+    entries
+        .into_iter()
+        .partition(|entry| seen.insert(entry.clone()))
+}
+
+/// The first of every entry in `entries`, in order, and the number of
+/// repeats left out.
+pub fn dedup_entries<T: Eq + Hash + Clone>(entries: Vec<T>) -> (Vec<T>, usize) {
+    let total = entries.len();
+    let mut seen = HashSet::new();
+    let distinct: Vec<T> = entries
+        .into_iter()
+        .filter(|entry| seen.insert(entry.clone()))
+        .collect();
+    let repeats = total - distinct.len();
+    (distinct, repeats)
+}
+
+/// Positions in `entries` of the ones missing from `existing`, like
+/// [`split_present`], for sending them in file order.
+pub fn missing_positions<T: Eq + Hash + Clone>(
+    entries: &[T],
+    existing: impl IntoIterator<Item = T>,
+) -> Vec<usize> {
+    let mut seen: HashSet<T> = existing.into_iter().collect();
+    entries
+        .iter()
+        .enumerate()
+        .filter(|(_, entry)| seen.insert((*entry).clone()))
+        .map(|(position, _)| position)
+        .collect()
+}
+
+/// What makes `existing` match `desired`: the entries to add and the
+/// entries to remove.
+pub fn sync_delta<T: Eq + Hash + Clone>(desired: Vec<T>, existing: Vec<T>) -> (Vec<T>, Vec<T>) {
+    let wanted: HashSet<T> = desired.iter().cloned().collect();
+    let (to_add, _) = split_present(desired, existing.clone());
+    let to_remove = existing
+        .into_iter()
+        .filter(|entry| !wanted.contains(entry))
+        .collect();
+    (to_add, to_remove)
+}
+
+#[cfg(feature = "client")]
 impl From<proto::SessionKeyFilterV1> for SessionKeyFilter {
     fn from(filter: proto::SessionKeyFilterV1) -> Self {
         Self {
@@ -191,6 +1058,7 @@ impl From<proto::SessionKeyFilterV1> for SessionKeyFilter {
     }
 }
 
+#[cfg(feature = "client")]
 impl From<SessionKeyFilter> for proto::SessionKeyFilterV1 {
     fn from(filter: SessionKeyFilter) -> Self {
         Self {
@@ -201,6 +1069,7 @@ impl From<SessionKeyFilter> for proto::SessionKeyFilterV1 {
     }
 }
 
+#[cfg(feature = "client")]
 impl From<proto::OrgListResV1> for OrgList {
     fn from(org_list: proto::OrgListResV1) -> Self {
         Self {
@@ -209,6 +1078,7 @@ impl From<proto::OrgListResV1> for OrgList {
     }
 }
 
+#[cfg(feature = "client")]
 impl From<proto::OrgV1> for Org {
     fn from(org: proto::OrgV1) -> Self {
         let d = org.delegate_keys.into_iter().flat_map(PublicKey::try_from);
@@ -222,6 +1092,7 @@ impl From<proto::OrgV1> for Org {
     }
 }
 
+#[cfg(feature = "client")]
 impl From<Org> for proto::OrgV1 {
     fn from(org: Org) -> Self {
         Self {
@@ -234,6 +1105,7 @@ impl From<Org> for proto::OrgV1 {
     }
 }
 
+#[cfg(feature = "client")]
 impl From<proto::RouteListResV1> for RouteList {
     fn from(route_list: proto::RouteListResV1) -> Self {
         Self {
@@ -242,36 +1114,40 @@ impl From<proto::RouteListResV1> for RouteList {
     }
 }
 
+#[cfg(feature = "client")]
 impl From<proto::DevaddrRangeV1> for DevaddrRange {
     fn from(range: proto::DevaddrRangeV1) -> Self {
         Self {
-            route_id: range.route_id,
+            route_id: RouteId::new_unchecked(range.route_id),
             start_addr: range.start_addr.into(),
             end_addr: range.end_addr.into(),
         }
     }
 }
 
+#[cfg(feature = "client")]
 impl From<&proto::DevaddrRangeV1> for DevaddrRange {
     fn from(range: &proto::DevaddrRangeV1) -> Self {
         Self {
-            route_id: range.route_id.to_owned(),
+            route_id: RouteId::new_unchecked(range.route_id.as_str()),
             start_addr: range.start_addr.into(),
             end_addr: range.end_addr.into(),
         }
     }
 }
 
+#[cfg(feature = "client")]
 impl From<DevaddrRange> for proto::DevaddrRangeV1 {
     fn from(range: DevaddrRange) -> Self {
         Self {
-            route_id: range.route_id,
+            route_id: range.route_id.into(),
             start_addr: range.start_addr.into(),
             end_addr: range.end_addr.into(),
         }
     }
 }
 
+#[cfg(feature = "client")]
 impl From<proto::DevaddrConstraintV1> for DevaddrConstraint {
     fn from(value: proto::DevaddrConstraintV1) -> Self {
         Self {
@@ -281,6 +1157,7 @@ impl From<proto::DevaddrConstraintV1> for DevaddrConstraint {
     }
 }
 
+#[cfg(feature = "client")]
 impl From<DevaddrConstraint> for proto::DevaddrConstraintV1 {
     fn from(value: DevaddrConstraint) -> Self {
         Self {
@@ -290,30 +1167,33 @@ impl From<DevaddrConstraint> for proto::DevaddrConstraintV1 {
     }
 }
 
+#[cfg(feature = "client")]
 impl From<proto::EuiPairV1> for Eui {
     fn from(value: proto::EuiPairV1) -> Self {
         Self {
-            route_id: value.route_id,
+            route_id: RouteId::new_unchecked(value.route_id),
             app_eui: value.app_eui.into(),
             dev_eui: value.dev_eui.into(),
         }
     }
 }
 
+#[cfg(feature = "client")]
 impl From<&proto::EuiPairV1> for Eui {
     fn from(value: &proto::EuiPairV1) -> Self {
         Self {
-            route_id: value.route_id.clone(),
+            route_id: RouteId::new_unchecked(value.route_id.as_str()),
             app_eui: value.app_eui.into(),
             dev_eui: value.dev_eui.into(),
         }
     }
 }
 
+#[cfg(feature = "client")]
 impl From<Eui> for proto::EuiPairV1 {
     fn from(value: Eui) -> Self {
         Self {
-            route_id: value.route_id,
+            route_id: value.route_id.into(),
             app_eui: value.app_eui.0,
             dev_eui: value.dev_eui.0,
         }
@@ -322,7 +1202,13 @@ impl From<Eui> for proto::EuiPairV1 {
 
 #[cfg(test)]
 mod tests {
-    use crate::{hex_field, DevaddrRange, Eui};
+    use crate::{
+        collect_all, dedup_entries, hex_field, missing_positions, route::RouteId, split_present,
+        sync_delta, wildcard_overlaps, AddReport, DevaddrRange, Eui, ExportedFilter, ImportDiff,
+        Msg, Org, OrgList, Route, RouteExport, RouteFilter, RouteList, RouteSort, SessionKeyFilter,
+        SessionKeyFilterExport,
+    };
+    use anyhow::anyhow;
 
     #[test]
     fn deserialize_devaddr_range() {
@@ -330,7 +1216,7 @@ mod tests {
         let val: DevaddrRange = serde_json::from_str(d).unwrap();
         assert_eq!(
             DevaddrRange {
-                route_id: "the-route-id".to_string(),
+                route_id: RouteId::new_unchecked("the-route-id"),
                 start_addr: hex_field::devaddr(0x11223344),
                 end_addr: hex_field::devaddr(0x22334455)
             },
@@ -344,11 +1230,396 @@ mod tests {
         let val: Eui = serde_json::from_str(d).unwrap();
         assert_eq!(
             Eui {
-                route_id: "the-route-id".to_string(),
+                route_id: RouteId::new_unchecked("the-route-id"),
                 app_eui: hex_field::eui(0x1122334411223344),
                 dev_eui: hex_field::eui(0x2233445522334455)
             },
             val
         );
     }
+
+    #[test]
+    fn session_key_filter_export() {
+        let filters = vec![
+            SessionKeyFilter::new(4, hex_field::devaddr(0x48000001), "key-1".to_string()),
+            SessionKeyFilter::new(4, hex_field::devaddr(0x48000002), "key-2".to_string()),
+        ];
+        let json = serde_json::to_string(&SessionKeyFilterExport::new(4, filters.clone())).unwrap();
+        assert_eq!(
+            r#"{"version":1,"oui":4,"filters":[{"devaddr":"48000001","session_key":"key-1"},{"devaddr":"48000002","session_key":"key-2"}]}"#,
+            json
+        );
+        let export = SessionKeyFilterExport::from_json(&json).unwrap();
+        assert_eq!(filters, export.into_filters());
+
+        let future = r#"{"version":2,"oui":4,"filters":[]}"#;
+        assert!(SessionKeyFilterExport::from_json(future).is_err());
+    }
+
+    #[cfg(feature = "client")]
+    #[test]
+    fn route_export_proto() {
+        let route_id = RouteId::new_unchecked("route-one");
+        let mut route = Route::new(hex_field::net_id(0xC00053), 4, 2);
+        route.id = route_id.clone();
+        let export = RouteExport::new(
+            route,
+            vec![
+                Eui::new(route_id.clone(), hex_field::eui(1), hex_field::eui(2)).unwrap(),
+                Eui::new(route_id.clone(), hex_field::eui(3), hex_field::eui(4)).unwrap(),
+            ],
+            vec![DevaddrRange::new(
+                route_id,
+                hex_field::devaddr(0x48000000),
+                hex_field::devaddr(0x48000003),
+            )
+            .unwrap()],
+        );
+
+        let bytes = export.to_proto().unwrap();
+        assert_eq!(export, RouteExport::from_proto(&bytes).unwrap());
+        assert!(RouteExport::from_proto(&bytes[..bytes.len() - 1]).is_err());
+        assert!(RouteExport::from_proto(&[bytes.as_slice(), &[0]].concat()).is_err());
+
+        let json = serde_json::to_string(&export).unwrap();
+        assert_eq!(export, RouteExport::from_json(&json).unwrap());
+
+        let mut moved = export;
+        moved.rebind(&RouteId::new_unchecked("route-two"));
+        assert!(moved
+            .euis
+            .iter()
+            .all(|eui| eui.route_id.as_str() == "route-two"));
+        assert!(moved
+            .devaddrs
+            .iter()
+            .all(|range| range.route_id.as_str() == "route-two"));
+    }
+
+    #[test]
+    fn porcelain_output() {
+        let msg = Msg::Success(r#"{"oui": 1}"#.to_string());
+        let val: serde_json::Value = serde_json::from_str(&msg.porcelain().unwrap()).unwrap();
+        assert_eq!(val["version"], 1);
+        assert_eq!(val["status"], "success");
+        assert_eq!(val["message"], r#"{"oui": 1}"#);
+        assert_eq!(val["data"]["oui"], 1);
+
+        let msg = Msg::DryRun("delete route".to_string());
+        let val: serde_json::Value = serde_json::from_str(&msg.porcelain().unwrap()).unwrap();
+        assert_eq!(val["status"], "dry_run");
+        assert_eq!(val["message"], "delete route");
+        assert!(val["data"].is_null());
+    }
+
+    #[test]
+    fn org_list_page() {
+        use helium_crypto::{KeyTag, KeyType, Keypair, Network, PublicKey};
+        let owners: Vec<PublicKey> = (0..2)
+            .map(|_| {
+                let tag = KeyTag {
+                    network: Network::MainNet,
+                    key_type: KeyType::Ed25519,
+                };
+                Keypair::generate(tag, &mut rand::rngs::OsRng)
+                    .public_key()
+                    .clone()
+            })
+            .collect();
+        let org = |oui: u64, owner: PublicKey| Org {
+            oui,
+            owner: owner.clone(),
+            payer: owner,
+            delegate_keys: vec![],
+            locked: false,
+        };
+        let mut list = OrgList {
+            orgs: (1..=5)
+                .map(|oui| org(oui, owners[oui as usize % 2].clone()))
+                .collect(),
+        };
+        let ouis = |list: &OrgList| list.orgs.iter().map(|org| org.oui).collect::<Vec<_>>();
+
+        list.retain_owner(&owners[1]);
+        assert_eq!(vec![1, 3, 5], ouis(&list));
+        list.page(1, Some(1));
+        assert_eq!(vec![3], ouis(&list));
+        list.page(0, None);
+        assert_eq!(vec![3], ouis(&list));
+        list.page(2, None);
+        assert!(list.orgs.is_empty());
+    }
+
+    #[test]
+    fn route_list_filter_and_sort() {
+        let route = |id: &str, host: &str, max_copies: u32, active: bool| {
+            let mut route = Route::new(hex_field::net_id(0xC00053), 1, max_copies);
+            route.id = RouteId::new_unchecked(id);
+            route.server.host = host.to_string();
+            route.active = active;
+            route
+        };
+        let mut list = RouteList {
+            routes: vec![
+                route("b", "z.example.com", 1, true),
+                route("a", "y.example.com", 3, false),
+                route("c", "x.example.com", 2, true),
+            ],
+        };
+        let ids = |list: &RouteList| {
+            list.routes
+                .iter()
+                .map(|r| r.id.to_string())
+                .collect::<Vec<_>>()
+        };
+
+        list.sort(RouteSort::MaxCopies);
+        assert_eq!(vec!["b", "c", "a"], ids(&list));
+        list.sort(RouteSort::Host);
+        assert_eq!(vec!["c", "a", "b"], ids(&list));
+
+        let filters: Vec<RouteFilter> = ["active=true", "protocol=packet_router"]
+            .iter()
+            .map(|filter| filter.parse().unwrap())
+            .collect();
+        list.retain(&filters);
+        list.sort(RouteSort::Id);
+        assert_eq!(vec!["b", "c"], ids(&list));
+
+        assert!("active".parse::<RouteFilter>().is_err());
+        assert!("active=yes".parse::<RouteFilter>().is_err());
+        assert!("color=red".parse::<RouteFilter>().is_err());
+        assert!("protocol=ftp".parse::<RouteFilter>().is_err());
+    }
+
+    #[test]
+    fn envelope_output() {
+        let msg = Msg::Success(
+            "warning: port 1700 is also the server port\ncreated route one\n{\"id\": \"one\"}"
+                .to_string(),
+        );
+        let val: serde_json::Value =
+            serde_json::from_str(&msg.envelope("route new").unwrap()).unwrap();
+        assert_eq!(
+            serde_json::json!({
+                "status": "success",
+                "command": "route new",
+                "data": {"id": "one"},
+                "warnings": ["port 1700 is also the server port"],
+            }),
+            val
+        );
+
+        let msg = Msg::DryRun("delete route one".to_string());
+        let val: serde_json::Value =
+            serde_json::from_str(&msg.envelope("route delete").unwrap()).unwrap();
+        assert_eq!("dry_run", val["status"]);
+        assert_eq!("delete route one", val["data"]);
+        assert_eq!(serde_json::json!([]), val["warnings"]);
+    }
+
+    #[test]
+    fn quiet_output() {
+        let created = Msg::Success("created route one\n{\"id\": \"one\", \"oui\": 1}".to_string());
+        assert_eq!(Some("one".to_string()), created.quiet());
+
+        let orgs = Msg::Success(r#"{"orgs": [{"oui": 1}, {"oui": 2}]}"#.to_string());
+        assert_eq!(Some("1\n2".to_string()), orgs.quiet());
+
+        let org = Msg::Success(r#"{"org": {"oui": 3}, "devaddr_constraints": []}"#.to_string());
+        assert_eq!(Some("3".to_string()), org.quiet());
+
+        assert_eq!(None, Msg::Success("deleted route one".to_string()).quiet());
+        assert_eq!(None, Msg::DryRun(r#"{"id": "one"}"#.to_string()).quiet());
+    }
+
+    #[test]
+    #[cfg(feature = "client")]
+    fn exit_codes() {
+        use crate::ExitCode;
+        use anyhow::{anyhow, Error};
+        use tonic::Status;
+        let code = |status: Status| ExitCode::from_error(&Error::from(status));
+
+        assert_eq!(ExitCode::NotFound, code(Status::not_found("route")));
+        assert_eq!(
+            ExitCode::Unauthorized,
+            code(Status::permission_denied("signature"))
+        );
+        assert_eq!(
+            ExitCode::Invalid,
+            code(Status::invalid_argument("max_copies"))
+        );
+        assert_eq!(ExitCode::Failure, code(Status::unavailable("down")));
+        assert_eq!(ExitCode::Failure, ExitCode::from_error(&anyhow!("io")));
+
+        let err = Error::from(Status::not_found("route")).context("could not get route");
+        assert_eq!(ExitCode::NotFound, ExitCode::from_error(&err));
+        let msg = Msg::failed("could not get route", err).unwrap();
+        assert_eq!(ExitCode::NotFound, msg.exit_code());
+    }
+
+    #[test]
+    fn proto_names() {
+        let msg = Msg::ok(
+            r#"{"route_id": "id", "server": {"protocol": {"type": "gwmp", "mapping": {"AS923_1": 1700}}}, "max_copies": 1}"#
+                .to_string(),
+        )
+        .unwrap()
+        .with_proto_names()
+        .unwrap();
+        let value: serde_json::Value = serde_json::from_str(&msg.into_inner()).unwrap();
+        assert_eq!(
+            serde_json::json!({
+                "routeId": "id",
+                "server": {"protocol": {"type": "gwmp", "mapping": {"AS923_1": 1700}}},
+                "maxCopies": 1
+            }),
+            value
+        );
+
+        let plain = Msg::plain("not json".to_string()).unwrap();
+        assert_eq!("not json", plain.with_proto_names().unwrap().into_inner());
+    }
+
+    #[test]
+    fn add_report() {
+        let (missing, present) = split_present(vec![1, 2, 3, 3, 4], vec![2, 5]);
+        assert_eq!(vec![1, 3, 4], missing);
+        assert_eq!(vec![2, 3], present);
+        assert_eq!(
+            vec![0, 2, 4],
+            missing_positions(&[1, 2, 3, 3, 4], vec![2, 5])
+        );
+        assert_eq!(
+            "entries: 6, to-add: 3, already-present: 1, duplicates: 2",
+            ImportDiff::new(&[1, 2, 3, 3, 4, 2], &[0, 2, 4]).to_string()
+        );
+
+        let report = AddReport::new(missing.len(), present.len());
+        assert_eq!(
+            "added: 3, already-present: 2, duplicates: 0, failed: 0",
+            report.to_string()
+        );
+        assert_eq!(
+            "added: 0, already-present: 2, duplicates: 1, failed: 3",
+            report.with_duplicates(1).failed().to_string()
+        );
+        assert_eq!((vec![1, 2, 3], 2), dedup_entries(vec![1, 2, 1, 3, 2]));
+    }
+
+    #[test]
+    fn sync_delta_from_csv() {
+        let route_id = RouteId::new_unchecked("route-id");
+        let csv = "app_eui,dev_eui\n# comment\n\n0000000000000001, 0000000000000002\n0000000000000003,0000000000000004\n";
+        let desired = Eui::from_csv(&route_id, csv).unwrap();
+        assert_eq!(
+            vec![
+                Eui::new(route_id.clone(), hex_field::eui(1), hex_field::eui(2)).unwrap(),
+                Eui::new(route_id.clone(), hex_field::eui(3), hex_field::eui(4)).unwrap(),
+            ],
+            desired
+        );
+
+        let existing = vec![
+            Eui::new(route_id.clone(), hex_field::eui(3), hex_field::eui(4)).unwrap(),
+            Eui::new(route_id.clone(), hex_field::eui(5), hex_field::eui(6)).unwrap(),
+        ];
+        let (to_add, to_remove) = sync_delta(desired.clone(), existing.clone());
+        assert_eq!(vec![desired[0].clone()], to_add);
+        assert_eq!(vec![existing[1].clone()], to_remove);
+
+        let err = Eui::from_csv(&route_id, "0000000000000001\n").unwrap_err();
+        assert!(err.to_string().starts_with("line 1:"));
+    }
+
+    #[test]
+    fn euis_from_json() {
+        let route_id = RouteId::new_unchecked("route-id");
+        let json = r#"[
+            {"app_eui": "0000000000000001", "dev_eui": "0000000000000002"},
+            {"route_id": "other", "app_eui": "0000000000000003", "dev_eui": "0000000000000004"}
+        ]"#;
+        assert_eq!(
+            vec![
+                Eui::new(route_id.clone(), hex_field::eui(1), hex_field::eui(2)).unwrap(),
+                Eui::new(route_id.clone(), hex_field::eui(3), hex_field::eui(4)).unwrap(),
+            ],
+            Eui::from_json(&route_id, json).unwrap()
+        );
+        assert!(Eui::from_json(&route_id, r#"[{"app_eui": "01"}]"#).is_err());
+    }
+
+    #[test]
+    fn eui_wildcards() {
+        let route_id = RouteId::new_unchecked("route-id");
+        let csv = "app_eui,dev_eui\n\
+                   0000000000000001,*\n\
+                   0000000000000001,0000000000000002\n\
+                   0000000000000001,0000000000000003\n\
+                   0000000000000004,0000000000000005\n\
+                   0000000000000006,0000000000000000\n\
+                   0000000000000001,0000000000000003\n";
+        let euis = Eui::from_csv(&route_id, csv).unwrap();
+        assert_eq!(Eui::wildcard(route_id.clone(), hex_field::eui(1)), euis[0]);
+        assert!(euis[4].is_wildcard());
+        assert!(!euis[1].is_wildcard());
+        assert_eq!(vec![(hex_field::eui(1), 2)], wildcard_overlaps(&euis));
+    }
+
+    #[test]
+    fn import_errors_listed() {
+        let route_id = RouteId::new_unchecked("route-id");
+        let csv = "app_eui,dev_eui\n\
+                   0000000000000001,0000000000000002\n\
+                   zz,0000000000000002\n\
+                   0000000000000001\n";
+        let err = Eui::from_csv(&route_id, csv).unwrap_err().to_string();
+        assert!(err.starts_with("invalid entries: 2 of 3\nline 3: "));
+        assert!(err.ends_with("\nline 4: expected <app_eui>,<dev_eui>"));
+
+        let filters = r#"[
+            {"devaddr": "48000001", "session_key": "0011"},
+            {"devaddr": "48000002", "session_key": "00112233445566778899aabbccddeeff"},
+            {"devaddr": "zz", "session_key": "00112233445566778899aabbccddeeff"}
+        ]"#;
+        let err = ExportedFilter::from_json(filters).unwrap_err().to_string();
+        assert!(err.starts_with(
+            "invalid entries: 2 of 3\nentry 0: session key 0011 is not 32 hex characters\nentry 2: "
+        ));
+
+        let err = collect_all((0..60).map(|i| Err::<(), _>(anyhow!("bad {i}"))))
+            .unwrap_err()
+            .to_string();
+        assert!(err.ends_with("\nbad 49\nand 10 more"));
+    }
+
+    #[test]
+    fn devaddr_ranges_from_json() {
+        let route_id = RouteId::new_unchecked("route-id");
+        let json = r#"[
+            {"start_addr": "48000000", "end_addr": "480000FF"},
+            {"route_id": "other", "start_addr": "48000100", "end_addr": "480001FF"}
+        ]"#;
+        assert_eq!(
+            vec![
+                DevaddrRange::new(
+                    route_id.clone(),
+                    hex_field::devaddr(0x48000000),
+                    hex_field::devaddr(0x480000FF)
+                )
+                .unwrap(),
+                DevaddrRange::new(
+                    route_id.clone(),
+                    hex_field::devaddr(0x48000100),
+                    hex_field::devaddr(0x480001FF)
+                )
+                .unwrap(),
+            ],
+            DevaddrRange::from_json(&route_id, json).unwrap()
+        );
+
+        let reversed = r#"[{"start_addr": "480000FF", "end_addr": "48000000"}]"#;
+        assert!(DevaddrRange::from_json(&route_id, reversed).is_err());
+    }
 }
@@ -1,29 +1,122 @@
-use clap::Parser;
+use clap::{parser::ValueSource, ArgMatches, CommandFactory, FromArgMatches};
 use helium_config_service_cli::{
     cmds::{
-        self, env, org, region_params,
-        route::{self, devaddrs, euis},
-        session_key_filter as skf, Cli, Commands, EnvCommands as Env, OrgCommands as Org,
-        RegionParamsCommands, RouteCommands, RouteUpdateCommand,
+        self, apply, doctor, env, import, org, region, region_params,
+        route::{self, bundle, devaddrs, euis},
+        server_info, session_key_filter as skf, Cli, Commands, EnvCommands as Env,
+        OrgCommands as Org, RegionCommands, RegionParamsCommands, RouteCommands,
+        RouteUpdateCommand,
     },
-    Msg, Result,
+    output, progress,
+    render::OutputFormat,
+    ExitCode, Msg, Result,
 };
+use std::io::Write;
 
 #[tokio::main]
 async fn main() -> Result {
-    let cli = Cli::parse();
+    let matches = Cli::command().get_matches();
+    let mut cli = Cli::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+    if let Err(err) = cmds::check_route_ids(&matches, cli.allow_any_id) {
+        Cli::command()
+            .error(clap::error::ErrorKind::ValueValidation, err)
+            .exit();
+    }
+    let porcelain = cli.porcelain;
+    let quiet = cli.quiet;
+    let (command, leaf) = command_name(&matches);
+    // Only an explicit `--format json` wraps the output in an envelope, JSON
+    // is also the default.
+    let envelope = !porcelain
+        && cli.format == OutputFormat::Json
+        && leaf.value_source("format") == Some(ValueSource::CommandLine);
+    if porcelain {
+        cli.format = OutputFormat::Json;
+    }
+    let proto_names = cli.proto_names;
+    let format = cli.format;
+    if let Err(err) = output::set_out_file(cli.out.clone(), cli.force) {
+        Cli::command()
+            .error(clap::error::ErrorKind::Io, format!("{err:#}"))
+            .exit();
+    }
+    output::set_hex_format(cli.hex_case, cli.hex_style);
+    output::set_streaming(!porcelain && !envelope && !quiet && !proto_names);
+    progress::set_enabled(!porcelain && !quiet);
 
-    let msg = handle_cli(cli).await?;
-    println!("{msg}");
+    let result = match handle_cli(cli).await {
+        Ok(msg) if proto_names => msg.with_proto_names(),
+        result => result,
+    };
+    let result = match result {
+        Ok(msg) if output::hex_format().is_some() => msg.with_hex_format(),
+        result => result,
+    };
+    let result = match result {
+        Ok(msg) if !porcelain && !quiet => msg.with_format(format),
+        result => result,
+    };
+    let msg = match result {
+        Ok(msg) => msg,
+        Err(err) if porcelain => {
+            let code = ExitCode::from_error(&err);
+            println!("{}", Msg::Error(err.to_string(), code).porcelain()?);
+            code.exit();
+        }
+        Err(err) if envelope => {
+            let code = ExitCode::from_error(&err);
+            println!("{}", Msg::Error(err.to_string(), code).envelope(&command)?);
+            code.exit();
+        }
+        Err(err) => {
+            eprintln!("Error: {err:?}");
+            ExitCode::from_error(&err).exit();
+        }
+    };
+    // Errors stay on the terminal with `--out`.
+    let out = match &msg {
+        _ if porcelain => Some(msg.porcelain()?),
+        _ if envelope => Some(msg.envelope(&command)?),
+        Msg::Error(err, _) if quiet => {
+            eprintln!("{err}");
+            None
+        }
+        _ if quiet => msg.quiet(),
+        Msg::Error(..) => {
+            println!("{msg}");
+            None
+        }
+        // Output that was already written while streaming.
+        Msg::Plain(msg) if msg.is_empty() => None,
+        _ => Some(msg.to_string()),
+    };
+    if let Some(out) = out {
+        writeln!(output::open()?, "{out}")?;
+    }
+    if msg.exit_code() != ExitCode::Success {
+        msg.exit_code().exit();
+    }
 
     Ok(())
 }
 
+/// Names of the subcommands given, `route update http`, and the matches of
+/// the last one.
+fn command_name(matches: &ArgMatches) -> (String, &ArgMatches) {
+    let mut names = vec![];
+    let mut leaf = matches;
+    while let Some((name, sub)) = leaf.subcommand() {
+        names.push(name);
+        leaf = sub;
+    }
+    (names.join(" "), leaf)
+}
+
 pub async fn handle_cli(cli: Cli) -> Result<Msg> {
     match cli.command {
         Commands::Env { command } => match command {
             Env::Init => env::env_init().await,
-            Env::Info(args) => env::env_info(args),
+            Env::Info(args) => env::env_info(args).await,
             Env::GenerateKeypair(args) => env::generate_keypair(args),
         },
         Commands::Route { command } => match command {
@@ -37,27 +130,48 @@ pub async fn handle_cli(cli: Cli) -> Result<Msg> {
                 RouteUpdateCommand::Http(args) => route::update_http(args).await,
                 RouteUpdateCommand::AddGwmpRegion(args) => route::add_gwmp_region(args).await,
                 RouteUpdateCommand::RemoveGwmpRegion(args) => route::remove_gwmp_region(args).await,
+                RouteUpdateCommand::Gwmp(args) => route::update_gwmp(args).await,
                 RouteUpdateCommand::PacketRouter(args) => route::update_packet_router(args).await,
             },
             RouteCommands::Euis { command } => match command {
                 cmds::EuiCommands::List(args) => euis::list_euis(args).await,
                 cmds::EuiCommands::Add(args) => euis::add_eui(args).await,
                 cmds::EuiCommands::Remove(args) => euis::remove_eui(args).await,
+                cmds::EuiCommands::Move(args) => euis::move_euis(args).await,
                 cmds::EuiCommands::Clear(args) => euis::clear_euis(args).await,
+                cmds::EuiCommands::Sync(args) => euis::sync_euis(args).await,
+                cmds::EuiCommands::Export(args) => euis::export_euis(args).await,
             },
             RouteCommands::Devaddrs { command } => match command {
                 cmds::DevaddrCommands::List(args) => devaddrs::list_devaddrs(args).await,
                 cmds::DevaddrCommands::Add(args) => devaddrs::add_devaddr(args).await,
                 cmds::DevaddrCommands::Remove(args) => devaddrs::remove_devaddr(args).await,
+                cmds::DevaddrCommands::Move(args) => devaddrs::move_devaddr(args).await,
                 cmds::DevaddrCommands::SubnetMask(args) => devaddrs::subnet_mask(args).await,
                 cmds::DevaddrCommands::Clear(args) => devaddrs::clear_devaddrs(args).await,
+                cmds::DevaddrCommands::Sync(args) => devaddrs::sync_devaddrs(args).await,
+                cmds::DevaddrCommands::Map(args) => devaddrs::devaddr_map(args).await,
+                cmds::DevaddrCommands::Split(args) => devaddrs::split_devaddrs(args).await,
             },
             RouteCommands::Activate(args) => route::activate_route(args).await,
             RouteCommands::Deactivate(args) => route::deactivate_route(args).await,
+            RouteCommands::Schema => route::route_schema(),
+            RouteCommands::Export(args) => route::export_route(args).await,
+            RouteCommands::Import(args) => route::import_route(args).await,
+            RouteCommands::Diff(args) => route::diff_route(args).await,
+            RouteCommands::Sync(args) => route::sync_routes(args).await,
+            RouteCommands::Migrate(args) => route::migrate_route(args).await,
+            RouteCommands::PushAll(args) => route::push_all_routes(args).await,
+            RouteCommands::Bundle { command } => match command {
+                cmds::BundleCommands::Export(args) => bundle::export_bundle(args).await,
+                cmds::BundleCommands::Import(args) => bundle::import_bundle(args).await,
+            },
         },
         Commands::Org { command } => match command {
             Org::List(args) => org::list_orgs(args).await,
             Org::Get(args) => org::get_org(args).await,
+            Org::Report(args) => org::org_report(args).await,
+            Org::Restore(args) => org::restore_org(args).await,
             Org::CreateHelium(args) => org::create_helium_org(args).await,
             Org::CreateRoaming(args) => org::create_roaming_org(args).await,
         },
@@ -66,10 +180,32 @@ pub async fn handle_cli(cli: Cli) -> Result<Msg> {
             cmds::SessionKeyFilterCommands::Get(args) => skf::get_filters(args).await,
             cmds::SessionKeyFilterCommands::Add(args) => skf::add_filter(args).await,
             cmds::SessionKeyFilterCommands::Remove(args) => skf::remove_filter(args).await,
+            cmds::SessionKeyFilterCommands::RemoveRange(args) => {
+                skf::remove_filter_range(args).await
+            }
+            cmds::SessionKeyFilterCommands::Replace(args) => skf::replace_filter(args).await,
+            cmds::SessionKeyFilterCommands::Export(args) => skf::export_filters(args).await,
+            cmds::SessionKeyFilterCommands::Import(args) => skf::import_filters(args).await,
         },
         Commands::SubnetMask(args) => cmds::subnet_mask(args),
+        Commands::Subnet { command } => match command {
+            cmds::SubnetCommands::Split(args) => cmds::subnet_split(args),
+        },
+        Commands::Region { command } => match command {
+            RegionCommands::List(args) => region::list_regions(args),
+        },
         Commands::RegionParams { command } => match command {
             RegionParamsCommands::Push(args) => region_params::push_params(args).await,
+            RegionParamsCommands::Generate(args) => region_params::generate_params(args),
+            RegionParamsCommands::Prepare(args) => region_params::prepare_params(args),
+            RegionParamsCommands::Submit(args) => region_params::submit_params(args).await,
+        },
+        Commands::Doctor(args) => doctor::doctor(args).await,
+        Commands::ServerInfo(args) => server_info::server_info(args).await,
+        Commands::Apply(args) => apply::apply(args).await,
+        Commands::Import { command } => match command {
+            cmds::ImportCommands::Chirpstack(args) => import::import_chirpstack(args).await,
+            cmds::ImportCommands::Tts(args) => import::import_tts(args).await,
         },
     }
 }
@@ -0,0 +1,139 @@
+//! Where command output goes, stdout or the file given with `--out`.
+use crate::{
+    hex_field::{self, HexCase, HexField, HexStyle},
+    Result,
+};
+use anyhow::{anyhow, Context};
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, IsTerminal, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+};
+
+static OUT_FILE: Mutex<Option<File>> = Mutex::new(None);
+static STREAMING: AtomicBool = AtomicBool::new(true);
+static HEX_FORMAT: Mutex<(HexCase, HexStyle)> = Mutex::new((HexCase::Upper, HexStyle::Plain));
+
+/// Send output to `path` instead of stdout, replacing an existing file only
+/// with `force`. The file is created, with its parent directories, before
+/// the command runs so a path that can't be written fails without sending
+/// anything to the server.
+pub fn set_out_file(path: Option<PathBuf>, force: bool) -> Result<()> {
+    let file = path.map(|path| create(&path, force)).transpose()?;
+    *OUT_FILE.lock().unwrap_or_else(|err| err.into_inner()) = file;
+    Ok(())
+}
+
+fn create(path: &Path, force: bool) -> Result<File> {
+    if let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("creating directory {}", parent.display()))?;
+    }
+    OpenOptions::new()
+        .write(true)
+        .create(true)
+        .create_new(!force)
+        .truncate(true)
+        .open(path)
+        .map_err(|err| match err.kind() {
+            io::ErrorKind::AlreadyExists => {
+                anyhow!(
+                    "{} already exists, pass `--force` to overwrite it",
+                    path.display()
+                )
+            }
+            _ => anyhow::Error::from(err).context(format!("creating {}", path.display())),
+        })
+}
+
+/// Let list commands write to an `--out` file as the entries arrive. Off
+/// for `--porcelain`, `--quiet` and the other outputs that need all of the
+/// output before printing any.
+pub fn set_streaming(enabled: bool) {
+    STREAMING.store(enabled, Ordering::Relaxed);
+}
+
+/// Entries of a list can be written as they arrive. Otherwise the command
+/// returns all of them, for `--porcelain` and the others to wrap.
+pub fn streams() -> bool {
+    STREAMING.load(Ordering::Relaxed)
+}
+
+/// Output goes to an `--out` file, and entries can be written to it as they
+/// arrive rather than once all of them have.
+pub fn streams_to_file() -> bool {
+    streams() && OUT_FILE.lock().map_or(false, |out_file| out_file.is_some())
+}
+
+/// Write the hex fields of output in `case` and `style`.
+pub fn set_hex_format(case: HexCase, style: HexStyle) {
+    *HEX_FORMAT.lock().unwrap_or_else(|err| err.into_inner()) = (case, style);
+}
+
+/// The `--hex-case` and `--hex-style` of the output, `None` when hex fields
+/// are printed as they serialize, plain uppercase.
+pub fn hex_format() -> Option<(HexCase, HexStyle)> {
+    let format = *HEX_FORMAT.lock().unwrap_or_else(|err| err.into_inner());
+    (format != (HexCase::Upper, HexStyle::Plain)).then_some(format)
+}
+
+/// `field` as it is printed in output.
+pub fn hex<const WIDTH: usize>(field: HexField<WIDTH>) -> String {
+    let (case, style) = hex_format().unwrap_or_default();
+    field.to_string_with_style(case, style)
+}
+
+/// `value` with its hex fields as they are printed in output.
+pub fn restyle_hex(mut value: serde_json::Value) -> serde_json::Value {
+    if let Some((case, style)) = hex_format() {
+        hex_field::restyle(&mut value, case, style);
+    }
+    value
+}
+
+/// Output for people, stdout on a terminal and not redirected with `--out`.
+pub fn is_terminal() -> bool {
+    OUT_FILE.lock().map_or(false, |out_file| out_file.is_none()) && io::stdout().is_terminal()
+}
+
+/// Open the output, stdout or the `--out` file opened by [`set_out_file`].
+/// Writes to the file follow each other.
+pub fn open() -> Result<Box<dyn Write>> {
+    let out_file = OUT_FILE.lock().unwrap_or_else(|err| err.into_inner());
+    match out_file.as_ref() {
+        Some(file) => Ok(Box::new(file.try_clone()?)),
+        None => Ok(Box::new(io::stdout())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{open, set_out_file};
+    use std::io::Write;
+    use temp_dir::TempDir;
+
+    #[test]
+    fn out_file() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.child("nested/dir/routes.json");
+
+        set_out_file(Some(path.clone()), false).unwrap();
+        write!(open().unwrap(), "first").unwrap();
+        writeln!(open().unwrap(), " second").unwrap();
+        assert_eq!("first second\n", std::fs::read_to_string(&path).unwrap());
+        assert!(set_out_file(Some(path.clone()), false).is_err());
+
+        set_out_file(Some(path.clone()), true).unwrap();
+        writeln!(open().unwrap(), "third").unwrap();
+        assert_eq!("third\n", std::fs::read_to_string(&path).unwrap());
+
+        set_out_file(None, false).unwrap();
+    }
+}
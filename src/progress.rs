@@ -0,0 +1,58 @@
+//! Progress bars on stderr for requests that stream many entries.
+use indicatif::{ProgressBar, ProgressStyle};
+use std::{
+    io::{self, IsTerminal},
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+static ENABLED: AtomicBool = AtomicBool::new(true);
+
+/// Turn progress bars and retry warnings off, for `--quiet` and
+/// `--porcelain`.
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether progress, bars and retry warnings, goes to stderr.
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// A bar counting `what` with the rate, against `len` when the total is
+/// known. Hidden when disabled or stderr is not a terminal.
+pub fn bar(what: &'static str, len: Option<u64>) -> ProgressBar {
+    if !enabled() || !io::stderr().is_terminal() {
+        return ProgressBar::hidden();
+    }
+    let bar = match len {
+        Some(len) => ProgressBar::new(len).with_style(
+            ProgressStyle::with_template("{msg} [{bar:40}] {pos}/{len} ({per_sec}, {elapsed})")
+                .expect("valid progress template")
+                .progress_chars("=> "),
+        ),
+        None => {
+            let bar = ProgressBar::new_spinner().with_style(
+                ProgressStyle::with_template("{spinner} {msg} {pos} ({per_sec}, {elapsed})")
+                    .expect("valid progress template"),
+            );
+            // Keep spinning while the first entries are on their way.
+            bar.enable_steady_tick(Duration::from_millis(100));
+            bar
+        }
+    };
+    bar.with_message(what)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{bar, set_enabled};
+
+    #[test]
+    fn disabled_progress() {
+        set_enabled(false);
+        assert!(bar("euis", Some(10)).is_hidden());
+        assert!(bar("euis", None).is_hidden());
+        set_enabled(true);
+    }
+}
@@ -43,6 +43,47 @@ impl Region {
             .map(|r| r.into())
             .ok_or_else(|| anyhow!("unsupported region {v}"))
     }
+
+    /// Short description of the band a region typically operates in.
+    pub fn frequency_plan(&self) -> &'static str {
+        match self {
+            Region::Us915 => "902-928 MHz",
+            Region::Eu868 => "863-870 MHz",
+            Region::Eu433 => "433.05-434.79 MHz",
+            Region::Cn470 => "470-510 MHz",
+            Region::Cn779 => "779-787 MHz",
+            Region::Au915 => "915-928 MHz",
+            Region::As923_1 => "915-928 MHz, AS923 no offset",
+            Region::As923_1b => "915-928 MHz, AS923-1 variant B",
+            Region::As923_2 => "920-923 MHz, AS923 offset -1.8 MHz",
+            Region::As923_3 => "915-921 MHz, AS923 offset -6.6 MHz",
+            Region::As923_4 => "917-920 MHz, AS923 offset -5.9 MHz",
+            Region::Kr920 => "920.9-923.3 MHz",
+            Region::In865 => "865-867 MHz",
+            Region::Cd900_1a => "900 MHz band, CD900-1A",
+            Region::Ru864 => "864-870 MHz",
+            Region::Eu868A => "863-870 MHz, EU868 variant A",
+            Region::Eu868B => "863-870 MHz, EU868 variant B",
+            Region::Eu868C => "863-870 MHz, EU868 variant C",
+            Region::Eu868D => "863-870 MHz, EU868 variant D",
+            Region::Eu868E => "863-870 MHz, EU868 variant E",
+            Region::Eu868F => "863-870 MHz, EU868 variant F",
+            Region::Au915Sb1 => "915-928 MHz, AU915 sub-band 1",
+            Region::Au915Sb2 => "915-928 MHz, AU915 sub-band 2",
+            Region::As9231a => "915-928 MHz, AS923-1 variant A",
+            Region::As9231c => "915-928 MHz, AS923-1 variant C",
+            Region::As9231d => "915-928 MHz, AS923-1 variant D",
+            Region::As9231e => "915-928 MHz, AS923-1 variant E",
+            Region::As9231f => "915-928 MHz, AS923-1 variant F",
+        }
+    }
+}
+
+/// Same as the serialized region, `US915`.
+impl fmt::Display for Region {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", ProtoRegion::from(self))
+    }
 }
 
 impl Serialize for Region {
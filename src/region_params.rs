@@ -1,4 +1,4 @@
-use crate::Result;
+use crate::{region::Region, Result};
 use anyhow::{anyhow, Context};
 use serde::{de, Deserialize, Deserializer, Serialize};
 use std::{fmt, fs, path::PathBuf, str::FromStr};
@@ -52,6 +52,193 @@ impl RegionParams {
             .context(format!("parsing params file {}", path.display()))?;
         Ok(listing)
     }
+
+    /// Build the 125 kHz uplink channel plan for a region.
+    ///
+    /// `sub_band` (1-8) selects which 8 channels to use in regions with
+    /// 64 uplink channels, defaulting to sub-band 2. `max_eirp` is in dBm and
+    /// defaults to the regional limit. Max packet sizes are the LoRaWAN
+    /// regional parameter application payload limits (N).
+    pub fn generate(region: &Region, sub_band: Option<u8>, max_eirp: Option<f32>) -> Result<Self> {
+        use RegionSpreading::*;
+
+        let (frequencies, default_eirp, spreading) = match region {
+            Region::Us915 => (
+                sub_band_frequencies(902_300_000, sub_band.unwrap_or(2))?,
+                36.0,
+                vec![(Sf10, 11), (Sf9, 53), (Sf8, 125), (Sf7, 242)],
+            ),
+            Region::Au915 => (
+                sub_band_frequencies(915_200_000, sub_band.unwrap_or(2))?,
+                30.0,
+                vec![
+                    (Sf12, 51),
+                    (Sf11, 51),
+                    (Sf10, 51),
+                    (Sf9, 115),
+                    (Sf8, 242),
+                    (Sf7, 242),
+                ],
+            ),
+            Region::Eu868 => {
+                if sub_band.is_some() {
+                    return Err(anyhow!("EU868 does not have sub-bands"));
+                }
+                (
+                    vec![
+                        868_100_000,
+                        868_300_000,
+                        868_500_000,
+                        867_100_000,
+                        867_300_000,
+                        867_500_000,
+                        867_700_000,
+                        867_900_000,
+                    ],
+                    16.0,
+                    vec![
+                        (Sf12, 51),
+                        (Sf11, 51),
+                        (Sf10, 51),
+                        (Sf9, 115),
+                        (Sf8, 242),
+                        (Sf7, 242),
+                    ],
+                )
+            }
+            other => {
+                return Err(anyhow!(
+                "cannot generate params for {other:?}, supported regions are us915, au915, eu868"
+            ))
+            }
+        };
+
+        let max_eirp = max_eirp.unwrap_or(default_eirp);
+        if max_eirp <= 0.0 {
+            return Err(anyhow!("max eirp must be positive, got {max_eirp}"));
+        }
+
+        let spreading = BlockchainRegionSpreading {
+            tagged_spreading: spreading
+                .into_iter()
+                .map(|(region_spreading, max_packet_size)| TaggedSpreading {
+                    region_spreading,
+                    max_packet_size,
+                })
+                .collect(),
+        };
+
+        Ok(Self {
+            region_params: frequencies
+                .into_iter()
+                .map(|channel_frequency| RegionParam {
+                    channel_frequency,
+                    bandwidth: 125_000,
+                    // params are stored in deci-dBm
+                    max_eirp: (max_eirp * 10.0).round() as u32,
+                    spreading: spreading.clone(),
+                })
+                .collect(),
+        })
+    }
+}
+
+/// Regulatory limits used to sanity check region params before pushing.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RegulatoryLimits {
+    pub max_eirp_dbm: f32,
+    pub min_frequency: u64,
+    pub max_frequency: u64,
+}
+
+impl RegulatoryLimits {
+    /// Limits for the band a region belongs to, regional variants share the
+    /// limits of their parent region. `None` when the limits are not known.
+    pub fn for_region(region: &Region) -> Option<Self> {
+        let (max_eirp_dbm, min_frequency, max_frequency) = match region {
+            Region::Us915 => (36.0, 902_000_000, 928_000_000),
+            Region::Au915 | Region::Au915Sb1 | Region::Au915Sb2 => (30.0, 915_000_000, 928_000_000),
+            Region::Eu868
+            | Region::Eu868A
+            | Region::Eu868B
+            | Region::Eu868C
+            | Region::Eu868D
+            | Region::Eu868E
+            | Region::Eu868F => (16.0, 863_000_000, 870_000_000),
+            Region::Eu433 => (12.15, 433_050_000, 434_790_000),
+            Region::Cn470 => (19.15, 470_000_000, 510_000_000),
+            Region::Cn779 => (12.15, 779_000_000, 787_000_000),
+            Region::As923_1
+            | Region::As923_1b
+            | Region::As923_2
+            | Region::As923_3
+            | Region::As923_4
+            | Region::As9231a
+            | Region::As9231c
+            | Region::As9231d
+            | Region::As9231e
+            | Region::As9231f => (16.0, 915_000_000, 928_000_000),
+            Region::Kr920 => (14.0, 920_900_000, 923_300_000),
+            Region::In865 => (30.0, 865_000_000, 867_000_000),
+            Region::Ru864 => (16.0, 864_000_000, 870_000_000),
+            Region::Cd900_1a => return None,
+        };
+        Some(Self {
+            max_eirp_dbm,
+            min_frequency,
+            max_frequency,
+        })
+    }
+}
+
+impl RegionParams {
+    /// Check every channel against the regulatory limits of `region`.
+    ///
+    /// Region params do not carry duty-cycle information, so only the max
+    /// EIRP and channel edges are checked. Returns a description of each
+    /// violation found.
+    pub fn compliance_violations(&self, region: &Region) -> Vec<String> {
+        let limits = match RegulatoryLimits::for_region(region) {
+            Some(limits) => limits,
+            None => return vec![],
+        };
+        let max_eirp = (limits.max_eirp_dbm * 10.0).round() as u32;
+
+        let mut violations = vec![];
+        for param in self.region_params.iter() {
+            let half_bandwidth = param.bandwidth as u64 / 2;
+            let low = param.channel_frequency.saturating_sub(half_bandwidth);
+            let high = param.channel_frequency + half_bandwidth;
+            if low < limits.min_frequency || high > limits.max_frequency {
+                violations.push(format!(
+                    "channel {} Hz ({} Hz wide) is outside {}-{} Hz",
+                    param.channel_frequency,
+                    param.bandwidth,
+                    limits.min_frequency,
+                    limits.max_frequency
+                ));
+            }
+            if param.max_eirp > max_eirp {
+                violations.push(format!(
+                    "channel {} Hz max_eirp {} exceeds the {} limit of {max_eirp}",
+                    param.channel_frequency,
+                    param.max_eirp,
+                    helium_proto::Region::from(region)
+                ));
+            }
+        }
+        violations
+    }
+}
+
+fn sub_band_frequencies(first_channel: u64, sub_band: u8) -> Result<Vec<u64>> {
+    if !(1..=8).contains(&sub_band) {
+        return Err(anyhow!("sub-band must be between 1 and 8, got {sub_band}"));
+    }
+    let first = (sub_band as u64 - 1) * 8;
+    Ok((first..first + 8)
+        .map(|channel| first_channel + channel * 200_000)
+        .collect())
 }
 
 impl From<RegionParams> for proto::BlockchainRegionParamsV1 {
@@ -221,3 +408,61 @@ impl From<RegionSpreading> for i32 {
         proto::RegionSpreading::from(region) as i32
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::RegionParams;
+    use crate::region::Region;
+
+    #[test]
+    fn generate_us915_sub_band() {
+        let params = RegionParams::generate(&Region::Us915, Some(2), Some(36.0)).unwrap();
+        let frequencies: Vec<u64> = params
+            .region_params
+            .iter()
+            .map(|p| p.channel_frequency)
+            .collect();
+        assert_eq!(
+            vec![
+                903_900_000,
+                904_100_000,
+                904_300_000,
+                904_500_000,
+                904_700_000,
+                904_900_000,
+                905_100_000,
+                905_300_000
+            ],
+            frequencies
+        );
+        assert!(params.region_params.iter().all(|p| p.max_eirp == 360));
+        assert!(params.region_params.iter().all(|p| p.bandwidth == 125_000));
+    }
+
+    #[test]
+    fn generate_defaults_and_errors() {
+        let params = RegionParams::generate(&Region::Eu868, None, None).unwrap();
+        assert_eq!(8, params.region_params.len());
+        assert!(params.region_params.iter().all(|p| p.max_eirp == 160));
+
+        assert!(RegionParams::generate(&Region::Eu868, Some(1), None).is_err());
+        assert!(RegionParams::generate(&Region::Us915, Some(9), None).is_err());
+        assert!(RegionParams::generate(&Region::Us915, None, Some(0.0)).is_err());
+        assert!(RegionParams::generate(&Region::Cn779, None, None).is_err());
+    }
+
+    #[test]
+    fn compliance() {
+        let mut params = RegionParams::generate(&Region::Us915, Some(1), None).unwrap();
+        assert!(params.compliance_violations(&Region::Us915).is_empty());
+        // a us915 plan is out of band in eu868
+        assert_eq!(16, params.compliance_violations(&Region::Eu868).len());
+
+        params.region_params[0].max_eirp = 361;
+        params.region_params[1].channel_frequency = 927_950_000;
+        assert_eq!(2, params.compliance_violations(&Region::Us915).len());
+
+        // no known limits
+        assert!(params.compliance_violations(&Region::Cd900_1a).is_empty());
+    }
+}
@@ -0,0 +1,327 @@
+//! Rendering of JSON output as tables, CSV, YAML, or JSON Lines for
+//! `--format`.
+//!
+//! Commands produce JSON. A list, or an object holding a single list like
+//! `{"routes": [..]}`, becomes one row per entry. Any other object is a
+//! single row. Nested values are written as compact JSON in their cell.
+use crate::{output, Result};
+use anyhow::anyhow;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    #[default]
+    Json,
+    Table,
+    Csv,
+    Yaml,
+    /// One compact JSON value per line, an entry of a list per line.
+    Jsonl,
+    /// One <base>/<prefix> subnet per line, for the subnet mask commands.
+    Cidr,
+}
+
+/// Render JSON `value` in `format`.
+pub fn render(value: &Value, format: OutputFormat) -> Result<String> {
+    Ok(match format {
+        OutputFormat::Json => serde_json::to_string_pretty(value)?,
+        OutputFormat::Table => {
+            let (columns, rows) = tabulate(value);
+            table(&columns, &rows)
+        }
+        OutputFormat::Csv => {
+            let (columns, rows) = tabulate(value);
+            csv(&columns, &rows)
+        }
+        OutputFormat::Yaml => serde_yaml::to_string(value)?,
+        OutputFormat::Jsonl => entries(value)
+            .into_iter()
+            .map(serde_json::to_string)
+            .collect::<Result<Vec<_>, _>>()?
+            .join("\n"),
+        // Subnet commands print their own lines, nothing else has subnets.
+        OutputFormat::Cidr => {
+            return Err(anyhow!(
+                "--format cidr is only for `subnet-mask` and `subnet split`"
+            ))
+        }
+    })
+}
+
+/// Column names, in order of first appearance, and the cells of each row.
+pub fn tabulate(value: &Value) -> (Vec<String>, Vec<Vec<String>>) {
+    let entries: Vec<Map<String, Value>> = entries(value)
+        .into_iter()
+        .map(|entry| match entry {
+            Value::Object(map) => map.clone(),
+            other => Map::from_iter([("value".to_string(), other.clone())]),
+        })
+        .collect();
+
+    let mut columns: Vec<String> = vec![];
+    for entry in &entries {
+        for key in entry.keys() {
+            if !columns.contains(key) {
+                columns.push(key.clone());
+            }
+        }
+    }
+    let rows = entries
+        .iter()
+        .map(|entry| {
+            columns
+                .iter()
+                .map(|column| entry.get(column).map(cell).unwrap_or_default())
+                .collect()
+        })
+        .collect();
+    (columns, rows)
+}
+
+fn entries(value: &Value) -> Vec<&Value> {
+    match value {
+        Value::Array(values) => values.iter().collect(),
+        Value::Object(map) if map.len() == 1 => match map.values().next() {
+            Some(Value::Array(values)) => values.iter().collect(),
+            _ => vec![value],
+        },
+        _ => vec![value],
+    }
+}
+
+fn cell(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Keep only `fields` of each entry of `value`. A field can name a nested
+/// value with dots, `server.host`.
+///
+/// Fails on a field no entry has, as it is most likely a typo.
+pub fn select(value: &Value, fields: &[String]) -> Result<Value> {
+    let entries = entries(value);
+    let unknown = fields
+        .iter()
+        .find(|field| !entries.iter().any(|entry| lookup(entry, field).is_some()));
+    if let (Some(field), false) = (unknown, entries.is_empty()) {
+        return Err(anyhow!("unknown field {field:?}"));
+    }
+
+    let project = |entry: &Value| -> Value {
+        fields
+            .iter()
+            .filter_map(|field| Some((field.clone(), lookup(entry, field)?.clone())))
+            .collect::<Map<_, _>>()
+            .into()
+    };
+    Ok(match value {
+        Value::Array(values) => values.iter().map(project).collect(),
+        Value::Object(map) if map.len() == 1 => match map.iter().next() {
+            Some((key, Value::Array(values))) => Value::Object(Map::from_iter([(
+                key.clone(),
+                values.iter().map(project).collect(),
+            )])),
+            _ => project(value),
+        },
+        _ => project(value),
+    })
+}
+
+fn lookup<'a>(entry: &'a Value, field: &str) -> Option<&'a Value> {
+    field
+        .split('.')
+        .try_fold(entry, |value, key| value.get(key))
+}
+
+/// Columns padded to their widest cell, separated by two spaces.
+pub fn table(columns: &[String], rows: &[Vec<String>]) -> String {
+    let widths: Vec<usize> = columns
+        .iter()
+        .enumerate()
+        .map(|(index, column)| {
+            rows.iter()
+                .map(|row| row[index].chars().count())
+                .chain([column.chars().count()])
+                .max()
+                .unwrap_or_default()
+        })
+        .collect();
+    let line = |cells: &[String]| {
+        cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{cell:width$}"))
+            .collect::<Vec<_>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+    let header: Vec<String> = columns.iter().map(|c| c.to_uppercase()).collect();
+    std::iter::once(line(&header))
+        .chain(rows.iter().map(|row| line(row)))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// GitHub flavored Markdown table, `|` in cells escaped.
+pub fn markdown(columns: &[String], rows: &[Vec<String>]) -> String {
+    let line = |cells: &[String]| {
+        let cells: Vec<String> = cells.iter().map(|cell| cell.replace('|', "\\|")).collect();
+        format!("| {} |", cells.join(" | "))
+    };
+    let rule = format!("|{}|", vec!["---"; columns.len()].join("|"));
+    std::iter::once(line(columns))
+        .chain([rule])
+        .chain(rows.iter().map(|row| line(row)))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// RFC 4180 CSV with a header row.
+pub fn csv(columns: &[String], rows: &[Vec<String>]) -> String {
+    std::iter::once(columns)
+        .chain(rows.iter().map(Vec::as_slice))
+        .map(csv_row)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub fn csv_row(cells: &[String]) -> String {
+    cells
+        .iter()
+        .map(|cell| {
+            if cell.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", cell.replace('"', "\"\""))
+            } else {
+                cell.clone()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// A `--template` rendered once per entry of a list, the entry bound to
+/// `name`: `{{ route.id }}` with the name `route`.
+pub struct Template<'source> {
+    env: minijinja::Environment<'source>,
+    name: &'source str,
+}
+
+impl<'source> Template<'source> {
+    const TEMPLATE: &'static str = "template";
+
+    pub fn new(source: &'source str, name: &'source str) -> Result<Self> {
+        let mut env = minijinja::Environment::new();
+        env.add_template(Self::TEMPLATE, source)?;
+        Ok(Self { env, name })
+    }
+
+    pub fn render<T: Serialize>(&self, entry: &T) -> Result<String> {
+        let template = self.env.get_template(Self::TEMPLATE)?;
+        let entry = output::restyle_hex(serde_json::to_value(entry)?);
+        Ok(template.render(BTreeMap::from([(self.name, entry)]))?)
+    }
+
+    /// Every entry rendered, one per line.
+    pub fn render_all<T: Serialize>(&self, entries: &[T]) -> Result<String> {
+        Ok(entries
+            .iter()
+            .map(|entry| self.render(entry))
+            .collect::<Result<Vec<_>>>()?
+            .join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{markdown, render, select, OutputFormat, Template};
+    use serde_json::json;
+
+    #[test]
+    fn render_formats() {
+        let routes = json!({"routes": [
+            {"id": "one", "max_copies": 1, "server": {"host": "a", "port": 1}},
+            {"id": "two", "max_copies": 12, "active": false},
+        ]});
+
+        assert_eq!(
+            [
+                "ID   MAX_COPIES  SERVER                 ACTIVE",
+                r#"one  1           {"host":"a","port":1}"#,
+                "two  12                                 false",
+            ]
+            .join("\n"),
+            render(&routes, OutputFormat::Table).unwrap()
+        );
+        assert_eq!(
+            [
+                "id,max_copies,server,active",
+                r#"one,1,"{""host"":""a"",""port"":1}","#,
+                "two,12,,false",
+            ]
+            .join("\n"),
+            render(&routes, OutputFormat::Csv).unwrap()
+        );
+        assert_eq!(
+            [
+                r#"{"id":"one","max_copies":1,"server":{"host":"a","port":1}}"#,
+                r#"{"active":false,"id":"two","max_copies":12}"#,
+            ]
+            .join("\n"),
+            render(&routes, OutputFormat::Jsonl).unwrap()
+        );
+        assert_eq!(
+            "oui: 1\nowner: key\n",
+            render(&json!({"oui": 1, "owner": "key"}), OutputFormat::Yaml).unwrap()
+        );
+    }
+
+    #[test]
+    fn markdown_table() {
+        let columns = ["oui", "owner"].map(String::from);
+        let rows = vec![vec!["1".to_string(), "a|b".to_string()]];
+        assert_eq!(
+            "| oui | owner |\n|---|---|\n| 1 | a\\|b |",
+            markdown(&columns, &rows)
+        );
+    }
+
+    #[test]
+    fn select_fields() {
+        let orgs = json!({"orgs": [
+            {"oui": 1, "owner": "a", "payer": "b", "locked": false},
+            {"oui": 2, "owner": "c", "payer": "d", "locked": true},
+        ]});
+        let fields = ["locked", "oui"].map(String::from);
+        assert_eq!(
+            json!({"orgs": [{"locked": false, "oui": 1}, {"locked": true, "oui": 2}]}),
+            select(&orgs, &fields).unwrap()
+        );
+
+        let route = json!({"id": "one", "server": {"host": "a", "port": 1}});
+        let fields = ["id", "server.host"].map(String::from);
+        assert_eq!(
+            json!({"id": "one", "server.host": "a"}),
+            select(&route, &fields).unwrap()
+        );
+
+        assert!(select(&orgs, &["ouis".to_string()]).is_err());
+    }
+
+    #[test]
+    fn render_template() {
+        let routes = [
+            json!({"id": "one", "server": {"host": "a", "port": 1}}),
+            json!({"id": "two", "server": {"host": "b", "port": 2}}),
+        ];
+        let template = Template::new("{{ route.id }} {{ route.server.host }}", "route").unwrap();
+        assert_eq!("one a\ntwo b", template.render_all(&routes).unwrap());
+
+        assert!(Template::new("{{ route.id", "route").is_err());
+    }
+}
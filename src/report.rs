@@ -0,0 +1,191 @@
+//! Markdown report of the full configuration of an Org, for runbooks and
+//! change requests.
+use crate::{
+    render::markdown, route::Route, server::Protocol, subnet::DevaddrConstraint, DevaddrRange, Eui,
+    OrgResponse, SessionKeyFilter,
+};
+use std::collections::BTreeMap;
+
+/// A Route with its EUI pairs and Devaddr Ranges.
+pub struct RouteReport {
+    pub route: Route,
+    pub euis: Vec<Eui>,
+    pub devaddrs: Vec<DevaddrRange>,
+}
+
+/// Session keys are secrets, filters are reported as the number of keys per
+/// devaddr.
+pub fn org_markdown(
+    org: &OrgResponse,
+    routes: &[RouteReport],
+    filters: &[SessionKeyFilter],
+) -> String {
+    let columns = |names: &[&str]| {
+        names
+            .iter()
+            .map(|name| name.to_string())
+            .collect::<Vec<_>>()
+    };
+    let mut sections = vec![
+        format!("# Org {}", org.org.oui),
+        markdown(
+            &columns(&["field", "value"]),
+            &[
+                vec!["owner".to_string(), org.org.owner.to_string()],
+                vec!["payer".to_string(), org.org.payer.to_string()],
+                vec!["net_id".to_string(), org.net_id.to_string()],
+                vec!["locked".to_string(), org.org.locked.to_string()],
+                vec![
+                    "delegate_keys".to_string(),
+                    org.org.delegate_keys.len().to_string(),
+                ],
+            ],
+        ),
+        "## Devaddr Constraints".to_string(),
+        ranges_table(org.devaddr_constraints.clone()),
+        "## Routes".to_string(),
+    ];
+
+    for RouteReport {
+        route,
+        euis,
+        devaddrs,
+    } in routes
+    {
+        let protocol = route
+            .server
+            .protocol
+            .as_ref()
+            .map_or("none", Protocol::name);
+        sections.push(format!("### Route {}", route.id));
+        sections.push(markdown(
+            &columns(&["field", "value"]),
+            &[
+                vec![
+                    "server".to_string(),
+                    format!("{}:{}", route.server.host, route.server.port),
+                ],
+                vec!["protocol".to_string(), protocol.to_string()],
+                vec!["max_copies".to_string(), route.max_copies.to_string()],
+                vec!["active".to_string(), route.active.to_string()],
+                vec!["locked".to_string(), route.locked.to_string()],
+            ],
+        ));
+
+        if let Some(Protocol::Gwmp(gwmp)) = &route.server.protocol {
+            let rows: Vec<Vec<String>> = gwmp
+                .mapping
+                .iter()
+                .map(|(region, port)| vec![region.to_string(), port.to_string()])
+                .collect();
+            sections.push("#### GWMP Regions".to_string());
+            sections.push(markdown(&columns(&["region", "port"]), &rows));
+        }
+
+        sections.push(format!("#### Devaddr Ranges ({})", devaddrs.len()));
+        sections.push(ranges_table(
+            devaddrs
+                .iter()
+                .cloned()
+                .map(DevaddrConstraint::from)
+                .collect(),
+        ));
+
+        let rows: Vec<Vec<String>> = euis
+            .iter()
+            .map(|eui| vec![eui.app_eui.to_string(), eui.dev_eui.to_string()])
+            .collect();
+        sections.push(format!("#### EUI Pairs ({})", euis.len()));
+        sections.push(markdown(&columns(&["app_eui", "dev_eui"]), &rows));
+    }
+
+    let mut keys: BTreeMap<String, usize> = BTreeMap::new();
+    for filter in filters {
+        *keys.entry(filter.devaddr.to_string()).or_default() += 1;
+    }
+    let rows: Vec<Vec<String>> = keys
+        .into_iter()
+        .map(|(devaddr, count)| vec![devaddr, count.to_string()])
+        .collect();
+    sections.push(format!("## Session Key Filters ({})", filters.len()));
+    sections.push(markdown(&columns(&["devaddr", "session_keys"]), &rows));
+
+    sections.join("\n\n") + "\n"
+}
+
+fn ranges_table(ranges: Vec<DevaddrConstraint>) -> String {
+    let columns = ["start_addr", "end_addr", "subnets"].map(String::from);
+    let rows: Vec<Vec<String>> = ranges
+        .into_iter()
+        .map(|range| {
+            vec![
+                range.start_addr.to_string(),
+                range.end_addr.to_string(),
+                range.to_subnet().subnets.join(", "),
+            ]
+        })
+        .collect();
+    markdown(&columns, &rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{org_markdown, RouteReport};
+    use crate::{
+        hex_field, route::RouteId, subnet::DevaddrConstraint, DevaddrRange, Eui, Org, OrgResponse,
+        Route, SessionKeyFilter,
+    };
+    use helium_crypto::{KeyTag, KeyType, Keypair, Network};
+
+    #[test]
+    fn org_report() {
+        let tag = KeyTag {
+            network: Network::MainNet,
+            key_type: KeyType::Ed25519,
+        };
+        let owner = Keypair::generate(tag, &mut rand::rngs::OsRng)
+            .public_key()
+            .clone();
+        let org = OrgResponse {
+            org: Org {
+                oui: 4,
+                owner: owner.clone(),
+                payer: owner,
+                delegate_keys: vec![],
+                locked: false,
+            },
+            net_id: hex_field::net_id(0xC00053),
+            devaddr_constraints: vec![DevaddrConstraint::new(
+                hex_field::devaddr(0x48000000),
+                hex_field::devaddr(0x48000007),
+            )
+            .unwrap()],
+        };
+        let mut route = Route::new(hex_field::net_id(0xC00053), 4, 1);
+        route.id = RouteId::new_unchecked("route-one");
+        let report = RouteReport {
+            euis: vec![Eui::new(route.id.clone(), hex_field::eui(1), hex_field::eui(2)).unwrap()],
+            devaddrs: vec![DevaddrRange::new(
+                route.id.clone(),
+                hex_field::devaddr(0x48000000),
+                hex_field::devaddr(0x48000003),
+            )
+            .unwrap()],
+            route,
+        };
+        let filters = ["key-one", "key-two"]
+            .map(|key| SessionKeyFilter::new(4, hex_field::devaddr(0x48000000), key.to_string()));
+
+        let markdown = org_markdown(&org, &[report], &filters);
+        assert!(markdown.starts_with("# Org 4\n\n| field | value |\n|---|---|\n"));
+        assert!(markdown.contains("| 48000000 | 48000007 | 48000000/29 |"));
+        assert!(markdown.contains("### Route route-one"));
+        assert!(markdown.contains("| protocol | packet_router |"));
+        assert!(markdown.contains("| 48000000 | 48000003 | 48000000/30 |"));
+        assert!(markdown.contains("| 0000000000000001 | 0000000000000002 |"));
+        assert!(markdown.contains(
+            "## Session Key Filters (2)\n\n| devaddr | session_keys |\n|---|---|\n| 48000000 | 2 |"
+        ));
+        assert!(!markdown.contains("key-one"));
+    }
+}
@@ -1,14 +1,89 @@
 use crate::{
     hex_field,
-    server::{GwmpMap, Http, Server},
-    Oui, Result,
+    server::{Gwmp, GwmpMap, Http, Protocol, Server},
+    DevaddrRange, Oui, Result,
 };
+use anyhow::{anyhow, Context};
+#[cfg(feature = "client")]
 use helium_proto::services::iot_config::RouteV1 as ProtoRoute;
 use serde::{Deserialize, Serialize};
+use std::{fmt::Display, fs, ops::Deref, path::Path, str::FromStr};
+
+/// Id of a route as assigned by the config service, a UUID.
+///
+/// Parsing validates the format so typo'd ids are rejected before a request
+/// is signed. Ids received from the config service are taken as-is. The
+/// empty default is the id of a route that has not been created yet.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct RouteId(String);
+
+impl RouteId {
+    pub fn new_unchecked(id: impl Into<String>) -> Self {
+        Self(id.into())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Ok for a UUID, or for any id with `allow_any_id`.
+    pub fn check(&self, allow_any_id: bool) -> Result {
+        if !allow_any_id {
+            self.0.parse::<RouteId>()?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for RouteId {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let groups: Vec<&str> = s.split('-').collect();
+        let is_uuid = groups.len() == 5
+            && groups.iter().zip([8, 4, 4, 4, 12]).all(|(group, len)| {
+                group.len() == len && group.chars().all(|c| c.is_ascii_hexdigit())
+            });
+        if !is_uuid {
+            return Err(anyhow!("invalid route id {s:?}, expected a UUID"));
+        }
+        Ok(Self(s.to_string()))
+    }
+}
+
+/// Any non-empty id. Clap value parsers cannot see other arguments, so ids
+/// are checked with [`RouteId::check`] once `--allow-any-id` is known.
+pub fn validate_route_id(s: &str) -> Result<RouteId> {
+    if s.trim().is_empty() {
+        return Err(anyhow!("route id cannot be empty"));
+    }
+    Ok(RouteId::new_unchecked(s))
+}
+
+impl Display for RouteId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Deref for RouteId {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<RouteId> for String {
+    fn from(id: RouteId) -> Self {
+        id.0
+    }
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct Route {
-    pub id: String,
+    pub id: RouteId,
     pub net_id: hex_field::HexNetID,
     pub oui: Oui,
     pub server: Server,
@@ -20,7 +95,7 @@ pub struct Route {
 impl Route {
     pub fn new(net_id: hex_field::HexNetID, oui: Oui, max_copies: u32) -> Self {
         Self {
-            id: "".into(),
+            id: RouteId::default(),
             net_id,
             oui,
             server: Server::default(),
@@ -41,12 +116,200 @@ impl Route {
     pub fn http_update(&mut self, http: Http) -> Result {
         self.server.http_update(http)
     }
+
+    /// Read a Route file, YAML for `.yaml` and `.yml` files, TOML for
+    /// `.toml` files, JSON otherwise.
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let data = fs::read_to_string(path)
+            .with_context(|| format!("reading route file {}", path.display()))?;
+        match RouteFileFormat::of(path) {
+            RouteFileFormat::Json => serde_json::from_str(&data).map_err(anyhow::Error::from),
+            RouteFileFormat::Yaml => serde_yaml::from_str(&data).map_err(anyhow::Error::from),
+            RouteFileFormat::Toml => toml::from_str(&data).map_err(anyhow::Error::from),
+        }
+        .with_context(|| format!("parsing route file {}", path.display()))
+    }
+
+    /// Check a Route file before it is pushed: it must have the id of an
+    /// existing Route, and a gwmp mapping must use distinct, valid ports.
+    pub fn validate(&self) -> Result {
+        if self.id.is_empty() {
+            return Err(anyhow!("route has no id, create it with `route new` first"));
+        }
+        if let Some(Protocol::Gwmp(gwmp)) = &self.server.protocol {
+            Gwmp::new(gwmp.mapping.clone())?;
+        }
+        Ok(())
+    }
+
+    /// Write a Route file in the format [`Route::from_file`] reads for `path`.
+    pub fn write(&self, path: &Path) -> Result {
+        let data = match RouteFileFormat::of(path) {
+            RouteFileFormat::Json => serde_json::to_string_pretty(self)?,
+            RouteFileFormat::Yaml => serde_yaml::to_string(self)?,
+            RouteFileFormat::Toml => toml::to_string_pretty(self)?,
+        };
+        fs::write(path, data).with_context(|| format!("writing route file {}", path.display()))
+    }
+
+    /// Compact description of the Route for people, with the number of EUI
+    /// pairs and the Devaddr Ranges it holds. The http auth header is left
+    /// out.
+    pub fn summary(&self, euis: u64, devaddrs: &[DevaddrRange]) -> String {
+        let mut lines = vec![
+            ("route", self.id.to_string()),
+            ("oui", self.oui.to_string()),
+            ("net_id", self.net_id.to_string()),
+            (
+                "status",
+                format!(
+                    "{}, {}",
+                    if self.active { "active" } else { "inactive" },
+                    if self.locked { "locked" } else { "unlocked" }
+                ),
+            ),
+            ("max_copies", self.max_copies.to_string()),
+            (
+                "server",
+                format!("{}:{}", self.server.host, self.server.port),
+            ),
+            (
+                "protocol",
+                self.server
+                    .protocol
+                    .as_ref()
+                    .map_or("none", Protocol::name)
+                    .to_string(),
+            ),
+        ];
+        match &self.server.protocol {
+            Some(Protocol::Gwmp(gwmp)) => lines.extend(
+                gwmp.mapping
+                    .iter()
+                    .map(|(region, port)| ("gwmp", format!("{region} -> {port}"))),
+            ),
+            Some(Protocol::Http(http)) => lines.push((
+                "http",
+                format!(
+                    "{} flow, {} ms dedupe, path {:?}",
+                    http.flow_type.name(),
+                    http.dedupe_timeout,
+                    http.path
+                ),
+            )),
+            Some(Protocol::PacketRouter) | None => (),
+        }
+        lines.push(("euis", euis.to_string()));
+        if devaddrs.is_empty() {
+            lines.push(("devaddrs", "none".to_string()));
+        }
+        lines.extend(devaddrs.iter().map(|range| {
+            let subnets = range.clone().to_subnet().subnets.join(", ");
+            (
+                "devaddrs",
+                format!("{}-{} ({subnets})", range.start_addr, range.end_addr),
+            )
+        }));
+
+        lines
+            .into_iter()
+            .map(|(label, value)| format!("{:<12}{value}", format!("{label}:")))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// JSON Schema (draft 2020-12) of a Route file, for validating route
+    /// files before they are pushed. YAML and TOML route files have the same
+    /// shape.
+    pub fn schema() -> serde_json::Value {
+        serde_json::json!({
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "title": "Route",
+            "type": "object",
+            "required": ["id", "net_id", "oui", "server", "max_copies", "active", "locked"],
+            "properties": {
+                "id": {
+                    "type": "string",
+                    "description": "UUID assigned by the config service, empty for a new route",
+                },
+                "net_id": hex_field::HexNetID::schema(),
+                "oui": {"type": "integer", "minimum": 0},
+                "server": {"$ref": "#/$defs/server"},
+                "max_copies": {"type": "integer", "minimum": 0, "maximum": u32::MAX},
+                "active": {"type": "boolean"},
+                "locked": {"type": "boolean"},
+            },
+            "$defs": {
+                "server": Server::schema(),
+                "protocol": Protocol::schema(),
+            },
+        })
+    }
+}
+
+/// Directory inside a `route sync` directory holding the version of each
+/// Route both sides agreed on at the last sync, as `<route id>.json`.
+pub const SYNC_BASE_DIR: &str = ".sync";
+
+/// What `route sync` does with a Route, from the Route file, the Route on
+/// the config service, and the version of the last sync.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncAction {
+    InSync,
+    /// Only the config service changed, the file is rewritten.
+    Pull,
+    /// Only the file changed, the Route is updated.
+    Push,
+    /// Both changed, or one side is missing after a previous sync. Left
+    /// for manual resolution.
+    Conflict,
+}
+
+impl SyncAction {
+    pub fn of(local: Option<&Route>, remote: Option<&Route>, base: Option<&Route>) -> Self {
+        match (local, remote, base) {
+            (None, None, _) => Self::InSync,
+            (Some(local), Some(remote), _) if local == remote => Self::InSync,
+            (None, Some(_), None) => Self::Pull,
+            (Some(local), Some(_), Some(base)) if local == base => Self::Pull,
+            (Some(_), Some(remote), Some(base)) if remote == base => Self::Push,
+            _ => Self::Conflict,
+        }
+    }
+}
+
+/// A JSON, YAML, or TOML file, by extension.
+pub fn is_route_file(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|ext| ext.to_str()),
+        Some("json" | "yaml" | "yml" | "toml")
+    )
 }
 
+/// Format of a file, YAML for `.yaml` and `.yml` files, TOML for `.toml`
+/// files, JSON otherwise.
+pub enum RouteFileFormat {
+    Json,
+    Yaml,
+    Toml,
+}
+
+impl RouteFileFormat {
+    pub fn of(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("yaml" | "yml") => Self::Yaml,
+            Some("toml") => Self::Toml,
+            _ => Self::Json,
+        }
+    }
+}
+
+#[cfg(feature = "client")]
 impl From<ProtoRoute> for Route {
     fn from(route: ProtoRoute) -> Self {
         Self {
-            id: route.id,
+            id: RouteId::new_unchecked(route.id),
             net_id: route.net_id.into(),
             oui: route.oui,
             server: route.server.map_or_else(Server::default, |s| s.into()),
@@ -57,10 +320,11 @@ impl From<ProtoRoute> for Route {
     }
 }
 
+#[cfg(feature = "client")]
 impl From<Route> for ProtoRoute {
     fn from(route: Route) -> Self {
         Self {
-            id: route.id,
+            id: route.id.into(),
             net_id: route.net_id.into(),
             oui: route.oui,
             server: Some(route.server.into()),
@@ -73,15 +337,25 @@ impl From<Route> for ProtoRoute {
 
 #[cfg(test)]
 mod tests {
-    use crate::{hex_field, server::Server, Route};
+    use super::{validate_route_id, RouteId, SyncAction};
+    use crate::{
+        hex_field,
+        region::Region,
+        server::{FlowType, Gwmp, Http, Protocol as RouteProtocol, Server},
+        DevaddrRange, Route,
+    };
+    #[cfg(feature = "client")]
     use helium_proto::services::iot_config::{
         server_v1::Protocol, ProtocolPacketRouterV1, RouteV1, ServerV1,
     };
+    use std::collections::{BTreeMap, BTreeSet};
+    use temp_dir::TempDir;
 
+    #[cfg(feature = "client")]
     #[test]
     fn route_to_route_v1_conversion() {
         let route = Route {
-            id: "route_id".into(),
+            id: RouteId::new_unchecked("route_id"),
             net_id: hex_field::net_id(1),
             oui: 66,
             server: Server::default(),
@@ -106,4 +380,209 @@ mod tests {
         assert_eq!(route, Route::from(v1.clone()));
         assert_eq!(v1, RouteV1::from(route));
     }
+
+    #[test]
+    fn route_file_round_trip() {
+        let dir = TempDir::new().unwrap();
+        let gwmp = Route {
+            id: RouteId::new_unchecked("5c1bfc5b-1d38-4a41-9b58-b16dc5b4c2a0"),
+            net_id: hex_field::net_id(0xC00053),
+            oui: 4,
+            server: Server::new(
+                "gwmp.example.com".to_string(),
+                1700,
+                RouteProtocol::Gwmp(Gwmp {
+                    mapping: BTreeMap::from([(Region::Us915, 1700), (Region::Eu868, 1701)]),
+                }),
+            ),
+            max_copies: 3,
+            active: true,
+            locked: false,
+        };
+        let http = Route {
+            server: Server::new(
+                "http.example.com".to_string(),
+                443,
+                RouteProtocol::Http(Http {
+                    flow_type: FlowType::Async,
+                    dedupe_timeout: 250,
+                    path: "/uplink".to_string(),
+                    auth_header: "Bearer token".to_string(),
+                }),
+            ),
+            ..gwmp.clone()
+        };
+
+        for route in [gwmp, http] {
+            for name in ["route.json", "route.yaml", "route.yml", "route.toml"] {
+                let path = dir.child(name);
+                route.write(&path).unwrap();
+                assert_eq!(route, Route::from_file(&path).unwrap());
+            }
+        }
+
+        // Hex fields stay hex strings in YAML.
+        let yaml = std::fs::read_to_string(dir.child("route.yaml")).unwrap();
+        assert!(yaml.contains("net_id: C00053"));
+        let toml = std::fs::read_to_string(dir.child("route.toml")).unwrap();
+        assert!(toml.contains(r#"net_id = "C00053""#));
+    }
+
+    #[test]
+    fn route_summary() {
+        let mut route = Route::new(hex_field::net_id(0xC00053), 4, 3);
+        route.id = RouteId::new_unchecked("5c1bfc5b-1d38-4a41-9b58-b16dc5b4c2a0");
+        route.server = Server::new(
+            "gwmp.example.com".to_string(),
+            1700,
+            RouteProtocol::Gwmp(Gwmp {
+                mapping: BTreeMap::from([(Region::Us915, 1700), (Region::Eu868, 1701)]),
+            }),
+        );
+        let devaddrs = [DevaddrRange::new(
+            route.id.clone(),
+            hex_field::devaddr(0x48000000),
+            hex_field::devaddr(0x48000007),
+        )
+        .unwrap()];
+
+        assert_eq!(
+            [
+                "route:      5c1bfc5b-1d38-4a41-9b58-b16dc5b4c2a0",
+                "oui:        4",
+                "net_id:     C00053",
+                "status:     active, unlocked",
+                "max_copies: 3",
+                "server:     gwmp.example.com:1700",
+                "protocol:   gwmp",
+                "gwmp:       US915 -> 1700",
+                "gwmp:       EU868 -> 1701",
+                "euis:       12",
+                "devaddrs:   48000000-48000007 (48000000/29)",
+            ]
+            .join("\n"),
+            route.summary(12, &devaddrs)
+        );
+    }
+
+    #[test]
+    fn route_schema() {
+        let schema = Route::schema();
+        let route = serde_json::to_value(Route::new(hex_field::net_id(0xC00053), 4, 1)).unwrap();
+
+        // Every field is required and described.
+        let fields: BTreeSet<&String> = route.as_object().unwrap().keys().collect();
+        let properties: BTreeSet<&String> =
+            schema["properties"].as_object().unwrap().keys().collect();
+        let required: BTreeSet<&str> = schema["required"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|key| key.as_str().unwrap())
+            .collect();
+        assert_eq!(fields, properties);
+        assert!(fields.iter().map(|field| field.as_str()).eq(required));
+
+        let types: Vec<&str> = schema["$defs"]["protocol"]["oneOf"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|protocol| protocol["properties"]["type"]["const"].as_str().unwrap())
+            .collect();
+        assert_eq!(vec!["gwmp", "http", "packet_router"], types);
+        assert_eq!(
+            route["server"]["protocol"]["type"],
+            schema["$defs"]["protocol"]["oneOf"][2]["properties"]["type"]["const"]
+        );
+
+        let regions = &schema["$defs"]["protocol"]["oneOf"][0]["properties"]["mapping"];
+        assert!(regions["propertyNames"]["enum"]
+            .as_array()
+            .unwrap()
+            .contains(&serde_json::json!("US915")));
+        assert_eq!(
+            "^[0-9A-Fa-f]{6}$",
+            schema["properties"]["net_id"]["pattern"]
+        );
+    }
+
+    #[test]
+    fn parse_route_id() {
+        let id: RouteId = "5c1bfc5b-1d38-4a41-9b58-b16dc5b4c2a0".parse().unwrap();
+        assert_eq!("5c1bfc5b-1d38-4a41-9b58-b16dc5b4c2a0", id.as_str());
+        assert!("5C1BFC5B-1D38-4A41-9B58-B16DC5B4C2A0"
+            .parse::<RouteId>()
+            .is_ok());
+
+        assert!("".parse::<RouteId>().is_err());
+        assert!("route_id".parse::<RouteId>().is_err());
+        assert!("5c1bfc5b-1d38-4a41-9b58-b16dc5b4c2a"
+            .parse::<RouteId>()
+            .is_err());
+        assert!("5c1bfc5b1d384a419b58b16dc5b4c2a0"
+            .parse::<RouteId>()
+            .is_err());
+        assert!("5c1bfc5b-1d38-4a41-9b58-b16dc5b4c2ag"
+            .parse::<RouteId>()
+            .is_err());
+    }
+
+    #[test]
+    fn validate_route_id_escape_hatch() {
+        let route_id = validate_route_id("route_id").unwrap();
+        assert_eq!("route_id", route_id.as_str());
+        assert!(route_id.check(false).is_err());
+        assert!(route_id.check(true).is_ok());
+        assert!(validate_route_id(" ").is_err());
+        assert!(validate_route_id("5c1bfc5b-1d38-4a41-9b58-b16dc5b4c2a0")
+            .unwrap()
+            .check(false)
+            .is_ok());
+    }
+
+    #[test]
+    fn route_sync_action() {
+        let base = Route::new(hex_field::net_id(0xC00053), 4, 1);
+        let changed = Route {
+            max_copies: 2,
+            ..base.clone()
+        };
+        let other = Route {
+            max_copies: 3,
+            ..base.clone()
+        };
+        let of = |local, remote, base| SyncAction::of(local, remote, base);
+
+        assert_eq!(SyncAction::InSync, of(Some(&base), Some(&base), None));
+        assert_eq!(SyncAction::Pull, of(None, Some(&base), None));
+        assert_eq!(
+            SyncAction::Pull,
+            of(Some(&base), Some(&changed), Some(&base))
+        );
+        assert_eq!(
+            SyncAction::Push,
+            of(Some(&changed), Some(&base), Some(&base))
+        );
+        assert_eq!(
+            SyncAction::Conflict,
+            of(Some(&changed), Some(&other), Some(&base))
+        );
+        assert_eq!(SyncAction::Conflict, of(Some(&changed), Some(&base), None));
+        assert_eq!(SyncAction::Conflict, of(None, Some(&base), Some(&base)));
+        assert_eq!(SyncAction::Conflict, of(Some(&base), None, None));
+    }
+
+    #[test]
+    fn route_validate() {
+        let mut route = Route::new(hex_field::net_id(0xC00053), 4, 1);
+        assert!(route.validate().is_err());
+
+        route.id = RouteId::new_unchecked("route-one");
+        assert!(route.validate().is_ok());
+
+        route.server.protocol = Some(RouteProtocol::Gwmp(Gwmp {
+            mapping: BTreeMap::from([(Region::Us915, 1700), (Region::Eu868, 1700)]),
+        }));
+        assert!(route.validate().is_err());
+    }
 }
@@ -1,8 +1,11 @@
-use crate::{region::Region, Result};
-use anyhow::anyhow;
+use crate::{region::Region, route::RouteId, Result};
+use anyhow::{anyhow, Context};
+use clap::ValueEnum;
 use serde::{Deserialize, Serialize};
-use std::collections::BTreeMap;
+use serde_json::json;
+use std::{collections::BTreeMap, env, fs, path::PathBuf};
 
+#[cfg(feature = "client")]
 pub mod proto {
     pub use helium_proto::services::iot_config::{
         protocol_http_roaming_v1::FlowTypeV1, server_v1::Protocol, ProtocolGwmpMappingV1,
@@ -12,6 +15,68 @@ pub mod proto {
 
 pub type Port = u32;
 pub type GwmpMap = BTreeMap<Region, Port>;
+/// User defined gwmp presets by name.
+///
+/// ```json
+/// { "my-preset": { "US915": 1700, "EU868": 1701 } }
+/// ```
+pub type GwmpPresets = BTreeMap<String, GwmpMap>;
+
+pub const BUILTIN_GWMP_PRESETS: [&str; 3] = ["chirpstack-default", "sequential", "single-port"];
+
+pub fn read_gwmp_presets(path: &PathBuf) -> Result<GwmpPresets> {
+    let data = fs::read_to_string(path).context("reading gwmp presets file")?;
+    serde_json::from_str(&data).context(format!("parsing gwmp presets file {}", path.display()))
+}
+
+/// Gwmp mappings of routes that were switched to another protocol.
+///
+/// The config service keeps no history, so mappings are saved locally to be
+/// restored when a route goes back to gwmp.
+///
+/// ```json
+/// { "<route id>": { "US915": 1700, "EU868": 1701 } }
+/// ```
+pub type GwmpHistory = BTreeMap<RouteId, GwmpMap>;
+
+/// `gwmp_history.json` in the state directory of the cli,
+/// `$XDG_STATE_HOME/helium-config-service-cli` or
+/// `~/.local/state/helium-config-service-cli`. The working directory when
+/// neither is set.
+pub fn default_gwmp_history() -> PathBuf {
+    let state_home = env::var_os("XDG_STATE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/state")));
+    match state_home {
+        Some(dir) => dir.join(env!("CARGO_PKG_NAME")).join("gwmp_history.json"),
+        None => PathBuf::from("gwmp_history.json"),
+    }
+}
+
+/// A missing history file is an empty history.
+pub fn read_gwmp_history(path: &PathBuf) -> Result<GwmpHistory> {
+    if !path.exists() {
+        return Ok(GwmpHistory::new());
+    }
+    let data = fs::read_to_string(path).context("reading gwmp history file")?;
+    serde_json::from_str(&data).context(format!("parsing gwmp history file {}", path.display()))
+}
+
+/// The parent directories of `path` are created.
+pub fn write_gwmp_history(path: &PathBuf, history: &GwmpHistory) -> Result {
+    if let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        fs::create_dir_all(parent).context(format!(
+            "creating gwmp history directory {}",
+            parent.display()
+        ))?;
+    }
+    fs::write(path, serde_json::to_string_pretty(history)?)
+        .context(format!("writing gwmp history file {}", path.display()))
+}
 
 #[derive(Serialize, Clone, Debug, Deserialize, PartialEq, Eq)]
 pub struct Server {
@@ -31,6 +96,21 @@ impl Default for Server {
 }
 
 impl Server {
+    /// JSON Schema of a serialized Server, see [`crate::route::Route::schema`].
+    pub fn schema() -> serde_json::Value {
+        json!({
+            "type": "object",
+            "required": ["host", "port"],
+            "properties": {
+                "host": {"type": "string"},
+                "port": {"type": "integer", "minimum": 0, "maximum": Port::MAX},
+                "protocol": {
+                    "oneOf": [{"type": "null"}, {"$ref": "#/$defs/protocol"}],
+                },
+            },
+        })
+    }
+
     pub fn new(host: String, port: Port, protocol: Protocol) -> Self {
         Self {
             host,
@@ -64,11 +144,82 @@ pub enum Protocol {
 }
 
 impl Protocol {
+    /// JSON Schema of a serialized Protocol, tagged by `type`.
+    pub fn schema() -> serde_json::Value {
+        let regions: Vec<serde_json::Value> = Region::value_variants()
+            .iter()
+            .filter_map(|region| serde_json::to_value(region).ok())
+            .collect();
+        json!({
+            "oneOf": [
+                {
+                    "type": "object",
+                    "required": ["type", "mapping"],
+                    "properties": {
+                        "type": {"const": "gwmp"},
+                        "mapping": {
+                            "type": "object",
+                            "propertyNames": {"enum": regions},
+                            "additionalProperties": {
+                                "type": "integer",
+                                "minimum": 1,
+                                "maximum": u16::MAX,
+                            },
+                        },
+                    },
+                },
+                {
+                    "type": "object",
+                    "required": ["type", "flow_type", "dedupe_timeout", "path", "auth_header"],
+                    "properties": {
+                        "type": {"const": "http"},
+                        "flow_type": {"enum": ["sync", "async"]},
+                        "dedupe_timeout": {"type": "integer", "minimum": 0, "maximum": u32::MAX},
+                        "path": {"type": "string", "pattern": "^\\S*$"},
+                        "auth_header": {"type": "string"},
+                    },
+                },
+                {
+                    "type": "object",
+                    "required": ["type"],
+                    "properties": {"type": {"const": "packet_router"}},
+                },
+            ],
+        })
+    }
+
     pub fn is_gwmp(&self) -> bool {
+        matches!(self, Protocol::Gwmp(_))
+    }
+
+    pub fn is_http(&self) -> bool {
+        matches!(self, Protocol::Http(_))
+    }
+
+    pub fn is_packet_router(&self) -> bool {
+        matches!(self, Protocol::PacketRouter)
+    }
+
+    /// Same as the `type` of the serialized protocol.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Protocol::Gwmp(_) => "gwmp",
+            Protocol::Http(_) => "http",
+            Protocol::PacketRouter => "packet_router",
+        }
+    }
+
+    pub fn as_gwmp(&self) -> Option<&Gwmp> {
         match self {
-            Protocol::Gwmp(_) => true,
-            Protocol::Http(_) => false,
-            Protocol::PacketRouter => false,
+            Protocol::Gwmp(gwmp) => Some(gwmp),
+            _ => None,
+        }
+    }
+
+    pub fn as_http(&self) -> Option<&Http> {
+        match self {
+            Protocol::Http(http) => Some(http),
+            _ => None,
         }
     }
 
@@ -88,27 +239,50 @@ impl Protocol {
         BTreeMap::from([(region, port)])
     }
 
-    pub fn make_http(dedupe_timeout: u32, path: String, auth_header: Option<String>) -> Self {
-        Self::Http(Http {
-            flow_type: FlowType::Async,
-            dedupe_timeout,
-            path,
-            auth_header: auth_header.unwrap_or_default(),
-        })
-    }
-
     pub fn make_gwmp(region: Region, port: Port) -> Result<Self> {
         let mut gwmp = Self::default_gwmp();
         gwmp.gwmp_add_mapping(Self::make_gwmp_mapping(region, port))?;
         Ok(gwmp)
     }
 
+    /// Map every supported region to a port, starting at `base_port` and
+    /// incrementing by `step` in the order regions are listed by `--help`.
+    pub fn make_gwmp_all_regions(base_port: Port, step: Port) -> Result<Self> {
+        let mut gwmp = Self::default_gwmp();
+        for (index, region) in Region::value_variants().iter().enumerate() {
+            let port = step.saturating_mul(index as Port).saturating_add(base_port);
+            gwmp.gwmp_add_mapping(Self::make_gwmp_mapping(region.clone(), port))?;
+        }
+        Ok(gwmp)
+    }
+
+    /// Build a gwmp protocol from a named preset.
+    ///
+    /// User presets take precedence over the builtin ones:
+    /// - `chirpstack-default`: EU868 on 1700, the ChirpStack gateway bridge default
+    /// - `sequential`: every region starting at 1700
+    /// - `single-port`: every region on 1700, see [`Gwmp::single_port`]
+    pub fn from_gwmp_preset(name: &str, user_presets: &GwmpPresets) -> Result<Self> {
+        let mapping = match (user_presets.get(name), name) {
+            (Some(mapping), _) => mapping.clone(),
+            (None, "chirpstack-default") => Self::make_gwmp_mapping(Region::Eu868, 1700),
+            (None, "sequential") => return Self::make_gwmp_all_regions(1700, 1),
+            (None, "single-port") => return Ok(Protocol::Gwmp(Gwmp::single_port(1700)?)),
+            (None, _) => {
+                return Err(anyhow!(
+                    "unknown gwmp preset {name}, builtin presets are {}",
+                    BUILTIN_GWMP_PRESETS.join(", ")
+                ))
+            }
+        };
+        let mut gwmp = Self::default_gwmp();
+        gwmp.gwmp_add_mapping(mapping)?;
+        Ok(gwmp)
+    }
+
     pub fn gwmp_add_mapping(&mut self, map: GwmpMap) -> Result {
         match self {
-            Protocol::Gwmp(Gwmp { ref mut mapping }) => {
-                mapping.extend(map);
-                Ok(())
-            }
+            Protocol::Gwmp(gwmp) => gwmp.add_mapping(map),
             Protocol::Http(_) => Err(anyhow!("cannot add region mapping to http")),
             Protocol::PacketRouter => Err(anyhow!("cannot add region mapping to packet router")),
         }
@@ -116,8 +290,8 @@ impl Protocol {
 
     pub fn gwmp_remove_mapping(&mut self, region: &Region) -> Result {
         match self {
-            Protocol::Gwmp(Gwmp { ref mut mapping }) => {
-                mapping.remove(region);
+            Protocol::Gwmp(gwmp) => {
+                gwmp.remove_mapping(region);
                 Ok(())
             }
             Protocol::Http(_) => Err(anyhow!("cannot remove region mapping from http")),
@@ -125,7 +299,7 @@ impl Protocol {
         }
     }
 
-    fn http_update(&mut self, http: Http) -> Result {
+    pub fn http_update(&mut self, http: Http) -> Result {
         match self {
             Protocol::Http(_) => {
                 *self = Protocol::Http(http);
@@ -137,11 +311,64 @@ impl Protocol {
     }
 }
 
+/// Gwmp ports are UDP ports, 0 is not a valid destination.
+pub fn validate_gwmp_port(port: Port) -> Result {
+    match port {
+        0 => Err(anyhow!("gwmp port cannot be 0")),
+        p if p > u16::MAX as Port => Err(anyhow!("gwmp port {p} is out of range (1-65535)")),
+        _ => Ok(()),
+    }
+}
+
 #[derive(Serialize, Debug, Deserialize, Clone, PartialEq, Eq, Default)]
 pub struct Gwmp {
     pub mapping: GwmpMap,
 }
 
+impl Gwmp {
+    pub fn new(mapping: GwmpMap) -> Result<Self> {
+        let mut gwmp = Self::default();
+        gwmp.add_mapping(mapping)?;
+        Ok(gwmp)
+    }
+
+    /// Merge `map` into the existing mapping, replacing the port of regions
+    /// already mapped. Every region must end up on a distinct, valid port.
+    pub fn add_mapping(&mut self, map: GwmpMap) -> Result {
+        let mut merged = self.mapping.clone();
+        merged.extend(map.clone());
+        for (region, port) in map.iter() {
+            validate_gwmp_port(*port)?;
+            if let Some((other, _)) = merged.iter().find(|(r, p)| *r != region && *p == port) {
+                return Err(anyhow!("port {port} is already mapped to {other:?}"));
+            }
+        }
+        self.mapping = merged;
+        Ok(())
+    }
+
+    /// Every region on `port`, for servers that tell regions apart by
+    /// gateway rather than by port. The one mapping where regions share a
+    /// port, [`add_mapping`](Self::add_mapping) still keeps the ports of
+    /// regions it adds distinct.
+    pub fn single_port(port: Port) -> Result<Self> {
+        validate_gwmp_port(port)?;
+        Ok(Self {
+            mapping: Region::value_variants()
+                .iter()
+                .map(|region| (region.clone(), port))
+                .collect(),
+        })
+    }
+
+    pub fn remove_mapping(&mut self, region: &Region) {
+        self.mapping.remove(region);
+    }
+}
+
+/// Dedupe timeout in milliseconds for routes switched to http.
+pub const DEFAULT_DEDUPE_TIMEOUT: u32 = 250;
+
 #[derive(Serialize, Debug, Deserialize, Clone, PartialEq, Eq, Default)]
 pub struct Http {
     pub flow_type: FlowType,
@@ -150,6 +377,48 @@ pub struct Http {
     pub auth_header: String,
 }
 
+impl Http {
+    /// The path is appended to the Server `{host}:{port}` and the auth header
+    /// is sent verbatim, neither may contain whitespace that would break the
+    /// request line or headers.
+    pub fn new(
+        flow_type: FlowType,
+        dedupe_timeout: u32,
+        path: String,
+        auth_header: Option<String>,
+    ) -> Result<Self> {
+        if path.chars().any(char::is_whitespace) {
+            return Err(anyhow!("http path {path:?} cannot contain whitespace"));
+        }
+        let auth_header = auth_header.unwrap_or_default();
+        if auth_header.contains(['\r', '\n']) {
+            return Err(anyhow!("http auth header cannot contain line breaks"));
+        }
+        Ok(Self {
+            flow_type,
+            dedupe_timeout,
+            path,
+            auth_header,
+        })
+    }
+
+    /// Copy with only the given fields replaced, validated like [`Http::new`].
+    pub fn update(
+        &self,
+        flow_type: Option<FlowType>,
+        dedupe_timeout: Option<u32>,
+        path: Option<String>,
+        auth_header: Option<String>,
+    ) -> Result<Self> {
+        Self::new(
+            flow_type.unwrap_or_else(|| self.flow_type.clone()),
+            dedupe_timeout.unwrap_or(self.dedupe_timeout),
+            path.unwrap_or_else(|| self.path.clone()),
+            Some(auth_header.unwrap_or_else(|| self.auth_header.clone())),
+        )
+    }
+}
+
 #[derive(clap::ValueEnum, Clone, Serialize, Debug, Deserialize, PartialEq, Eq, Default)]
 #[serde(rename_all = "lowercase")]
 pub enum FlowType {
@@ -158,6 +427,17 @@ pub enum FlowType {
     Async,
 }
 
+impl FlowType {
+    /// Same as the serialized flow type.
+    pub fn name(&self) -> &'static str {
+        match self {
+            FlowType::Sync => "sync",
+            FlowType::Async => "async",
+        }
+    }
+}
+
+#[cfg(feature = "client")]
 impl FlowType {
     fn from_i32(v: i32) -> Result<Self> {
         proto::FlowTypeV1::from_i32(v)
@@ -166,6 +446,7 @@ impl FlowType {
     }
 }
 
+#[cfg(feature = "client")]
 impl From<proto::FlowTypeV1> for FlowType {
     fn from(ft: proto::FlowTypeV1) -> Self {
         match ft {
@@ -175,6 +456,7 @@ impl From<proto::FlowTypeV1> for FlowType {
     }
 }
 
+#[cfg(feature = "client")]
 impl From<FlowType> for proto::FlowTypeV1 {
     fn from(ft: FlowType) -> Self {
         match ft {
@@ -184,6 +466,7 @@ impl From<FlowType> for proto::FlowTypeV1 {
     }
 }
 
+#[cfg(feature = "client")]
 impl From<Server> for proto::ServerV1 {
     fn from(server: Server) -> Self {
         proto::ServerV1 {
@@ -194,6 +477,7 @@ impl From<Server> for proto::ServerV1 {
     }
 }
 
+#[cfg(feature = "client")]
 impl From<proto::ServerV1> for Server {
     fn from(server: proto::ServerV1) -> Self {
         Server {
@@ -204,6 +488,7 @@ impl From<proto::ServerV1> for Server {
     }
 }
 
+#[cfg(feature = "client")]
 impl From<Protocol> for proto::Protocol {
     fn from(protocol: Protocol) -> Self {
         match protocol {
@@ -230,6 +515,7 @@ impl From<Protocol> for proto::Protocol {
     }
 }
 
+#[cfg(feature = "client")]
 impl From<proto::Protocol> for Protocol {
     fn from(proto: proto::Protocol) -> Self {
         match proto {
@@ -256,13 +542,18 @@ impl From<proto::Protocol> for Protocol {
 mod tests {
     /// Ensure all the keys and values are snake_cased.
     /// Serialize regions as lowercase with underscores in the right places.
-    use super::{Gwmp, Protocol, Server};
+    use super::{
+        read_gwmp_history, write_gwmp_history, Gwmp, GwmpHistory, GwmpPresets, Protocol, Server,
+    };
     use crate::{
         region::Region,
+        route::RouteId,
         server::{FlowType, Http},
     };
+    use clap::ValueEnum;
     use serde_test::{assert_ser_tokens, Token};
     use std::collections::BTreeMap;
+    use temp_dir::TempDir;
 
     #[test]
     fn server_ser() {
@@ -368,4 +659,128 @@ mod tests {
             ],
         );
     }
+
+    #[test]
+    fn gwmp_port_validation() {
+        let mut gwmp = Protocol::make_gwmp(Region::Us915, 1700).unwrap();
+
+        assert!(Protocol::make_gwmp(Region::Us915, 0).is_err());
+        assert!(Protocol::make_gwmp(Region::Us915, 65_536).is_err());
+
+        // same port for another region
+        let map = Protocol::make_gwmp_mapping(Region::Eu868, 1700);
+        assert!(gwmp.gwmp_add_mapping(map).is_err());
+
+        // replacing the port of an existing region
+        let map = Protocol::make_gwmp_mapping(Region::Us915, 1701);
+        assert!(gwmp.gwmp_add_mapping(map).is_ok());
+        let map = Protocol::make_gwmp_mapping(Region::Eu868, 1700);
+        assert!(gwmp.gwmp_add_mapping(map).is_ok());
+
+        assert_eq!(
+            Protocol::Gwmp(Gwmp {
+                mapping: BTreeMap::from([(Region::Us915, 1701), (Region::Eu868, 1700)])
+            }),
+            gwmp
+        );
+    }
+
+    #[test]
+    fn http_validation() {
+        let http = Http::new(FlowType::Sync, 250, "/fns".into(), None).unwrap();
+        assert_eq!(
+            Http {
+                flow_type: FlowType::Sync,
+                dedupe_timeout: 250,
+                path: "/fns".into(),
+                auth_header: "".into(),
+            },
+            http
+        );
+
+        assert!(Http::new(FlowType::Async, 250, "/f ns".into(), None).is_err());
+        assert!(Http::new(
+            FlowType::Async,
+            250,
+            "/fns".into(),
+            Some("a\r\nb: c".into())
+        )
+        .is_err());
+
+        let updated = http.update(None, Some(500), None, None).unwrap();
+        assert_eq!(500, updated.dedupe_timeout);
+        assert_eq!(
+            Http {
+                dedupe_timeout: 250,
+                ..updated.clone()
+            },
+            http
+        );
+        assert!(http.update(None, None, Some("/f ns".into()), None).is_err());
+
+        let protocol = Protocol::Http(http.clone());
+        assert!(protocol.is_http());
+        assert_eq!(Some(&http), protocol.as_http());
+        assert_eq!(None, protocol.as_gwmp());
+    }
+
+    #[test]
+    fn gwmp_all_regions() {
+        let gwmp = Protocol::make_gwmp_all_regions(1700, 2).unwrap();
+        let Protocol::Gwmp(Gwmp { mapping }) = gwmp else {
+            panic!("expected gwmp protocol");
+        };
+        assert_eq!(Region::value_variants().len(), mapping.len());
+        assert_eq!(Some(&1700), mapping.get(&Region::Us915));
+        assert_eq!(Some(&1702), mapping.get(&Region::Eu868));
+
+        // every region on the same port
+        assert!(Protocol::make_gwmp_all_regions(1700, 0).is_err());
+        // runs past the last valid port
+        assert!(Protocol::make_gwmp_all_regions(65_530, 1).is_err());
+    }
+
+    #[test]
+    fn gwmp_presets() {
+        let user_presets: GwmpPresets =
+            serde_json::from_str(r#"{"mine": {"US915": 1800, "EU868": 1801}}"#).unwrap();
+
+        assert_eq!(
+            Protocol::Gwmp(Gwmp {
+                mapping: BTreeMap::from([(Region::Us915, 1800), (Region::Eu868, 1801)])
+            }),
+            Protocol::from_gwmp_preset("mine", &user_presets).unwrap()
+        );
+        assert_eq!(
+            Protocol::make_gwmp(Region::Eu868, 1700).unwrap(),
+            Protocol::from_gwmp_preset("chirpstack-default", &user_presets).unwrap()
+        );
+        assert_eq!(
+            Protocol::make_gwmp_all_regions(1700, 1).unwrap(),
+            Protocol::from_gwmp_preset("sequential", &user_presets).unwrap()
+        );
+        let Protocol::Gwmp(single) =
+            Protocol::from_gwmp_preset("single-port", &user_presets).unwrap()
+        else {
+            panic!("single-port is a gwmp preset");
+        };
+        assert_eq!(Region::value_variants().len(), single.mapping.len());
+        assert!(single.mapping.values().all(|port| *port == 1700));
+        assert!(Gwmp::single_port(0).is_err());
+        assert!(Protocol::from_gwmp_preset("unknown", &user_presets).is_err());
+    }
+
+    #[test]
+    fn gwmp_history() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.child("gwmp_history.json");
+        assert!(read_gwmp_history(&path).unwrap().is_empty());
+
+        let history = GwmpHistory::from([(
+            RouteId::new_unchecked("route-id"),
+            BTreeMap::from([(Region::Us915, 1700), (Region::Eu868, 1701)]),
+        )]);
+        write_gwmp_history(&path, &history).unwrap();
+        assert_eq!(history, read_gwmp_history(&path).unwrap());
+    }
 }
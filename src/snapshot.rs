@@ -0,0 +1,513 @@
+//! Snapshot of an Org on disk, and the plan to restore the config service to
+//! it.
+//!
+//! A snapshot directory holds `routes/*.json`, one `route export` per Route,
+//! and `skfs.json` as written by `skf export`. A desired state file holds
+//! the same in one JSON, YAML, or TOML document, for `apply`.
+use crate::{
+    diff::{self, FieldChange},
+    hex_field::HexEui,
+    route::{Route, RouteFileFormat},
+    subnet::DevaddrConstraint,
+    sync_delta, DevaddrRange, Eui, ExportedFilter, Oui, Result, RouteExport, SessionKeyFilter,
+    SessionKeyFilterExport,
+};
+use anyhow::{anyhow, Context};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{fs, path::Path};
+
+pub const ROUTES_DIR: &str = "routes";
+pub const SKFS_FILE: &str = "skfs.json";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrgSnapshot {
+    pub oui: Oui,
+    pub routes: Vec<RouteExport>,
+    pub filters: Vec<SessionKeyFilter>,
+}
+
+impl OrgSnapshot {
+    /// Every Route and the filters must belong to `oui`. A snapshot without
+    /// `skfs.json` has no filters.
+    pub fn from_dir(dir: &Path, oui: Oui) -> Result<Self> {
+        let routes_dir = dir.join(ROUTES_DIR);
+        let mut paths = fs::read_dir(&routes_dir)
+            .with_context(|| format!("reading {}", routes_dir.display()))?
+            .map(|entry| Ok(entry?.path()))
+            .collect::<Result<Vec<_>>>()?;
+        paths.retain(|path| path.extension().map_or(false, |ext| ext == "json"));
+        paths.sort();
+
+        let mut routes = vec![];
+        for path in paths {
+            let json =
+                fs::read_to_string(&path).with_context(|| format!("reading {}", path.display()))?;
+            let export = RouteExport::from_json(&json)
+                .with_context(|| format!("parsing {}", path.display()))?;
+            if export.route.oui != oui {
+                return Err(anyhow!(
+                    "{} is a route of oui {}, not {oui}",
+                    path.display(),
+                    export.route.oui
+                ));
+            }
+            routes.push(export);
+        }
+
+        let skfs = dir.join(SKFS_FILE);
+        let filters = if skfs.exists() {
+            let json =
+                fs::read_to_string(&skfs).with_context(|| format!("reading {}", skfs.display()))?;
+            let export = SessionKeyFilterExport::from_json(&json)
+                .with_context(|| format!("parsing {}", skfs.display()))?;
+            if export.oui != oui {
+                return Err(anyhow!(
+                    "{} holds filters of oui {}, not {oui}",
+                    skfs.display(),
+                    export.oui
+                ));
+            }
+            export.into_filters()
+        } else {
+            vec![]
+        };
+
+        Ok(Self {
+            oui,
+            routes,
+            filters,
+        })
+    }
+}
+
+/// Desired state file of an Org:
+///
+/// ```yaml
+/// oui: 4
+/// routes:
+///   - route: { id: "", net_id: C00053, oui: 4, ... }
+///     euis: [{ app_eui: "0000000000000001", dev_eui: "0000000000000002" }]
+///     devaddrs: [{ start_addr: "48000000", end_addr: "48000007" }]
+/// skfs: [{ devaddr: "48000001", session_key: "..." }]
+/// ```
+#[derive(Debug, Deserialize)]
+struct DesiredState {
+    oui: Oui,
+    #[serde(default)]
+    routes: Vec<DesiredRoute>,
+    #[serde(default)]
+    skfs: Vec<ExportedFilter>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DesiredRoute {
+    route: Route,
+    #[serde(default)]
+    euis: Vec<EuiPair>,
+    #[serde(default)]
+    devaddrs: Vec<DevaddrConstraint>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EuiPair {
+    app_eui: HexEui,
+    dev_eui: HexEui,
+}
+
+impl OrgSnapshot {
+    /// Read a desired state file. Routes with an empty id are created, unless
+    /// the Org already has one with the same `net_id` and server.
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let data =
+            fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+        let state: DesiredState = match RouteFileFormat::of(path) {
+            RouteFileFormat::Json => serde_json::from_str(&data).map_err(anyhow::Error::from),
+            RouteFileFormat::Yaml => serde_yaml::from_str(&data).map_err(anyhow::Error::from),
+            RouteFileFormat::Toml => toml::from_str(&data).map_err(anyhow::Error::from),
+        }
+        .with_context(|| format!("parsing {}", path.display()))?;
+
+        let mut routes = vec![];
+        for DesiredRoute {
+            route,
+            euis,
+            devaddrs,
+        } in state.routes
+        {
+            if route.oui != state.oui {
+                return Err(anyhow!(
+                    "route {} is a route of oui {}, not {}",
+                    route.id,
+                    route.oui,
+                    state.oui
+                ));
+            }
+            let euis = euis
+                .into_iter()
+                .map(|pair| Eui::new(route.id.clone(), pair.app_eui, pair.dev_eui))
+                .collect::<Result<_>>()?;
+            let devaddrs = devaddrs
+                .into_iter()
+                .map(|range| DevaddrRange::new(route.id.clone(), range.start_addr, range.end_addr))
+                .collect::<Result<_>>()?;
+            routes.push(RouteExport::new(route, euis, devaddrs));
+        }
+
+        Ok(Self {
+            oui: state.oui,
+            routes,
+            filters: state
+                .skfs
+                .into_iter()
+                .map(|filter| SessionKeyFilter::new(state.oui, filter.devaddr, filter.session_key))
+                .collect(),
+        })
+    }
+}
+
+/// What to do with one Route.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RouteAction {
+    /// In the snapshot only, created with a new id.
+    Create,
+    /// In both, with differing fields, EUI pairs, or Devaddr Ranges.
+    Update,
+    /// Live only, deleted.
+    Remove,
+    Unchanged,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Delta<T> {
+    pub add: Vec<T>,
+    pub remove: Vec<T>,
+}
+
+impl<T> Delta<T> {
+    fn counts(&self) -> serde_json::Value {
+        json!({ "add": self.add.len(), "remove": self.remove.len() })
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct RoutePlan {
+    pub action: RouteAction,
+    /// The Route as it should be, the live one for `Remove`.
+    pub route: Route,
+    pub fields: Vec<FieldChange>,
+    pub euis: Delta<Eui>,
+    pub devaddrs: Delta<DevaddrRange>,
+}
+
+/// A live Route with its EUI pairs and Devaddr Ranges.
+pub type LiveRoute = (Route, Vec<Eui>, Vec<DevaddrRange>);
+
+#[derive(Debug, PartialEq)]
+pub struct RestorePlan {
+    pub oui: Oui,
+    pub routes: Vec<RoutePlan>,
+    pub filters: Delta<SessionKeyFilter>,
+}
+
+impl RestorePlan {
+    /// Routes are matched by id. A Route without one, as in a desired state
+    /// file, is matched to a live Route with the same `net_id` and server
+    /// host and port, so applying the same file again changes nothing.
+    pub fn new(
+        snapshot: OrgSnapshot,
+        mut live: Vec<LiveRoute>,
+        live_filters: Vec<SessionKeyFilter>,
+    ) -> Result<Self> {
+        let mut routes = vec![];
+        for RouteExport {
+            route,
+            euis,
+            devaddrs,
+            ..
+        } in snapshot.routes
+        {
+            let position = live.iter().position(|(live_route, _, _)| {
+                if route.id.is_empty() {
+                    live_route.net_id == route.net_id
+                        && live_route.server.host == route.server.host
+                        && live_route.server.port == route.server.port
+                } else {
+                    live_route.id == route.id
+                }
+            });
+            let Some(position) = position else {
+                routes.push(RoutePlan {
+                    action: RouteAction::Create,
+                    fields: vec![],
+                    euis: Delta {
+                        add: euis,
+                        remove: vec![],
+                    },
+                    devaddrs: Delta {
+                        add: devaddrs,
+                        remove: vec![],
+                    },
+                    route,
+                });
+                continue;
+            };
+            let (live_route, live_euis, live_devaddrs) = live.remove(position);
+            let route = Route {
+                id: live_route.id.clone(),
+                ..route
+            };
+            let euis: Vec<Eui> = euis
+                .into_iter()
+                .map(|eui| Eui {
+                    route_id: route.id.clone(),
+                    ..eui
+                })
+                .collect();
+            let devaddrs: Vec<DevaddrRange> = devaddrs
+                .into_iter()
+                .map(|range| DevaddrRange {
+                    route_id: route.id.clone(),
+                    ..range
+                })
+                .collect();
+            let fields = diff::fields(
+                &serde_json::to_value(&live_route)?,
+                &serde_json::to_value(&route)?,
+            );
+            let (add, remove) = sync_delta(euis, live_euis);
+            let euis = Delta { add, remove };
+            let (add, remove) = sync_delta(devaddrs, live_devaddrs);
+            let devaddrs = Delta { add, remove };
+            let unchanged = fields.is_empty()
+                && euis.add.is_empty()
+                && euis.remove.is_empty()
+                && devaddrs.add.is_empty()
+                && devaddrs.remove.is_empty();
+            routes.push(RoutePlan {
+                action: if unchanged {
+                    RouteAction::Unchanged
+                } else {
+                    RouteAction::Update
+                },
+                route,
+                fields,
+                euis,
+                devaddrs,
+            });
+        }
+
+        for (route, euis, devaddrs) in live {
+            routes.push(RoutePlan {
+                action: RouteAction::Remove,
+                route,
+                fields: vec![],
+                euis: Delta {
+                    add: vec![],
+                    remove: euis,
+                },
+                devaddrs: Delta {
+                    add: vec![],
+                    remove: devaddrs,
+                },
+            });
+        }
+
+        let (add, remove) = sync_delta(snapshot.filters, live_filters);
+        Ok(Self {
+            oui: snapshot.oui,
+            routes,
+            filters: Delta { add, remove },
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.filters.add.is_empty()
+            && self.filters.remove.is_empty()
+            && self
+                .routes
+                .iter()
+                .all(|plan| plan.action == RouteAction::Unchanged)
+    }
+
+    /// Counts of the EUI pairs, Devaddr Ranges, and filters to add and
+    /// remove, session keys are left out.
+    pub fn summary(&self) -> serde_json::Value {
+        let routes: Vec<serde_json::Value> = self
+            .routes
+            .iter()
+            .map(|plan| {
+                json!({
+                    "route_id": plan.route.id,
+                    "action": plan.action,
+                    "fields": plan.fields,
+                    "euis": plan.euis.counts(),
+                    "devaddrs": plan.devaddrs.counts(),
+                })
+            })
+            .collect();
+        json!({
+            "oui": self.oui,
+            "routes": routes,
+            "skfs": self.filters.counts(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{OrgSnapshot, RestorePlan, RouteAction};
+    use crate::{
+        hex_field, route::RouteId, DevaddrRange, Eui, PrettyJson, Route, RouteExport,
+        SessionKeyFilter, SessionKeyFilterExport,
+    };
+    use temp_dir::TempDir;
+
+    fn route(id: &str, max_copies: u32) -> Route {
+        let mut route = Route::new(hex_field::net_id(0xC00053), 4, max_copies);
+        route.id = RouteId::new_unchecked(id);
+        route
+    }
+
+    fn eui(id: &str, dev_eui: u64) -> Eui {
+        Eui::new(
+            RouteId::new_unchecked(id),
+            hex_field::eui(1),
+            hex_field::eui(dev_eui),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn restore_plan() {
+        let dir = TempDir::new().unwrap();
+        std::fs::create_dir(dir.child("routes")).unwrap();
+        let range = DevaddrRange::new(
+            RouteId::new_unchecked("kept"),
+            hex_field::devaddr(0x48000000),
+            hex_field::devaddr(0x48000003),
+        )
+        .unwrap();
+        let kept = RouteExport::new(route("kept", 2), vec![eui("kept", 2)], vec![range.clone()]);
+        let created = RouteExport::new(route("created", 1), vec![eui("created", 3)], vec![]);
+        for (name, export) in [("kept", &kept), ("created", &created)] {
+            std::fs::write(
+                dir.child(format!("routes/{name}.json")),
+                export.pretty_json().unwrap(),
+            )
+            .unwrap();
+        }
+        let filter = SessionKeyFilter::new(4, hex_field::devaddr(0x48000001), "key".to_string());
+        std::fs::write(
+            dir.child("skfs.json"),
+            SessionKeyFilterExport::new(4, vec![filter.clone()])
+                .pretty_json()
+                .unwrap(),
+        )
+        .unwrap();
+
+        let snapshot = OrgSnapshot::from_dir(dir.path(), 4).unwrap();
+        assert_eq!(2, snapshot.routes.len());
+        assert!(OrgSnapshot::from_dir(dir.path(), 5).is_err());
+
+        let live = vec![
+            (
+                route("kept", 1),
+                vec![eui("kept", 2), eui("kept", 9)],
+                vec![range],
+            ),
+            (route("removed", 1), vec![eui("removed", 4)], vec![]),
+        ];
+        let plan = RestorePlan::new(snapshot, live, vec![]).unwrap();
+        let actions: Vec<(&str, RouteAction)> = plan
+            .routes
+            .iter()
+            .map(|plan| (plan.route.id.as_str(), plan.action))
+            .collect();
+        assert_eq!(
+            vec![
+                ("created", RouteAction::Create),
+                ("kept", RouteAction::Update),
+                ("removed", RouteAction::Remove),
+            ],
+            actions
+        );
+        let kept_plan = &plan.routes[1];
+        assert_eq!("max_copies", kept_plan.fields[0].field);
+        assert_eq!(vec![eui("kept", 9)], kept_plan.euis.remove);
+        assert!(kept_plan.devaddrs.add.is_empty());
+        assert_eq!(vec![filter], plan.filters.add);
+        assert!(!plan.is_empty());
+        assert_eq!(1, plan.summary()["skfs"]["add"]);
+    }
+
+    #[test]
+    fn desired_state_file() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.child("config.yaml");
+        std::fs::write(
+            &path,
+            r#"
+oui: 4
+routes:
+  - route:
+      id: ""
+      net_id: C00053
+      oui: 4
+      server:
+        host: lns.example.com
+        port: 8080
+        protocol: null
+      max_copies: 1
+      active: true
+      locked: false
+    euis:
+      - { app_eui: "0000000000000001", dev_eui: "0000000000000002" }
+    devaddrs:
+      - { start_addr: "48000000", end_addr: "48000007" }
+skfs:
+  - { devaddr: "48000001", session_key: "key" }
+"#,
+        )
+        .unwrap();
+
+        let state = OrgSnapshot::from_file(&path).unwrap();
+        assert_eq!(4, state.oui);
+        assert_eq!(1, state.routes[0].euis.len());
+        assert_eq!(
+            hex_field::devaddr(0x48000007),
+            state.routes[0].devaddrs[0].end_addr
+        );
+        assert_eq!(1, state.filters.len());
+
+        let plan = RestorePlan::new(
+            OrgSnapshot::from_file(&path).unwrap(),
+            vec![(route("old", 1), vec![], vec![])],
+            vec![],
+        )
+        .unwrap();
+        assert_eq!(RouteAction::Create, plan.routes[0].action);
+        assert_eq!(RouteAction::Remove, plan.routes[1].action);
+
+        // The Route created by the first apply is the one of the file.
+        let created = state.routes[0].route.clone();
+        let created = Route {
+            id: RouteId::new_unchecked("created"),
+            ..created
+        };
+        let live = vec![(
+            created,
+            vec![eui("created", 2)],
+            vec![DevaddrRange::new(
+                RouteId::new_unchecked("created"),
+                hex_field::devaddr(0x48000000),
+                hex_field::devaddr(0x48000007),
+            )
+            .unwrap()],
+        )];
+        let live_filters = state.filters.clone();
+        let plan = RestorePlan::new(state, live, live_filters).unwrap();
+        assert!(plan.is_empty(), "{}", plan.summary());
+    }
+}
@@ -5,6 +5,7 @@ use std::net;
 
 use crate::{
     hex_field::{self, HexDevAddr},
+    route::RouteId,
     DevaddrRange, Result,
 };
 
@@ -73,6 +74,210 @@ impl DevaddrConstraint {
             }
         }
     }
+
+    /// Number of devaddrs in the range, both ends inclusive.
+    pub fn len(&self) -> u64 {
+        self.end_addr
+            .0
+            .saturating_add(1)
+            .saturating_sub(self.start_addr.0)
+    }
+
+    /// Only true for a range built with `end_addr` before `start_addr`.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn contains(&self, addr: HexDevAddr) -> bool {
+        self.start_addr <= addr && addr <= self.end_addr
+    }
+
+    /// Devaddrs in both ranges, `None` if they do not overlap.
+    pub fn intersect(&self, other: &DevaddrConstraint) -> Option<DevaddrConstraint> {
+        let start_addr = self.start_addr.0.max(other.start_addr.0);
+        let end_addr = self.end_addr.0.min(other.end_addr.0);
+        (start_addr <= end_addr).then(|| DevaddrConstraint {
+            start_addr: start_addr.into(),
+            end_addr: end_addr.into(),
+        })
+    }
+
+    /// Split into `start_addr..addr` and `addr..=end_addr`.
+    ///
+    /// `None` unless both halves would be non-empty.
+    pub fn split_at(&self, addr: HexDevAddr) -> Option<(DevaddrConstraint, DevaddrConstraint)> {
+        if addr <= self.start_addr || addr > self.end_addr {
+            return None;
+        }
+        Some((
+            DevaddrConstraint {
+                start_addr: self.start_addr,
+                end_addr: (addr.0 - 1).into(),
+            },
+            DevaddrConstraint {
+                start_addr: addr,
+                end_addr: self.end_addr,
+            },
+        ))
+    }
+
+    /// Split into `parts` consecutive ranges of the same size, give or take
+    /// one devaddr when the length does not divide evenly.
+    ///
+    /// A range aligned to its power of two length split into a power of two
+    /// `parts` gives parts that are each a single subnet.
+    pub fn split(&self, parts: u32) -> Result<Vec<DevaddrConstraint>> {
+        let len = self.len();
+        if parts == 0 || u64::from(parts) > len {
+            return Err(anyhow!("cannot split {} devaddrs into {parts} parts", len));
+        }
+        let boundary = |part: u64| self.start_addr.0 + part * len / u64::from(parts);
+        Ok((0..u64::from(parts))
+            .map(|part| DevaddrConstraint {
+                start_addr: boundary(part).into(),
+                end_addr: (boundary(part + 1) - 1).into(),
+            })
+            .collect())
+    }
+
+    /// The parts of this constraint not covered by any of the `used` ranges.
+    pub fn gaps(&self, used: &[DevaddrConstraint]) -> Vec<DevaddrConstraint> {
+        let mut used: Vec<_> = used
+            .iter()
+            .filter(|range| range.end_addr >= self.start_addr && range.start_addr <= self.end_addr)
+            .collect();
+        used.sort_by_key(|range| range.start_addr.0);
+
+        let mut gaps = vec![];
+        let mut next = self.start_addr.0;
+        for range in used {
+            if range.start_addr.0 > next {
+                gaps.push(DevaddrConstraint {
+                    start_addr: next.into(),
+                    end_addr: (range.start_addr.0 - 1).into(),
+                });
+            }
+            next = next.max(range.end_addr.0.saturating_add(1));
+        }
+        if next <= self.end_addr.0 {
+            gaps.push(DevaddrConstraint {
+                start_addr: next.into(),
+                end_addr: self.end_addr,
+            });
+        }
+        gaps
+    }
+}
+
+/// Merge overlapping and adjacent ranges, sorted by start address.
+pub fn merge_ranges(mut ranges: Vec<DevaddrConstraint>) -> Vec<DevaddrConstraint> {
+    ranges.sort_by_key(|range| range.start_addr.0);
+
+    let mut merged: Vec<DevaddrConstraint> = vec![];
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start_addr.0 <= last.end_addr.0.saturating_add(1) => {
+                if range.end_addr > last.end_addr {
+                    last.end_addr = range.end_addr;
+                }
+            }
+            _ => merged.push(range),
+        }
+    }
+    merged
+}
+
+/// Minimal set of subnets covering all `ranges`.
+pub fn aggregate_subnets(ranges: Vec<DevaddrConstraint>) -> Vec<DevaddrSubnet> {
+    merge_ranges(ranges)
+        .into_iter()
+        .map(DevaddrConstraint::to_subnet)
+        .collect()
+}
+
+/// One `<base>/<prefix>` subnet per line, in the order given.
+pub fn cidr_lines(subnets: &[DevaddrSubnet]) -> String {
+    subnets
+        .iter()
+        .flat_map(|subnet| subnet.subnets.iter().cloned())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Draw each Org constraint as a bar of at most `width` cells, marking the
+/// Routes whose ranges fall in each cell.
+///
+/// A cell is `.` when unassigned, the letter of a Route in the legend, or `!`
+/// when more than one Route holds devaddrs in it.
+pub fn usage_map(
+    constraints: &[DevaddrConstraint],
+    routes: &[(RouteId, Vec<DevaddrConstraint>)],
+    width: u64,
+) -> String {
+    let labels: Vec<char> = ('A'..='Z').chain('a'..='z').chain('0'..='9').collect();
+    let label = |index: usize| labels.get(index).copied().unwrap_or('?');
+    let assigned: Vec<DevaddrConstraint> = routes
+        .iter()
+        .flat_map(|(_, ranges)| ranges.iter().cloned())
+        .collect();
+
+    let mut lines = vec![];
+    let mut overlaps = false;
+    for constraint in constraints {
+        let cells = width.min(constraint.len()).max(1);
+        let bar: String = (0..cells)
+            .map(|cell| {
+                let start = constraint.start_addr.0 + cell * constraint.len() / cells;
+                let end = constraint.start_addr.0 + (cell + 1) * constraint.len() / cells - 1;
+                let cell = DevaddrConstraint {
+                    start_addr: start.into(),
+                    end_addr: end.into(),
+                };
+                let mut holders = routes.iter().enumerate().filter(|(_, (_, ranges))| {
+                    ranges.iter().any(|range| range.intersect(&cell).is_some())
+                });
+                match (holders.next(), holders.next()) {
+                    (None, _) => '.',
+                    (Some((index, _)), None) => label(index),
+                    (Some(_), Some(_)) => {
+                        overlaps = true;
+                        '!'
+                    }
+                }
+            })
+            .collect();
+        let free: u64 = constraint.gaps(&assigned).iter().map(|gap| gap.len()).sum();
+        lines.push(format!(
+            "{}-{} |{bar}| {}/{} assigned",
+            constraint.start_addr,
+            constraint.end_addr,
+            constraint.len() - free,
+            constraint.len()
+        ));
+    }
+
+    lines.push(String::new());
+    for (index, (route_id, ranges)) in routes.iter().enumerate() {
+        let devaddrs: u64 = ranges.iter().map(DevaddrConstraint::len).sum();
+        lines.push(format!("{} {route_id} ({devaddrs} devaddrs)", label(index)));
+    }
+    lines.push(". unassigned".to_string());
+    if overlaps {
+        lines.push("! more than one route".to_string());
+    }
+    lines.join("\n")
+}
+
+/// Read one `<start_addr>-<end_addr>` range per line.
+///
+/// Blank lines and lines starting with `#` are skipped.
+pub fn parse_ranges(contents: &str) -> Result<Vec<DevaddrConstraint>> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(hex_field::validate_devaddr_range)
+        .collect()
 }
 
 impl From<DevaddrRange> for DevaddrConstraint {
@@ -88,6 +293,68 @@ impl DevaddrRange {
     pub fn to_subnet(self) -> DevaddrSubnet {
         DevaddrConstraint::from(self).to_subnet()
     }
+
+    fn constraint(&self) -> DevaddrConstraint {
+        DevaddrConstraint {
+            start_addr: self.start_addr,
+            end_addr: self.end_addr,
+        }
+    }
+
+    fn with_constraint(&self, constraint: DevaddrConstraint) -> DevaddrRange {
+        DevaddrRange {
+            route_id: self.route_id.clone(),
+            start_addr: constraint.start_addr,
+            end_addr: constraint.end_addr,
+        }
+    }
+
+    /// See [`DevaddrConstraint::len`].
+    pub fn len(&self) -> u64 {
+        self.constraint().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.constraint().is_empty()
+    }
+
+    pub fn contains(&self, addr: HexDevAddr) -> bool {
+        self.constraint().contains(addr)
+    }
+
+    /// See [`DevaddrConstraint::intersect`], the result keeps this route_id.
+    pub fn intersect(&self, other: &DevaddrConstraint) -> Option<DevaddrRange> {
+        self.constraint()
+            .intersect(other)
+            .map(|constraint| self.with_constraint(constraint))
+    }
+
+    /// See [`DevaddrConstraint::split_at`], both halves keep this route_id.
+    pub fn split_at(&self, addr: HexDevAddr) -> Option<(DevaddrRange, DevaddrRange)> {
+        self.constraint()
+            .split_at(addr)
+            .map(|(low, high)| (self.with_constraint(low), self.with_constraint(high)))
+    }
+}
+
+/// Every devaddr in the range, in order.
+impl IntoIterator for DevaddrConstraint {
+    type Item = HexDevAddr;
+    type IntoIter = std::iter::Map<std::ops::RangeInclusive<u64>, fn(u64) -> HexDevAddr>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        (self.start_addr.0..=self.end_addr.0).map(HexDevAddr::from as fn(u64) -> HexDevAddr)
+    }
+}
+
+/// Every devaddr in the range, in order.
+impl IntoIterator for DevaddrRange {
+    type Item = HexDevAddr;
+    type IntoIter = <DevaddrConstraint as IntoIterator>::IntoIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        DevaddrConstraint::from(self).into_iter()
+    }
 }
 
 impl HexDevAddr {
@@ -111,8 +378,10 @@ impl From<net::Ipv4Addr> for HexDevAddr {
 
 #[cfg(test)]
 mod tests {
-    use super::DevaddrSubnet;
-    use crate::{hex_field, subnet::DevaddrConstraint};
+    use super::{
+        aggregate_subnets, cidr_lines, merge_ranges, parse_ranges, usage_map, DevaddrSubnet,
+    };
+    use crate::{hex_field, route::RouteId, subnet::DevaddrConstraint, DevaddrRange};
     use pretty_assertions::assert_eq;
 
     #[test]
@@ -166,6 +435,261 @@ mod tests {
         )
     }
 
+    fn range(start: u64, end: u64) -> DevaddrConstraint {
+        DevaddrConstraint::new(hex_field::devaddr(start), hex_field::devaddr(end)).unwrap()
+    }
+
+    #[test]
+    fn constraint_len_and_contains() {
+        let block = range(0x48000000, 0x4800003F);
+        assert_eq!(64, block.len());
+        assert!(!block.is_empty());
+        assert_eq!(1, range(0x48000000, 0x48000000).len());
+        assert_eq!(1 << 32, range(0, 0xFFFFFFFF).len());
+
+        let backwards = DevaddrConstraint {
+            start_addr: hex_field::devaddr(0x48000001),
+            end_addr: hex_field::devaddr(0x48000000),
+        };
+        assert_eq!(0, backwards.len());
+        assert!(backwards.is_empty());
+
+        assert!(block.contains(hex_field::devaddr(0x48000000)));
+        assert!(block.contains(hex_field::devaddr(0x4800003F)));
+        assert!(!block.contains(hex_field::devaddr(0x47FFFFFF)));
+        assert!(!block.contains(hex_field::devaddr(0x48000040)));
+    }
+
+    #[test]
+    fn constraint_intersect() {
+        let block = range(0x48000000, 0x4800003F);
+        assert_eq!(
+            Some(range(0x48000020, 0x4800003F)),
+            block.intersect(&range(0x48000020, 0x4800007F))
+        );
+        assert_eq!(
+            Some(range(0x48000010, 0x4800001F)),
+            block.intersect(&range(0x48000010, 0x4800001F))
+        );
+        assert_eq!(Some(block.clone()), block.intersect(&range(0, 0xFFFFFFFF)));
+        assert_eq!(
+            Some(range(0x4800003F, 0x4800003F)),
+            block.intersect(&range(0x4800003F, 0x48000040))
+        );
+        assert_eq!(None, block.intersect(&range(0x48000040, 0x4800007F)));
+        assert_eq!(
+            block.intersect(&range(0x48000020, 0x4800007F)),
+            range(0x48000020, 0x4800007F).intersect(&block)
+        );
+    }
+
+    #[test]
+    fn constraint_split_at() {
+        let block = range(0x48000000, 0x4800003F);
+        assert_eq!(
+            Some((range(0x48000000, 0x4800001F), range(0x48000020, 0x4800003F))),
+            block.split_at(hex_field::devaddr(0x48000020))
+        );
+        assert_eq!(
+            Some((range(0x48000000, 0x4800003E), range(0x4800003F, 0x4800003F))),
+            block.split_at(hex_field::devaddr(0x4800003F))
+        );
+        assert_eq!(None, block.split_at(hex_field::devaddr(0x48000000)));
+        assert_eq!(None, block.split_at(hex_field::devaddr(0x48000040)));
+        assert_eq!(None, block.split_at(hex_field::devaddr(0x47FFFFFF)));
+    }
+
+    #[test]
+    fn devaddr_range_arithmetic() {
+        let devaddr_range = DevaddrRange::new(
+            RouteId::new_unchecked("route-id"),
+            hex_field::devaddr(0x48000000),
+            hex_field::devaddr(0x4800003F),
+        )
+        .unwrap();
+        assert_eq!(64, devaddr_range.len());
+        assert!(devaddr_range.contains(hex_field::devaddr(0x48000010)));
+
+        let overlap = devaddr_range
+            .intersect(&range(0x48000030, 0x4800004F))
+            .unwrap();
+        assert_eq!("route-id", overlap.route_id.as_str());
+        assert_eq!(hex_field::devaddr(0x48000030), overlap.start_addr);
+        assert_eq!(hex_field::devaddr(0x4800003F), overlap.end_addr);
+
+        let (low, high) = devaddr_range
+            .split_at(hex_field::devaddr(0x48000008))
+            .unwrap();
+        assert_eq!("route-id", low.route_id.as_str());
+        assert_eq!("route-id", high.route_id.as_str());
+        assert_eq!(8, low.len());
+        assert_eq!(56, high.len());
+    }
+
+    #[test]
+    fn iterate_devaddrs() {
+        let devaddrs: Vec<_> = range(0x480000FE, 0x48000101).into_iter().collect();
+        assert_eq!(
+            vec![
+                hex_field::devaddr(0x480000FE),
+                hex_field::devaddr(0x480000FF),
+                hex_field::devaddr(0x48000100),
+                hex_field::devaddr(0x48000101),
+            ],
+            devaddrs
+        );
+
+        let devaddr_range = DevaddrRange::new(
+            RouteId::new_unchecked("route-id"),
+            hex_field::devaddr(0x48000000),
+            hex_field::devaddr(0x4800003F),
+        )
+        .unwrap();
+        assert_eq!(
+            devaddr_range.len(),
+            devaddr_range.clone().into_iter().count() as u64
+        );
+        assert_eq!(
+            Some(hex_field::devaddr(0x4800003F)),
+            devaddr_range.into_iter().last()
+        );
+    }
+
+    #[test]
+    fn constraint_gaps() {
+        let constraint = hex_field::devaddr(0x48_00_00_00).to_range(64);
+        let used = vec![
+            hex_field::devaddr(0x48_00_00_10).to_range(8),
+            hex_field::devaddr(0x48_00_00_00).to_range(8),
+            // overlaps the previous range
+            hex_field::devaddr(0x48_00_00_04).to_range(8),
+            // outside the constraint
+            hex_field::devaddr(0x49_00_00_00).to_range(8),
+        ];
+        assert_eq!(
+            vec![
+                DevaddrConstraint {
+                    start_addr: hex_field::devaddr(0x48_00_00_0C),
+                    end_addr: hex_field::devaddr(0x48_00_00_0F),
+                },
+                DevaddrConstraint {
+                    start_addr: hex_field::devaddr(0x48_00_00_18),
+                    end_addr: hex_field::devaddr(0x48_00_00_3F),
+                },
+            ],
+            constraint.gaps(&used)
+        );
+
+        assert_eq!(vec![constraint.clone()], constraint.gaps(&[]));
+        assert!(constraint.gaps(&[constraint.clone()]).is_empty());
+    }
+
+    #[test]
+    fn split_constraint() {
+        let constraint = DevaddrConstraint::new(
+            hex_field::devaddr(0x48000000),
+            hex_field::devaddr(0x4800003F),
+        )
+        .unwrap();
+        let parts = constraint.split(4).unwrap();
+        assert_eq!(
+            vec!["48000000/28", "48000010/28", "48000020/28", "48000030/28"],
+            parts
+                .into_iter()
+                .flat_map(|part| part.to_subnet().subnets)
+                .collect::<Vec<_>>()
+        );
+
+        let uneven = DevaddrConstraint::new(hex_field::devaddr(0), hex_field::devaddr(9)).unwrap();
+        let lens: Vec<u64> = uneven
+            .split(3)
+            .unwrap()
+            .iter()
+            .map(|part| part.len())
+            .collect();
+        assert_eq!(vec![3, 3, 4], lens);
+        assert_eq!(vec![uneven.clone()], uneven.split(1).unwrap());
+        assert!(uneven.split(0).is_err());
+        assert!(uneven.split(11).is_err());
+    }
+
+    #[test]
+    fn devaddr_usage_map() {
+        let constraints = [range(0x48000000, 0x4800000F)];
+        let routes = [
+            (
+                RouteId::new_unchecked("route-a"),
+                vec![range(0x48000000, 0x48000007)],
+            ),
+            (
+                RouteId::new_unchecked("route-b"),
+                vec![range(0x48000006, 0x48000009)],
+            ),
+        ];
+
+        assert_eq!(
+            "48000000-4800000F |AAAAAA!!BB......| 10/16 assigned\n\
+             \n\
+             A route-a (8 devaddrs)\n\
+             B route-b (4 devaddrs)\n\
+             . unassigned\n\
+             ! more than one route",
+            usage_map(&constraints, &routes, 64)
+        );
+        assert!(usage_map(&constraints, &routes, 8).starts_with("48000000-4800000F |AAA!B...|"));
+        assert!(!usage_map(&constraints, &routes[..1], 8).contains('!'));
+    }
+
+    #[test]
+    fn aggregate_ranges() {
+        let ranges = parse_ranges(
+            "# two adjacent blocks and an overlapping one
+            48000000-4800001F
+
+            48000020-4800003F
+            48000010-48000017
+            48000080-48000087",
+        )
+        .unwrap();
+        assert_eq!(4, ranges.len());
+
+        let subnets: Vec<Vec<String>> = aggregate_subnets(ranges)
+            .into_iter()
+            .map(|subnet| subnet.subnets)
+            .collect();
+        assert_eq!(
+            vec![
+                vec!["48000000/26".to_string()],
+                vec!["48000080/29".to_string()]
+            ],
+            subnets
+        );
+        assert_eq!(
+            "48000000/26\n48000080/29",
+            cidr_lines(&aggregate_subnets(
+                parse_ranges("48000000-4800003F\n48000080-48000087").unwrap()
+            ))
+        );
+
+        assert!(parse_ranges("48000000").is_err());
+    }
+
+    #[test]
+    fn merge_ranges_at_the_top() {
+        assert_eq!(
+            vec![range(0xFFFFFF00, 0xFFFFFFFF)],
+            merge_ranges(vec![
+                range(0xFFFFFFF0, 0xFFFFFFFF),
+                range(0xFFFFFF00, 0xFFFFFFFF)
+            ])
+        );
+        let top = DevaddrConstraint {
+            start_addr: hex_field::devaddr(u64::MAX - 1),
+            end_addr: hex_field::devaddr(u64::MAX),
+        };
+        assert_eq!(vec![top.clone()], merge_ranges(vec![top.clone(), top]));
+    }
+
     #[test]
     fn subnet_display() {
         assert_eq!(
@@ -0,0 +1,269 @@
+//! `proptest` and `arbitrary` support for the data model.
+//!
+//! Enabled with the `testing` feature. Generated values respect the
+//! invariants the CLI relies on: hex fields fit their width, devaddr ranges
+//! are ordered, and route ids are UUIDs.
+use crate::{
+    hex_field::HexField,
+    region::Region,
+    route::{Route, RouteId},
+    server::{FlowType, Gwmp, GwmpMap, Http, Port, Protocol, Server},
+    DevaddrRange, Eui,
+};
+use clap::ValueEnum;
+use proptest::{
+    arbitrary::{any, Arbitrary},
+    collection, option,
+    prelude::*,
+    sample,
+    strategy::BoxedStrategy,
+};
+
+fn hex_field_max(width: usize) -> u64 {
+    if width >= 16 {
+        u64::MAX
+    } else {
+        (1 << (width * 4)) - 1
+    }
+}
+
+fn uuid(bits: u128) -> RouteId {
+    RouteId::new_unchecked(format!(
+        "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+        bits >> 96,
+        (bits >> 80) & 0xffff,
+        (bits >> 64) & 0xffff,
+        (bits >> 48) & 0xffff,
+        bits & 0xffff_ffff_ffff
+    ))
+}
+
+impl<const WIDTH: usize> Arbitrary for HexField<WIDTH> {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_args: ()) -> Self::Strategy {
+        (0..=hex_field_max(WIDTH)).prop_map(HexField).boxed()
+    }
+}
+
+impl Arbitrary for RouteId {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_args: ()) -> Self::Strategy {
+        any::<u128>().prop_map(uuid).boxed()
+    }
+}
+
+impl Arbitrary for DevaddrRange {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_args: ()) -> Self::Strategy {
+        (any::<RouteId>(), any::<u32>(), any::<u32>())
+            .prop_map(|(route_id, a, b)| DevaddrRange {
+                route_id,
+                start_addr: a.min(b).into(),
+                end_addr: a.max(b).into(),
+            })
+            .boxed()
+    }
+}
+
+impl Arbitrary for Eui {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_args: ()) -> Self::Strategy {
+        (
+            any::<RouteId>(),
+            any::<HexField<16>>(),
+            any::<HexField<16>>(),
+        )
+            .prop_map(|(route_id, app_eui, dev_eui)| Eui {
+                route_id,
+                app_eui,
+                dev_eui,
+            })
+            .boxed()
+    }
+}
+
+fn region() -> impl Strategy<Value = Region> {
+    sample::select(Region::value_variants())
+}
+
+fn protocol() -> impl Strategy<Value = Protocol> {
+    let gwmp = collection::btree_map(region(), any::<Port>(), 0..4)
+        .prop_map(|mapping: GwmpMap| Protocol::Gwmp(Gwmp { mapping }));
+    let http = (
+        sample::select(FlowType::value_variants()),
+        any::<u32>(),
+        ".*",
+        ".*",
+    )
+        .prop_map(|(flow_type, dedupe_timeout, path, auth_header)| {
+            Protocol::Http(Http {
+                flow_type,
+                dedupe_timeout,
+                path,
+                auth_header,
+            })
+        });
+    prop_oneof![gwmp, http, Just(Protocol::PacketRouter)]
+}
+
+impl Arbitrary for Route {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_args: ()) -> Self::Strategy {
+        let server =
+            (".*", any::<Port>(), option::of(protocol())).prop_map(|(host, port, protocol)| {
+                Server {
+                    host,
+                    port,
+                    protocol,
+                }
+            });
+        (
+            any::<RouteId>(),
+            any::<HexField<6>>(),
+            any::<u64>(),
+            server,
+            any::<u32>(),
+            any::<bool>(),
+            any::<bool>(),
+        )
+            .prop_map(
+                |(id, net_id, oui, server, max_copies, active, locked)| Route {
+                    id,
+                    net_id,
+                    oui,
+                    server,
+                    max_copies,
+                    active,
+                    locked,
+                },
+            )
+            .boxed()
+    }
+}
+
+impl<'a, const WIDTH: usize> arbitrary::Arbitrary<'a> for HexField<WIDTH> {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(HexField(u.int_in_range(0..=hex_field_max(WIDTH))?))
+    }
+}
+
+impl<'a> arbitrary::Arbitrary<'a> for RouteId {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(uuid(u.arbitrary()?))
+    }
+}
+
+impl<'a> arbitrary::Arbitrary<'a> for DevaddrRange {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let (a, b): (u32, u32) = (u.arbitrary()?, u.arbitrary()?);
+        Ok(DevaddrRange {
+            route_id: u.arbitrary()?,
+            start_addr: a.min(b).into(),
+            end_addr: a.max(b).into(),
+        })
+    }
+}
+
+impl<'a> arbitrary::Arbitrary<'a> for Eui {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(Eui {
+            route_id: u.arbitrary()?,
+            app_eui: u.arbitrary()?,
+            dev_eui: u.arbitrary()?,
+        })
+    }
+}
+
+impl<'a> arbitrary::Arbitrary<'a> for Route {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let protocol = match u.int_in_range(0..=3)? {
+            0 => None,
+            1 => {
+                let mut mapping = GwmpMap::new();
+                for _ in 0..u.int_in_range(0..=3)? {
+                    mapping.insert(u.choose(Region::value_variants())?.clone(), u.arbitrary()?);
+                }
+                Some(Protocol::Gwmp(Gwmp { mapping }))
+            }
+            2 => Some(Protocol::Http(Http {
+                flow_type: u.choose(FlowType::value_variants())?.clone(),
+                dedupe_timeout: u.arbitrary()?,
+                path: u.arbitrary()?,
+                auth_header: u.arbitrary()?,
+            })),
+            _ => Some(Protocol::PacketRouter),
+        };
+        Ok(Route {
+            id: u.arbitrary()?,
+            net_id: u.arbitrary()?,
+            oui: u.arbitrary()?,
+            server: Server {
+                host: u.arbitrary()?,
+                port: u.arbitrary()?,
+                protocol,
+            },
+            max_copies: u.arbitrary()?,
+            active: u.arbitrary()?,
+            locked: u.arbitrary()?,
+        })
+    }
+}
+
+#[cfg(all(test, feature = "client"))]
+mod tests {
+    use crate::{hex_field::HexField, proto, route::Route, DevaddrRange, Eui};
+    use helium_proto::services::iot_config::RouteV1;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn hex_field_serde_round_trip(devaddr: HexField<8>, eui: HexField<16>, net_id: HexField<6>) {
+            let json = serde_json::to_string(&(devaddr, eui, net_id)).unwrap();
+            let parsed: (HexField<8>, HexField<16>, HexField<6>) = serde_json::from_str(&json).unwrap();
+            prop_assert_eq!((devaddr, eui, net_id), parsed);
+        }
+
+        #[test]
+        fn hex_field_parse_round_trip(eui: HexField<16>) {
+            prop_assert_eq!(eui, eui.to_string().parse::<HexField<16>>().unwrap());
+        }
+
+        #[test]
+        fn devaddr_range_round_trip(range: DevaddrRange) {
+            let json = serde_json::to_string(&range).unwrap();
+            prop_assert_eq!(&range, &serde_json::from_str::<DevaddrRange>(&json).unwrap());
+
+            let v1 = proto::DevaddrRangeV1::from(range.clone());
+            prop_assert_eq!(&range, &DevaddrRange::from(&v1));
+            prop_assert_eq!(range, DevaddrRange::from(v1));
+        }
+
+        #[test]
+        fn eui_round_trip(eui: Eui) {
+            let json = serde_json::to_string(&eui).unwrap();
+            prop_assert_eq!(&eui, &serde_json::from_str::<Eui>(&json).unwrap());
+
+            let v1 = proto::EuiPairV1::from(eui.clone());
+            prop_assert_eq!(&eui, &Eui::from(&v1));
+            prop_assert_eq!(eui, Eui::from(v1));
+        }
+
+        #[test]
+        fn route_round_trip(route: Route) {
+            let json = serde_json::to_string(&route).unwrap();
+            prop_assert_eq!(&route, &serde_json::from_str::<Route>(&json).unwrap());
+
+            prop_assert_eq!(route.clone(), Route::from(RouteV1::from(route)));
+        }
+    }
+}
@@ -0,0 +1,87 @@
+//! End devices from The Things Stack, as printed by `ttn-lw-cli end-devices
+//! list`, as EUI pairs.
+use crate::{hex_field::HexEui, route::RouteId, Eui, Result};
+use serde::Deserialize;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct EndDevice {
+    pub ids: EndDeviceIds,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct EndDeviceIds {
+    pub device_id: String,
+    pub dev_eui: Option<HexEui>,
+    /// `app_eui` in exports from before LoRaWAN 1.1.
+    #[serde(alias = "app_eui")]
+    pub join_eui: Option<HexEui>,
+}
+
+/// The list printed by `ttn-lw-cli`, or the `{"end_devices": [..]}` response
+/// of the API.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Export {
+    List(Vec<EndDevice>),
+    Response { end_devices: Vec<EndDevice> },
+}
+
+pub fn from_json(json: &str) -> Result<Vec<EndDevice>> {
+    let export: Export = serde_json::from_str(json)?;
+    Ok(match export {
+        Export::List(devices)
+        | Export::Response {
+            end_devices: devices,
+        } => devices,
+    })
+}
+
+/// EUI pairs of `devices` for `route_id`, and the ids of devices without a
+/// DevEUI or JoinEUI, like ABP devices.
+pub fn euis(route_id: &RouteId, devices: &[EndDevice]) -> Result<(Vec<Eui>, Vec<String>)> {
+    let mut euis = vec![];
+    let mut skipped = vec![];
+    for EndDevice { ids } in devices {
+        match (ids.join_eui, ids.dev_eui) {
+            (Some(join_eui), Some(dev_eui)) => {
+                euis.push(Eui::new(route_id.clone(), join_eui, dev_eui)?)
+            }
+            _ => skipped.push(ids.device_id.clone()),
+        }
+    }
+    Ok((euis, skipped))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{euis, from_json};
+    use crate::{hex_field, route::RouteId};
+
+    #[test]
+    fn tts_end_devices() {
+        let json = r#"[
+            {
+                "ids": {
+                    "device_id": "sensor-1",
+                    "application_ids": {"application_id": "app"},
+                    "dev_eui": "70B3D57ED0000001",
+                    "join_eui": "0000000000000002"
+                },
+                "created_at": "2023-01-01T00:00:00Z"
+            },
+            {"ids": {"device_id": "old", "dev_eui": "0000000000000003", "app_eui": "0000000000000004"}},
+            {"ids": {"device_id": "abp", "application_ids": {"application_id": "app"}}}
+        ]"#;
+        let route_id = RouteId::new_unchecked("route-one");
+        let devices = from_json(json).unwrap();
+        let (pairs, skipped) = euis(&route_id, &devices).unwrap();
+        assert_eq!(2, pairs.len());
+        assert_eq!(hex_field::eui(0x70B3D57ED0000001), pairs[0].dev_eui);
+        assert_eq!(hex_field::eui(2), pairs[0].app_eui);
+        assert_eq!(hex_field::eui(4), pairs[1].app_eui);
+        assert_eq!(vec!["abp".to_string()], skipped);
+
+        let response = r#"{"end_devices": [{"ids": {"device_id": "one"}}]}"#;
+        assert_eq!(1, from_json(response).unwrap().len());
+    }
+}
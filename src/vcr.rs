@@ -0,0 +1,586 @@
+//! Record and replay of gRPC interactions with the config service.
+//!
+//! `--vcr-record <FILE>` saves every request and response to a cassette file
+//! while talking to a live config service. `--vcr-replay <FILE>` answers from
+//! that file without connecting anywhere, and fails as soon as the cli sends
+//! a request that differs from the recorded one.
+//!
+//! Requests are compared byte for byte, signature included, so a replay only
+//! matches when it is signed with the keypair and `--timestamp` used for the
+//! recording.
+//!
+//! `--proto-out <FILE>` appends the raw response bodies to a file, as the
+//! gRPC length-prefixed frames they arrive in: a compression flag byte, a
+//! 4 byte big-endian length, then the encoded message.
+use crate::Result;
+use anyhow::{anyhow, Context};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use hyper::body::{Bytes, HttpBody};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    task::Poll,
+};
+use tonic::{
+    body::BoxBody,
+    codegen::{
+        http::{self, HeaderMap, HeaderName, HeaderValue},
+        BoxFuture, Service, StdError,
+    },
+    transport::Channel,
+};
+
+/// Tapes shared by every client of a command, so interactions are replayed
+/// in order no matter which client sends them. Kept until [`eject`].
+static TAPES: Mutex<Vec<(PathBuf, Arc<Mutex<Tape>>)>> = Mutex::new(Vec::new());
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Cassette {
+    pub interactions: Vec<Interaction>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Interaction {
+    /// gRPC method, `/helium.iot_config.route/list`.
+    pub path: String,
+    /// Base64 of the gRPC framed request body.
+    pub request: String,
+    pub headers: BTreeMap<String, String>,
+    /// Base64 of the gRPC framed response body.
+    pub response: String,
+    pub trailers: BTreeMap<String, String>,
+}
+
+impl Cassette {
+    pub fn read(path: &Path) -> Result<Self> {
+        let data = fs::read_to_string(path)
+            .with_context(|| format!("reading cassette {}", path.display()))?;
+        serde_json::from_str(&data).with_context(|| format!("parsing cassette {}", path.display()))
+    }
+
+    pub fn write(&self, path: &Path) -> Result {
+        fs::write(path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("writing cassette {}", path.display()))
+    }
+}
+
+impl Interaction {
+    fn into_response(self) -> Result<http::Response<hyper::Body>> {
+        Ok(respond(
+            header_map(&self.headers)?,
+            Bytes::from(STANDARD.decode(&self.response)?),
+            header_map(&self.trailers)?,
+        ))
+    }
+}
+
+fn respond(headers: HeaderMap, data: Bytes, trailers: HeaderMap) -> http::Response<hyper::Body> {
+    let mut response = http::Response::new(hyper::Body::empty());
+    *response.headers_mut() = headers;
+
+    let (mut sender, body) = hyper::Body::channel();
+    tokio::spawn(async move {
+        if sender.send_data(data).await.is_ok() {
+            let _ = sender.send_trailers(trailers).await;
+        }
+    });
+    *response.body_mut() = body;
+    response
+}
+
+/// Headers, body, and trailers of a response, once all of it has arrived.
+async fn read_response(
+    response: http::Response<hyper::Body>,
+) -> Result<(HeaderMap, Bytes, HeaderMap), StdError> {
+    let (parts, mut body) = response.into_parts();
+    let mut data = vec![];
+    while let Some(chunk) = body.data().await {
+        data.extend_from_slice(&chunk?);
+    }
+    let trailers = body.trailers().await?.unwrap_or_default();
+    Ok((parts.headers, data.into(), trailers))
+}
+
+/// Forget the tape of `path`, the next client records or replays it from
+/// the start. For running several commands in one process, each of them
+/// with its own recording.
+pub fn eject(path: &Path) {
+    TAPES
+        .lock()
+        .expect("vcr tapes lock")
+        .retain(|(tape_path, _)| tape_path != path);
+}
+
+struct Tape {
+    path: PathBuf,
+    recording: bool,
+    cassette: Cassette,
+    next: usize,
+}
+
+impl Tape {
+    /// The tape of `path`, a recording tape is never shared with a replaying
+    /// one.
+    fn shared(
+        path: &Path,
+        recording: bool,
+        load: impl FnOnce() -> Result<Cassette>,
+    ) -> Result<Arc<Mutex<Tape>>> {
+        let mut tapes = TAPES.lock().expect("vcr tapes lock");
+        if let Some((_, tape)) = tapes.iter().find(|(tape_path, tape)| {
+            tape_path == path && tape.lock().expect("vcr tape lock").recording == recording
+        }) {
+            return Ok(tape.clone());
+        }
+        tapes.retain(|(tape_path, _)| tape_path != path);
+        let tape = Arc::new(Mutex::new(Tape {
+            path: path.to_path_buf(),
+            recording,
+            cassette: load()?,
+            next: 0,
+        }));
+        tapes.push((path.to_path_buf(), tape.clone()));
+        Ok(tape)
+    }
+
+    fn record(&mut self, interaction: Interaction) -> Result {
+        self.cassette.interactions.push(interaction);
+        self.cassette.write(&self.path)
+    }
+
+    fn replay(&mut self, path: &str, request: &[u8]) -> Result<Interaction> {
+        let interaction = self.cassette.interactions.get(self.next).ok_or_else(|| {
+            anyhow!(
+                "{} has no recorded interaction left for {path}",
+                self.path.display()
+            )
+        })?;
+        if interaction.path != path {
+            return Err(anyhow!(
+                "{} expected a request to {}, the cli sent {path}",
+                self.path.display(),
+                interaction.path
+            ));
+        }
+        if interaction.request != STANDARD.encode(request) {
+            return Err(anyhow!(
+                "request to {path} differs from interaction {} of {}",
+                self.next,
+                self.path.display()
+            ));
+        }
+        self.next += 1;
+        Ok(interaction.clone())
+    }
+}
+
+/// What the config service clients send their requests through.
+///
+/// Passes requests to the config service, records them on the way, or
+/// replays them from a cassette without a connection.
+#[derive(Clone)]
+pub struct Transport {
+    channel: Option<Channel>,
+    tape: Option<Arc<Mutex<Tape>>>,
+    proto_out: Option<PathBuf>,
+}
+
+impl Transport {
+    pub fn live(channel: Channel) -> Self {
+        Self {
+            channel: Some(channel),
+            tape: None,
+            proto_out: None,
+        }
+    }
+
+    /// Record into `path`, replacing any previous recording.
+    ///
+    /// Clients of one command add to the same recording, a later command in
+    /// the same process replaces it only after [`eject`].
+    pub fn record(channel: Channel, path: &Path) -> Result<Self> {
+        Ok(Self {
+            channel: Some(channel),
+            tape: Some(Tape::shared(path, true, || Ok(Cassette::default()))?),
+            proto_out: None,
+        })
+    }
+
+    pub fn replay(path: &Path) -> Result<Self> {
+        Ok(Self {
+            channel: None,
+            tape: Some(Tape::shared(path, false, || Cassette::read(path))?),
+            proto_out: None,
+        })
+    }
+
+    /// Also append every response body to `path`.
+    pub fn with_proto_out(self, path: Option<PathBuf>) -> Self {
+        Self {
+            proto_out: path,
+            ..self
+        }
+    }
+}
+
+impl Service<http::Request<BoxBody>> for Transport {
+    type Response = http::Response<hyper::Body>;
+    type Error = StdError;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, cx: &mut std::task::Context<'_>) -> Poll<Result<(), StdError>> {
+        match &mut self.channel {
+            Some(channel) => channel.poll_ready(cx).map_err(Into::into),
+            None => Poll::Ready(Ok(())),
+        }
+    }
+
+    fn call(&mut self, request: http::Request<BoxBody>) -> Self::Future {
+        // The channel that was polled ready has to be the one that is called.
+        let channel = self.channel.as_mut().map(|channel| {
+            let clone = channel.clone();
+            std::mem::replace(channel, clone)
+        });
+
+        let response: Self::Future = match (channel, self.tape.clone()) {
+            (Some(mut channel), None) => Box::pin(async move { Ok(channel.call(request).await?) }),
+            (Some(channel), Some(tape)) => Box::pin(record(channel, tape, request)),
+            (None, Some(tape)) => Box::pin(replay(tape, request)),
+            (None, None) => unreachable!("transport without a channel or tape"),
+        };
+        match self.proto_out.clone() {
+            Some(path) => Box::pin(async move { dump_response(&path, response.await?).await }),
+            None => response,
+        }
+    }
+}
+
+async fn dump_response(
+    path: &Path,
+    response: http::Response<hyper::Body>,
+) -> Result<http::Response<hyper::Body>, StdError> {
+    let (headers, data, trailers) = read_response(response).await?;
+    fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(&data))
+        .with_context(|| format!("writing protobuf responses to {}", path.display()))?;
+    Ok(respond(headers, data, trailers))
+}
+
+async fn record(
+    mut channel: Channel,
+    tape: Arc<Mutex<Tape>>,
+    request: http::Request<BoxBody>,
+) -> Result<http::Response<hyper::Body>, StdError> {
+    let path = request.uri().path().to_string();
+    let (parts, body) = request.into_parts();
+    let request_body = hyper::body::to_bytes(body).await?;
+    let request = http::Request::from_parts(parts, boxed(request_body.clone()));
+
+    let (headers, response_body, trailers) = read_response(channel.call(request).await?).await?;
+
+    let interaction = Interaction {
+        path,
+        request: STANDARD.encode(&request_body),
+        headers: header_strings(&headers),
+        response: STANDARD.encode(&response_body),
+        trailers: header_strings(&trailers),
+    };
+    tape.lock()
+        .expect("vcr tape lock")
+        .record(interaction.clone())?;
+    Ok(interaction.into_response()?)
+}
+
+async fn replay(
+    tape: Arc<Mutex<Tape>>,
+    request: http::Request<BoxBody>,
+) -> Result<http::Response<hyper::Body>, StdError> {
+    let path = request.uri().path().to_string();
+    let request_body = hyper::body::to_bytes(request.into_body()).await?;
+    let interaction = tape
+        .lock()
+        .expect("vcr tape lock")
+        .replay(&path, &request_body)?;
+    Ok(interaction.into_response()?)
+}
+
+fn boxed(data: Bytes) -> BoxBody {
+    hyper::Body::from(data)
+        .map_err(|err| tonic::Status::internal(err.to_string()))
+        .boxed_unsync()
+}
+
+fn header_strings(headers: &HeaderMap) -> BTreeMap<String, String> {
+    headers
+        .iter()
+        .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+        .collect()
+}
+
+fn header_map(headers: &BTreeMap<String, String>) -> Result<HeaderMap> {
+    let mut map = HeaderMap::new();
+    for (name, value) in headers {
+        map.insert(
+            HeaderName::from_bytes(name.as_bytes())?,
+            HeaderValue::from_str(value)?,
+        );
+    }
+    Ok(map)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{eject, Cassette, Interaction, Transport};
+    use crate::{
+        client::{ConnectOpts, MsgSign},
+        cmds::{
+            env::generate_keypair, org::list_orgs, route::list_routes, GenerateKeypair, ListOrgs,
+            ListRoutes, PathBufKeypair,
+        },
+        proto::{OrgListResV1, OrgV1},
+        render::OutputFormat,
+        ExitCode,
+    };
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use helium_proto::{
+        services::iot_config::{org_client::OrgClient, OrgListReqV1, RouteListReqV1},
+        Message,
+    };
+    use std::{collections::BTreeMap, path::PathBuf};
+    use temp_dir::TempDir;
+    use tonic::transport::Endpoint;
+
+    /// `message` as the base64 of the gRPC frame it is sent in.
+    fn frame(message: impl Message) -> String {
+        let data = message.encode_to_vec();
+        let mut frame = vec![0u8];
+        frame.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        frame.extend_from_slice(&data);
+        STANDARD.encode(frame)
+    }
+
+    fn interaction(path: &str, request: String, response: String) -> Interaction {
+        Interaction {
+            path: path.to_string(),
+            request,
+            headers: BTreeMap::from([("content-type".to_string(), "application/grpc".to_string())]),
+            response,
+            trailers: BTreeMap::from([("grpc-status".to_string(), "0".to_string())]),
+        }
+    }
+
+    fn replaying(cassette: PathBuf) -> ConnectOpts {
+        ConnectOpts {
+            vcr_replay: Some(cassette),
+            ..ConnectOpts::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn replay_cassette() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.child("cassette.json");
+        // An empty message is a 5 byte frame: not compressed, 0 bytes long.
+        let empty_frame = STANDARD.encode([0u8; 5]);
+        Cassette {
+            interactions: vec![Interaction {
+                path: "/helium.iot_config.org/list".to_string(),
+                request: empty_frame.clone(),
+                headers: BTreeMap::from([(
+                    "content-type".to_string(),
+                    "application/grpc".to_string(),
+                )]),
+                response: empty_frame,
+                trailers: BTreeMap::from([("grpc-status".to_string(), "0".to_string())]),
+            }],
+        }
+        .write(&path)
+        .unwrap();
+
+        let mut client = OrgClient::new(Transport::replay(&path).unwrap());
+        let orgs = client.list(OrgListReqV1 {}).await.unwrap().into_inner();
+        assert!(orgs.orgs.is_empty());
+
+        // Every recorded interaction has been used.
+        assert!(client.list(OrgListReqV1 {}).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn proto_out() {
+        let dir = TempDir::new().unwrap();
+        let cassette = dir.child("cassette.json");
+        let proto_out = dir.child("responses.bin");
+        let empty_frame = STANDARD.encode([0u8; 5]);
+        Cassette {
+            interactions: vec![Interaction {
+                path: "/helium.iot_config.org/list".to_string(),
+                request: empty_frame.clone(),
+                headers: BTreeMap::from([(
+                    "content-type".to_string(),
+                    "application/grpc".to_string(),
+                )]),
+                response: empty_frame,
+                trailers: BTreeMap::from([("grpc-status".to_string(), "0".to_string())]),
+            }],
+        }
+        .write(&cassette)
+        .unwrap();
+
+        let transport = Transport::replay(&cassette)
+            .unwrap()
+            .with_proto_out(Some(proto_out.clone()));
+        OrgClient::new(transport)
+            .list(OrgListReqV1 {})
+            .await
+            .unwrap();
+        assert_eq!(vec![0u8; 5], std::fs::read(proto_out).unwrap());
+    }
+
+    #[tokio::test]
+    async fn replay_org_list_command() {
+        let dir = TempDir::new().unwrap();
+        let keypair = dir.child("keypair.bin");
+        generate_keypair(GenerateKeypair {
+            out_file: keypair.clone(),
+            commit: true,
+        })
+        .unwrap();
+        let owner = keypair.to_keypair().unwrap().public_key().to_vec();
+        let org = |oui| OrgV1 {
+            oui,
+            owner: owner.clone(),
+            payer: owner.clone(),
+            delegate_keys: vec![],
+            locked: false,
+        };
+        let cassette = dir.child("cassette.json");
+        Cassette {
+            interactions: vec![interaction(
+                "/helium.iot_config.org/list",
+                frame(OrgListReqV1 {}),
+                frame(OrgListResV1 {
+                    orgs: vec![org(1), org(7)],
+                }),
+            )],
+        }
+        .write(&cassette)
+        .unwrap();
+
+        let msg = list_orgs(ListOrgs {
+            ids: true,
+            fields: vec![],
+            owner: None,
+            offset: 1,
+            limit: None,
+            format: OutputFormat::Json,
+            template: None,
+            config_host: "http://localhost:50051".to_string(),
+            connection: replaying(cassette),
+        })
+        .await
+        .unwrap();
+        assert_eq!("7", msg.into_inner());
+    }
+
+    #[tokio::test]
+    async fn replay_signed_route_list_command() {
+        let dir = TempDir::new().unwrap();
+        let keypair = dir.child("keypair.bin");
+        generate_keypair(GenerateKeypair {
+            out_file: keypair.clone(),
+            commit: true,
+        })
+        .unwrap();
+        let timestamp = 1_700_000_000_000;
+        let mut request = RouteListReqV1 {
+            oui: 4,
+            timestamp,
+            signature: vec![],
+        };
+        request.signature = request.sign(&keypair.to_keypair().unwrap()).unwrap();
+        let cassette = dir.child("cassette.json");
+        Cassette {
+            interactions: vec![interaction(
+                "/helium.iot_config.route/list",
+                frame(request),
+                STANDARD.encode([0u8; 5]),
+            )],
+        }
+        .write(&cassette)
+        .unwrap();
+
+        let list = |timestamp| ListRoutes {
+            oui: 4,
+            ids: true,
+            fields: vec![],
+            sort_by: None,
+            filter: vec![],
+            with_counts: false,
+            format: OutputFormat::Json,
+            template: None,
+            keypair: keypair.clone(),
+            config_host: "http://localhost:50051".to_string(),
+            connection: ConnectOpts {
+                timestamp: Some(timestamp),
+                ..replaying(cassette.clone())
+            },
+            commit: false,
+        };
+
+        // Signed with another timestamp, the request differs from the recording.
+        let msg = list_routes(list(timestamp + 1)).await.unwrap();
+        assert!(msg.exit_code() != ExitCode::Success);
+
+        eject(&cassette);
+        let msg = list_routes(list(timestamp)).await.unwrap();
+        assert!(msg.exit_code() == ExitCode::Success);
+        assert_eq!("", msg.into_inner());
+    }
+
+    #[tokio::test]
+    async fn record_replaces_replayed_tape() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.child("cassette.json");
+        Cassette {
+            interactions: vec![interaction(
+                "/helium.iot_config.org/list",
+                STANDARD.encode([0u8; 5]),
+                STANDARD.encode([0u8; 5]),
+            )],
+        }
+        .write(&path)
+        .unwrap();
+
+        let replayed = Transport::replay(&path).unwrap();
+        let channel = Endpoint::from_static("http://127.0.0.1:1").connect_lazy();
+        let recorded = Transport::record(channel, &path).unwrap();
+        let interactions = |transport: &Transport| {
+            let tape = transport.tape.as_ref().unwrap();
+            tape.lock().unwrap().cassette.interactions.len()
+        };
+        assert_eq!(1, interactions(&replayed));
+        assert_eq!(0, interactions(&recorded));
+
+        // Until ejected, every client of a command shares the recording.
+        let channel = Endpoint::from_static("http://127.0.0.1:1").connect_lazy();
+        let shared = Transport::record(channel, &path).unwrap();
+        assert!(std::sync::Arc::ptr_eq(
+            recorded.tape.as_ref().unwrap(),
+            shared.tape.as_ref().unwrap()
+        ));
+        eject(&path);
+        let channel = Endpoint::from_static("http://127.0.0.1:1").connect_lazy();
+        let fresh = Transport::record(channel, &path).unwrap();
+        assert!(!std::sync::Arc::ptr_eq(
+            recorded.tape.as_ref().unwrap(),
+            fresh.tape.as_ref().unwrap()
+        ));
+    }
+}
@@ -3,7 +3,7 @@ use helium_config_service_cli::{
     client,
     cmds::{self, *},
     hex_field,
-    route::Route,
+    route::{Route, RouteId},
     OrgResponse, Result,
 };
 use helium_crypto::PublicKey;
@@ -47,12 +47,13 @@ pub async fn create_helium_org(
         devaddr_count,
         keypair: keypair_path,
         config_host: CONFIG_HOST.to_string(),
+        connection: Default::default(),
         commit: true,
     })
     .await?;
     info!("{out}");
 
-    let mut org_client = client::OrgClient::new(CONFIG_HOST).await?;
+    let mut org_client = client::OrgClient::new(CONFIG_HOST, &Default::default()).await?;
     let mut org_list = org_client.list().await?;
     // Put in creation order
     org_list.orgs.sort_by_key(|x| x.oui);
@@ -65,14 +66,22 @@ pub async fn create_helium_org(
 pub async fn ensure_no_routes(oui: u64, keypair_path: PathBuf) -> Result {
     let out = cmds::route::list_routes(ListRoutes {
         oui,
+        ids: false,
+        fields: vec![],
+        sort_by: None,
+        filter: vec![],
+        with_counts: false,
+        format: Default::default(),
+        template: None,
         keypair: keypair_path.clone(),
         config_host: CONFIG_HOST.to_string(),
+        connection: Default::default(),
         commit: false,
     })
     .await?;
     info!("{out}");
 
-    let mut route_client = client::RouteClient::new(CONFIG_HOST).await?;
+    let mut route_client = client::RouteClient::new(CONFIG_HOST, &Default::default()).await?;
     let route_list = route_client.list(oui, &keypair_path.to_keypair()?).await?;
     assert!(route_list.routes.is_empty());
     Ok(())
@@ -89,12 +98,13 @@ pub async fn create_empty_route(
         max_copies: 5,
         keypair: keypair_path.clone(),
         config_host: CONFIG_HOST.to_string(),
+        connection: Default::default(),
         commit: true,
     })
     .await?;
     info!("{out1}");
 
-    let mut route_client = client::RouteClient::new(CONFIG_HOST).await?;
+    let mut route_client = client::RouteClient::new(CONFIG_HOST, &Default::default()).await?;
     let route_list = route_client.list(oui, &keypair_path.to_keypair()?).await?;
     Ok(route_list
         .routes
@@ -103,32 +113,45 @@ pub async fn create_empty_route(
         .to_owned())
 }
 
-pub async fn get_route(route_id: &str, keypair_path: PathBuf) -> Result<Route> {
-    let mut route_client = client::RouteClient::new(CONFIG_HOST).await?;
+pub async fn get_route(route_id: &RouteId, keypair_path: PathBuf) -> Result<Route> {
+    let mut route_client = client::RouteClient::new(CONFIG_HOST, &Default::default()).await?;
     let route = route_client
         .get(route_id, &keypair_path.to_keypair()?)
         .await?;
     Ok(route)
 }
 
-pub async fn ensure_no_euis(route_id: &str, keypair_path: PathBuf) -> Result {
+pub async fn ensure_no_euis(route_id: &RouteId, keypair_path: PathBuf) -> Result {
     ensure_num_euis(0, route_id, keypair_path).await
 }
 
-pub async fn ensure_no_devaddrs(route_id: &str, keypair_path: PathBuf) -> Result {
+pub async fn ensure_no_devaddrs(route_id: &RouteId, keypair_path: PathBuf) -> Result {
     ensure_num_devaddrs(0, route_id, keypair_path).await
 }
 
-pub async fn ensure_num_euis(eui_count: usize, route_id: &str, keypair_path: PathBuf) -> Result {
+pub async fn ensure_num_euis(
+    eui_count: usize,
+    route_id: &RouteId,
+    keypair_path: PathBuf,
+) -> Result {
     let out = cmds::route::euis::list_euis(ListEuis {
-        route_id: route_id.to_string(),
+        route_id: route_id.clone(),
+        app_eui: None,
+        dev_eui: None,
+        wildcards_only: false,
+        count: true,
+        by_app_eui: false,
+        format: Default::default(),
+        template: None,
         keypair: keypair_path.clone(),
         config_host: CONFIG_HOST.to_string(),
+        connection: Default::default(),
     })
     .await?;
     info!("{out}");
+    assert_eq!(eui_count.to_string(), out.into_inner());
 
-    let mut eui_client = client::EuiClient::new(CONFIG_HOST).await?;
+    let mut eui_client = client::EuiClient::new(CONFIG_HOST, &Default::default()).await?;
     let euis = eui_client
         .get_euis(route_id, &keypair_path.to_keypair()?)
         .await?;
@@ -138,18 +161,24 @@ pub async fn ensure_num_euis(eui_count: usize, route_id: &str, keypair_path: Pat
 
 pub async fn ensure_num_devaddrs(
     devaddr_count: usize,
-    route_id: &str,
+    route_id: &RouteId,
     keypair_path: PathBuf,
 ) -> Result {
     let out = cmds::route::devaddrs::list_devaddrs(ListDevaddrs {
-        route_id: route_id.to_string(),
+        route_id: route_id.clone(),
+        gaps: false,
+        count: true,
+        format: Default::default(),
+        template: None,
         keypair: keypair_path.clone(),
         config_host: CONFIG_HOST.to_string(),
+        connection: Default::default(),
     })
     .await?;
     info!("{out}");
+    assert_eq!(devaddr_count.to_string(), out.into_inner());
 
-    let mut devaddr_client = client::DevaddrClient::new(CONFIG_HOST).await?;
+    let mut devaddr_client = client::DevaddrClient::new(CONFIG_HOST, &Default::default()).await?;
     let addrs = devaddr_client
         .get_devaddrs(route_id, &keypair_path.to_keypair()?)
         .await?;
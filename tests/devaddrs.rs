@@ -1,7 +1,9 @@
 use helium_config_service_cli::{
     client,
     cmds::{self, *},
-    hex_field, DevaddrRange, Result,
+    hex_field,
+    render::OutputFormat,
+    DevaddrRange, Result,
 };
 
 use temp_dir::TempDir;
@@ -17,7 +19,7 @@ async fn create_route_and_add_remove_devaddrs() -> Result {
     let keypair_path = working_dir.child("keypair.bin");
     let config_host = common::CONFIG_HOST.to_string();
 
-    let mut devaddr_client = client::DevaddrClient::new(&config_host).await?;
+    let mut devaddr_client = client::DevaddrClient::new(&config_host, &Default::default()).await?;
 
     // Generate keypair
     let public_key = common::generate_keypair(keypair_path.clone())?;
@@ -34,10 +36,15 @@ async fn create_route_and_add_remove_devaddrs() -> Result {
 
     // devaddr outside org constraint, should not add
     let out1 = cmds::route::devaddrs::add_devaddr(AddDevaddr {
-        start_addr: hex_field::devaddr(1),
-        end_addr: hex_field::devaddr(2),
+        start_addr: Some(hex_field::devaddr(1)),
+        end_addr: Some(hex_field::devaddr(2)),
+        range: vec![],
+        from_file: None,
+        count: None,
+        parallel: 1,
         route_id: route.id.clone(),
         config_host: config_host.clone(),
+        connection: Default::default(),
         keypair: keypair_path.clone(),
         commit: true,
     })
@@ -48,10 +55,15 @@ async fn create_route_and_add_remove_devaddrs() -> Result {
     // Construct a devaddr within the org contraint, add and remove
     let devaddr_range = constraint.start_addr.to_range(3);
     let out2 = cmds::route::devaddrs::add_devaddr(AddDevaddr {
-        start_addr: devaddr_range.start_addr,
-        end_addr: devaddr_range.end_addr,
+        start_addr: Some(devaddr_range.start_addr),
+        end_addr: Some(devaddr_range.end_addr),
+        range: vec![],
+        from_file: None,
+        count: None,
+        parallel: 1,
         route_id: route.id.clone(),
         config_host: config_host.clone(),
+        connection: Default::default(),
         keypair: keypair_path.clone(),
         commit: true,
     })
@@ -64,6 +76,7 @@ async fn create_route_and_add_remove_devaddrs() -> Result {
         end_addr: devaddr_range.end_addr,
         route_id: route.id.clone(),
         config_host: config_host.clone(),
+        connection: Default::default(),
         keypair: keypair_path.clone(),
         commit: true,
     })
@@ -88,8 +101,10 @@ async fn create_route_and_add_remove_devaddrs() -> Result {
     // Print subnets for visual inspection
     let out4 = cmds::route::devaddrs::subnet_mask(RouteSubnetMask {
         route_id: route.id.clone(),
+        format: OutputFormat::Json,
         keypair: keypair_path.clone(),
         config_host: config_host.clone(),
+        connection: Default::default(),
     })
     .await?;
     info!("4: {out4}");
@@ -98,6 +113,7 @@ async fn create_route_and_add_remove_devaddrs() -> Result {
         route_id: route.id.clone(),
         keypair: keypair_path.clone(),
         config_host: config_host.clone(),
+        connection: Default::default(),
         commit: true,
     })
     .await?;
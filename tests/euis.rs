@@ -30,10 +30,15 @@ async fn create_route_and_add_remove_euis() -> Result {
 
     // Add an EUI
     let out1 = cmds::route::euis::add_eui(AddEui {
-        dev_eui: hex_field::eui(1),
-        app_eui: hex_field::eui(2),
+        dev_eui: Some(hex_field::eui(1)),
+        app_eui: Some(hex_field::eui(2)),
+        pair: vec![],
+        from_file: None,
+        resume: false,
+        parallel: 1,
         route_id: route.id.clone(),
         config_host: config_host.clone(),
+        connection: Default::default(),
         keypair: keypair_path.clone(),
         commit: true,
     })
@@ -43,10 +48,13 @@ async fn create_route_and_add_remove_euis() -> Result {
 
     // Remove Eui
     let out2 = cmds::route::euis::remove_eui(RemoveEui {
-        dev_eui: hex_field::eui(1),
-        app_eui: hex_field::eui(2),
+        dev_eui: Some(hex_field::eui(1)),
+        app_eui: Some(hex_field::eui(2)),
+        from_file: None,
+        parallel: 1,
         route_id: route.id.clone(),
         config_host: config_host.clone(),
+        connection: Default::default(),
         keypair: keypair_path.clone(),
         commit: true,
     })
@@ -55,7 +63,7 @@ async fn create_route_and_add_remove_euis() -> Result {
     common::ensure_no_euis(&route.id, keypair_path.clone()).await?;
 
     // Add many Euis to delete
-    let mut eui_client = client::EuiClient::new(common::CONFIG_HOST).await?;
+    let mut eui_client = client::EuiClient::new(common::CONFIG_HOST, &Default::default()).await?;
     let mut euis = vec![];
     for e in 0..15 {
         euis.push(Eui::new(
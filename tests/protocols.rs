@@ -28,8 +28,10 @@ async fn create_route_and_update_server() -> Result {
     let route = common::create_empty_route(net_id, org_res.org.oui, keypair_path.clone()).await?;
     let out1 = cmds::route::get_route(GetRoute {
         route_id: route.id.clone(),
+        summary: false,
         keypair: keypair_path.clone(),
         config_host: config_host.clone(),
+        connection: Default::default(),
     })
     .await?;
     info!("{out1}");
@@ -38,8 +40,10 @@ async fn create_route_and_update_server() -> Result {
     // Set packet-router protocol
     let out2 = cmds::route::update_packet_router(UpdatePacketRouter {
         route_id: route.id.clone(),
+        gwmp_history: working_dir.child("gwmp_history.json"),
         keypair: keypair_path.clone(),
         config_host: config_host.clone(),
+        connection: Default::default(),
         commit: true,
     })
     .await?;
@@ -50,11 +54,14 @@ async fn create_route_and_update_server() -> Result {
     // Set Http Protocol
     let out3 = cmds::route::update_http(UpdateHttp {
         route_id: route.id.clone(),
-        dedupe_timeout: 234,
-        path: "path".to_string(),
+        flow_type: Some(server::FlowType::Async),
+        dedupe_timeout: Some(234),
+        path: Some("path".to_string()),
         auth_header: Some("test-header".to_string()),
+        gwmp_history: working_dir.child("gwmp_history.json"),
         keypair: keypair_path.clone(),
         config_host: config_host.clone(),
+        connection: Default::default(),
         commit: true,
     })
     .await?;
@@ -81,8 +88,10 @@ async fn create_route_and_update_server() -> Result {
         route_id: route.id.clone(),
         region: helium_config_service_cli::region::Region::As9231a,
         region_port: 9001,
+        gwmp_history: working_dir.child("gwmp_history.json"),
         keypair: keypair_path.clone(),
         config_host: config_host.clone(),
+        connection: Default::default(),
         commit: true,
     })
     .await?;
@@ -99,8 +108,10 @@ async fn create_route_and_update_server() -> Result {
         route_id: route.id.clone(),
         region: helium_config_service_cli::region::Region::Eu433,
         region_port: 9002,
+        gwmp_history: working_dir.child("gwmp_history.json"),
         keypair: keypair_path.clone(),
         config_host: config_host.clone(),
+        connection: Default::default(),
         commit: true,
     })
     .await?;
@@ -118,6 +129,7 @@ async fn create_route_and_update_server() -> Result {
         region: helium_config_service_cli::region::Region::As9231a,
         keypair: keypair_path.clone(),
         config_host: config_host.clone(),
+        connection: Default::default(),
         commit: true,
     })
     .await?;
@@ -17,7 +17,7 @@ async fn create_org_and_add_remove_session_key_filtesr() -> Result {
     let keypair_path = working_dir.child("keypair.bin");
     let config_host = common::CONFIG_HOST.to_string();
 
-    let mut skf_client = client::SkfClient::new(&config_host).await?;
+    let mut skf_client = client::SkfClient::new(&config_host, &Default::default()).await?;
 
     // Generate keypair
     let public_key = common::generate_keypair(keypair_path.clone())?;
@@ -29,8 +29,13 @@ async fn create_org_and_add_remove_session_key_filtesr() -> Result {
     // List session key filters, there are none
     let out = cmds::session_key_filter::list_filters(ListFilters {
         oui: org_res.org.oui,
+        count: false,
+        by_devaddr: false,
+        format: Default::default(),
+        template: None,
         keypair: keypair_path.clone(),
         config_host: config_host.clone(),
+        connection: Default::default(),
     })
     .await?;
     info!("empty list: {out}");
@@ -45,6 +50,7 @@ async fn create_org_and_add_remove_session_key_filtesr() -> Result {
         devaddr: hex_field::devaddr(1),
         session_key: "key-one".to_string(),
         config_host: config_host.clone(),
+        connection: Default::default(),
         keypair: keypair_path.clone(),
         commit: true,
     })
@@ -56,17 +62,39 @@ async fn create_org_and_add_remove_session_key_filtesr() -> Result {
         devaddr: hex_field::devaddr(2),
         session_key: "key-two".to_string(),
         config_host: config_host.clone(),
+        connection: Default::default(),
         keypair: keypair_path.clone(),
         commit: true,
     })
     .await?;
     info!("add 2: {out}");
 
+    // Adding the same filter again is skipped
+    let out = cmds::session_key_filter::add_filter(AddFilter {
+        oui: org_res.org.oui,
+        devaddr: hex_field::devaddr(1),
+        session_key: "key-one".to_string(),
+        config_host: config_host.clone(),
+        connection: Default::default(),
+        keypair: keypair_path.clone(),
+        commit: true,
+    })
+    .await?;
+    info!("add 1 again: {out}");
+    assert!(out
+        .to_string()
+        .contains("added: 0, already-present: 1, failed: 0"));
+
     // List session key filters again, expecting 2
     let out = cmds::session_key_filter::list_filters(ListFilters {
         oui: org_res.org.oui,
+        count: false,
+        by_devaddr: false,
+        format: Default::default(),
+        template: None,
         keypair: keypair_path.clone(),
         config_host: config_host.clone(),
+        connection: Default::default(),
     })
     .await?;
     info!("list of 2: {out}");
@@ -81,6 +109,7 @@ async fn create_org_and_add_remove_session_key_filtesr() -> Result {
         devaddr: hex_field::devaddr(1),
         keypair: keypair_path.clone(),
         config_host: config_host.clone(),
+        connection: Default::default(),
     })
     .await?;
     info!("get, list of 1: {out}");
@@ -99,6 +128,7 @@ async fn create_org_and_add_remove_session_key_filtesr() -> Result {
         devaddr: hex_field::devaddr(1),
         session_key: "key-one".to_string(),
         config_host: config_host.clone(),
+        connection: Default::default(),
         keypair: keypair_path.clone(),
         commit: true,
     })
@@ -110,6 +140,7 @@ async fn create_org_and_add_remove_session_key_filtesr() -> Result {
         devaddr: hex_field::devaddr(2),
         session_key: "key-two".to_string(),
         config_host: config_host.clone(),
+        connection: Default::default(),
         keypair: keypair_path.clone(),
         commit: true,
     })
@@ -119,8 +150,13 @@ async fn create_org_and_add_remove_session_key_filtesr() -> Result {
     // List session key filters, expecting none
     let out = cmds::session_key_filter::list_filters(ListFilters {
         oui: org_res.org.oui,
+        count: false,
+        by_devaddr: false,
+        format: Default::default(),
+        template: None,
         keypair: keypair_path.clone(),
         config_host: config_host.clone(),
+        connection: Default::default(),
     })
     .await?;
     info!("empty list: {out}");
@@ -27,8 +27,10 @@ async fn create_route_and_update_server() -> Result {
     let route = common::create_empty_route(net_id, org_res.org.oui, keypair_path.clone()).await?;
     let out = cmds::route::get_route(GetRoute {
         route_id: route.id.clone(),
+        summary: false,
         keypair: keypair_path.clone(),
         config_host: config_host.clone(),
+        connection: Default::default(),
     })
     .await?;
     info!("{out}");
@@ -39,6 +41,7 @@ async fn create_route_and_update_server() -> Result {
         route_id: route.id.clone(),
         keypair: keypair_path.clone(),
         config_host: config_host.clone(),
+        connection: Default::default(),
         commit: true,
     })
     .await?;
@@ -51,6 +54,7 @@ async fn create_route_and_update_server() -> Result {
         route_id: route.id.clone(),
         keypair: keypair_path.clone(),
         config_host: config_host.clone(),
+        connection: Default::default(),
         commit: true,
     })
     .await?;